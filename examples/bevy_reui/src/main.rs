@@ -1,5 +1,5 @@
 use bevy::{prelude::*, window::PrimaryWindow};
-use reui::{plugin::Recorder, Color, FillRule, Offset, Path, Rect, Rounding, Transform};
+use reui::{plugin::Recorder, BlendMode, Color, FillRule, Offset, Path, Rect, Rounding, Transform};
 
 fn main() {
     App::new()
@@ -28,6 +28,13 @@ fn draw(mut query: Query<&mut Recorder>, windows: Query<&Window, With<PrimaryWin
         path.rrect(rect, Rounding::same(8.0));
 
         recorder.clear();
-        recorder.fill(&path, color, transform, FillRule::NonZero, true);
+        recorder.fill(
+            &path,
+            color,
+            transform,
+            FillRule::NonZero,
+            BlendMode::SrcOver,
+            true,
+        );
     }
 }
@@ -14,9 +14,9 @@ fn offset_color(
     if delta != 0 {
         let offset = delta as f32 / 255.0;
         Color {
-            red: (red + offset).max(0.0).min(1.0),
-            green: (green + offset).max(0.0).min(1.0),
-            blue: (blue + offset).max(0.0).min(1.0),
+            red: (red + offset).clamp(0.0, 1.0),
+            green: (green + offset).clamp(0.0, 1.0),
+            blue: (blue + offset).clamp(0.0, 1.0),
             alpha,
         }
     } else {
@@ -56,6 +56,7 @@ pub struct WidgetTheme {
     pub background: u32,
     pub active: u32,
 
+    #[allow(dead_code)]
     pub color: u32,
     pub radius: f32,
     /*
@@ -1,10 +1,30 @@
 use reui::{
-    BoxGradient, Canvas, Color, FillRule, LineCap, LineJoin, LinearGradient, Offset, Path,
-    RadialGradient, Rect, Rounding, Solidity, Stroke, Transform,
+    BlendMode, BoxGradient, Canvas, Color, FillRule, FontId, Gradient, ImagePattern, LineCap,
+    LineJoin, LinearGradient, Offset, Paragraph, ParagraphStyle, Path, RadialGradient, Rect,
+    Rounding, Solidity, Spread, Stroke, SweepGradient, Transform,
 };
 use std::f32::consts::TAU;
 
-pub fn render_demo(ctx: &mut Canvas, mouse: Offset, wsize: Offset, time: f32, blowup: bool) {
+#[derive(Clone, Copy)]
+pub struct Fonts {
+    pub regular: FontId,
+    pub bold: FontId,
+    pub icons: FontId,
+    pub emoji: FontId,
+}
+
+const ICON_SEARCH: &str = "\u{1F50D}";
+const ICON_CIRCLED_CROSS: &str = "\u{2716}";
+
+pub fn render_demo(
+    ctx: &mut Canvas,
+    fonts: Fonts,
+    image: u32,
+    mouse: Offset,
+    wsize: Offset,
+    time: f32,
+    blowup: bool,
+) {
     let (width, height) = wsize.into();
 
     {
@@ -23,6 +43,8 @@ pub fn render_demo(ctx: &mut Canvas, mouse: Offset, wsize: Offset, time: f32, bl
 
         draw_rrect(ctx, 400.0, 15.0);
         draw_fills(ctx, 450.0, 0.0);
+        draw_patterns(ctx, image, 390.0, 270.0, time);
+        draw_gradients(ctx, 560.0, 270.0);
 
         draw_blending(ctx, 260.0, 5.0, 85.0, 40.0);
         draw_palette(ctx, Offset::new(10.0, 10.0));
@@ -47,7 +69,7 @@ pub fn render_demo(ctx: &mut Canvas, mouse: Offset, wsize: Offset, time: f32, bl
 
         // Widgets
         let win = Rect::from_ltwh(50.0, 50.0, 300.0, 400.0);
-        draw_window(ctx, win, |ctx, window| {
+        draw_window(ctx, fonts, "Widgets `n Stuff", win, |ctx, window| {
             let window = window.deflate(10.0);
             let width = window.dx();
 
@@ -55,21 +77,33 @@ pub fn render_demo(ctx: &mut Canvas, mouse: Offset, wsize: Offset, time: f32, bl
             let line_height = 40.0;
 
             let mut offset = window.min;
-            draw_search_box(ctx, Rect::from_size(width, item_h).translate(offset));
+            draw_search_box(
+                ctx,
+                fonts,
+                "Search",
+                Rect::from_size(width, item_h).translate(offset),
+            );
             offset.y += line_height;
             draw_drop_down(ctx, Rect::from_size(width, item_h).translate(offset));
             offset.y += line_height;
 
             // Form
-            draw_edit_box(ctx, Rect::from_size(width, item_h).translate(offset));
+            let rect = Rect::from_size(width, item_h).translate(offset);
+            draw_edit_box(ctx, fonts, "Email \u{1F4E7}", rect, mouse);
             offset.y += line_height;
             offset.y += line_height;
             {
                 let mut offset = offset;
                 let item = Rect::from_size(width / 2.0, item_h);
-                draw_checkbox(ctx, item.translate(offset).deflate(3.0));
+                draw_checkbox(
+                    ctx,
+                    fonts,
+                    "Remember me",
+                    item.translate(offset).deflate(3.0),
+                );
                 offset.x += width / 2.0;
-                draw_edit_box(ctx, item.translate(offset).deflate(3.0));
+                let rect = item.translate(offset).deflate(3.0);
+                draw_edit_box(ctx, fonts, "Password", rect, mouse);
             }
             offset.y += line_height;
 
@@ -79,18 +113,37 @@ pub fn render_demo(ctx: &mut Canvas, mouse: Offset, wsize: Offset, time: f32, bl
                 let item = Rect::from_size(width / 2.0, item_h);
                 draw_slider(ctx, 0.4, item.translate(offset).deflate(3.0));
                 offset.x += width / 2.0;
-                draw_edit_box_num(ctx, item.translate(offset).deflate(3.0));
+                let rect = item.translate(offset).deflate(3.0);
+                draw_edit_box_num(ctx, fonts, "123.00", "px", rect);
             }
 
             offset.y = window.max.y - item_h;
             {
                 let mut offset = offset;
                 let btn = Rect::from_size(width / 3.0, item_h);
-                draw_button(ctx, btn.translate(offset).deflate(3.0), 0xFF_801008);
+                draw_button(
+                    ctx,
+                    fonts,
+                    "Delete",
+                    btn.translate(offset).deflate(3.0),
+                    0xFF_801008,
+                );
                 offset.x += width / 3.0;
-                draw_button(ctx, btn.translate(offset).deflate(3.0), 0x00_000000);
+                draw_button(
+                    ctx,
+                    fonts,
+                    "Cancel",
+                    btn.translate(offset).deflate(3.0),
+                    0x00_000000,
+                );
                 offset.x += width / 3.0;
-                draw_button(ctx, btn.translate(offset).deflate(3.0), 0xFF_006080);
+                draw_button(
+                    ctx,
+                    fonts,
+                    "Sign in",
+                    btn.translate(offset).deflate(3.0),
+                    0xFF_006080,
+                );
             }
         });
     }
@@ -121,7 +174,7 @@ pub fn render_demo(ctx: &mut Canvas, mouse: Offset, wsize: Offset, time: f32, bl
     }
 }
 
-fn draw_blending(ctx: &mut Canvas, x: f32, y: f32, width: f32, height: f32) {
+pub fn draw_blending(ctx: &mut Canvas, x: f32, y: f32, width: f32, height: f32) {
     // blending test
 
     let bg_paint = Color::bgra(0xFF_FFFFFF);
@@ -155,7 +208,7 @@ fn draw_blending(ctx: &mut Canvas, x: f32, y: f32, width: f32, height: f32) {
     ctx.fill_rect(rect.shift(x, y + h * 8.0), hb[1]);
 }
 
-fn draw_palette(ctx: &mut Canvas, offset: Offset) {
+pub fn draw_palette(ctx: &mut Canvas, offset: Offset) {
     let colors: &[u32] = &[
         0xFF_001F3F,
         0xFF_0074D9,
@@ -210,7 +263,13 @@ fn draw_palette(ctx: &mut Canvas, offset: Offset) {
     }
 }
 
-pub fn draw_window(ctx: &mut Canvas, bounds: Rect, content: impl FnOnce(&mut Canvas, Rect)) {
+pub fn draw_window(
+    ctx: &mut Canvas,
+    fonts: Fonts,
+    title: &str,
+    bounds: Rect,
+    content: impl FnOnce(&mut Canvas, Rect),
+) {
     let header_bg = Color::bgra(0xFC_3B3F41);
     let body_bg = Color::bgra(0xFC_47484B);
 
@@ -249,6 +308,9 @@ pub fn draw_window(ctx: &mut Canvas, bounds: Rect, content: impl FnOnce(&mut Can
         ctx.fill_circle(center + off * 0.0, radius, btn_r);
         ctx.fill_circle(center + off * 1.0, radius, btn_y);
         ctx.fill_circle(center + off * 2.0, radius, btn_g);
+
+        let position = Offset::new(left + header_height * 3.0, top + 14.0);
+        ctx.fill_text(title, position, fonts.bold, 14.0, Color::bgra(0xA0_DCDCDC));
     }
 
     // Drop shadow
@@ -268,7 +330,7 @@ pub fn draw_window(ctx: &mut Canvas, bounds: Rect, content: impl FnOnce(&mut Can
     }
 }
 
-pub fn draw_search_box(ctx: &mut Canvas, rect: Rect) {
+pub fn draw_search_box(ctx: &mut Canvas, fonts: Fonts, text: &str, rect: Rect) {
     let [left, top, width, height] = rect.to_xywh();
 
     let corner_radius = Rounding::same(height / 2.0 - 1.0);
@@ -281,9 +343,57 @@ pub fn draw_search_box(ctx: &mut Canvas, rect: Rect) {
         Color::bgra(0x60_000000),
     );
     ctx.fill_rrect(rect, corner_radius, paint);
+
+    let cy = top + height * 0.5;
+
+    let size = height * 1.3;
+    let icon = Offset::new(left + height * 0.55, cy);
+    draw_centered_text(ctx, ICON_SEARCH, icon, fonts.icons, size, 0x40_FFFFFF);
+
+    let size = 17.0;
+    let baseline = centered_baseline(ctx, fonts.regular, size, cy);
+    let position = Offset::new(left + height * 1.05, baseline);
+    ctx.fill_text(
+        text,
+        position,
+        fonts.regular,
+        size,
+        Color::bgra(0x20_FFFFFF),
+    );
+
+    let size = height * 1.3;
+    let icon = Offset::new(left + width - height * 0.55, cy);
+    draw_centered_text(
+        ctx,
+        ICON_CIRCLED_CROSS,
+        icon,
+        fonts.icons,
+        size,
+        0x20_FFFFFF,
+    );
+}
+
+/// Returns the baseline that centers a line of text around `cy`.
+fn centered_baseline(ctx: &Canvas, font: FontId, size: f32, cy: f32) -> f32 {
+    let metrics = ctx.measure_text("", font, size);
+    cy + (metrics.ascent + metrics.descent) * 0.5
+}
+
+fn draw_centered_text(
+    ctx: &mut Canvas,
+    text: &str,
+    center: Offset,
+    font: FontId,
+    size: f32,
+    color: u32,
+) {
+    let metrics = ctx.measure_text(text, font, size);
+    let baseline = centered_baseline(ctx, font, size, center.y);
+    let position = Offset::new(center.x - metrics.advance * 0.5, baseline);
+    ctx.fill_text(text, position, font, size, Color::bgra(color));
 }
 
-pub fn draw_button(ctx: &mut Canvas, rr: Rect, col: u32) {
+pub fn draw_button(ctx: &mut Canvas, fonts: Fonts, text: &str, rr: Rect, col: u32) {
     let [x, y, w, h] = rr.to_xywh();
 
     let corner_radius = 4.0;
@@ -305,9 +415,12 @@ pub fn draw_button(ctx: &mut Canvas, rr: Rect, col: u32) {
     let radius = Rounding::same(corner_radius - 0.5);
     let rrect = Rect::from_ltwh(x + 0.5, y + 0.5, w - 1.0, h - 1.0);
     ctx.stroke_rrect(rrect, radius, Color::bgra(0x30_000000), Stroke::default());
+
+    let position = Offset::new(x + 10.0, y + h * 0.5 + 5.0);
+    ctx.fill_text(text, position, fonts.bold, 15.0, Color::bgra(0xA0_FFFFFF));
 }
 
-pub fn draw_checkbox(ctx: &mut Canvas, rr: Rect) {
+pub fn draw_checkbox(ctx: &mut Canvas, fonts: Fonts, text: &str, rr: Rect) {
     let [x, y, _, h] = rr.to_xywh();
 
     let radius = Rounding::same(3.0);
@@ -320,6 +433,15 @@ pub fn draw_checkbox(ctx: &mut Canvas, rr: Rect) {
         Color::bgra(0x60_000000),
     );
     ctx.fill_rrect(rect, radius, paint);
+
+    let position = Offset::new(x + 28.0, y + h * 0.5 + 5.0);
+    ctx.fill_text(
+        text,
+        position,
+        fonts.regular,
+        14.0,
+        Color::bgra(0xA0_FFFFFF),
+    );
 }
 
 pub fn draw_drop_down(ctx: &mut Canvas, bounds: Rect) {
@@ -605,12 +727,53 @@ fn draw_edit_box_base(ctx: &mut Canvas, rr: Rect) {
     ctx.stroke_rrect(rect, radius, Color::bgra(0x30_000000), Stroke::default());
 }
 
-pub fn draw_edit_box(ctx: &mut Canvas, rr: Rect) {
+pub fn draw_edit_box(ctx: &mut Canvas, fonts: Fonts, text: &str, rr: Rect, mouse: Offset) {
     draw_edit_box_base(ctx, rr);
+
+    let [left, top, _, height] = rr.to_xywh();
+
+    let style = ParagraphStyle::new(fonts.regular, 17.0).fallback(fonts.emoji);
+    let paragraph = Paragraph::new(ctx.fonts(), text, &style);
+    let origin = Offset::new(left + height * 0.3, top + (height - paragraph.height) * 0.5);
+
+    // Select up to the hovered caret position.
+    if rr.contains(mouse) {
+        let caret = paragraph.hit_test(mouse - origin);
+        for rect in paragraph.selection_rects(0..caret) {
+            ctx.fill_rect(rect.translate(origin), Color::bgra(0x40_00C0FF));
+        }
+        let rect = paragraph.caret_rect(caret).translate(origin);
+        ctx.fill_rect(rect.inflate(0.5), Color::bgra(0xC0_FFFFFF));
+    }
+
+    ctx.fill_paragraph(&paragraph, origin, Color::bgra(0x40_FFFFFF));
 }
 
-pub fn draw_edit_box_num(ctx: &mut Canvas, rr: Rect) {
+pub fn draw_edit_box_num(ctx: &mut Canvas, fonts: Fonts, text: &str, units: &str, rr: Rect) {
     draw_edit_box_base(ctx, rr);
+
+    let [left, top, width, height] = rr.to_xywh();
+    let baseline = centered_baseline(ctx, fonts.regular, 15.0, top + height * 0.5);
+
+    let units_width = ctx.measure_text(units, fonts.regular, 15.0).advance;
+    let position = Offset::new(left + width - height * 0.3 - units_width, baseline);
+    ctx.fill_text(
+        units,
+        position,
+        fonts.regular,
+        15.0,
+        Color::bgra(0x40_FFFFFF),
+    );
+
+    let text_width = ctx.measure_text(text, fonts.regular, 17.0).advance;
+    let position = Offset::new(position.x - height * 0.5 * 0.5 - text_width, baseline);
+    ctx.fill_text(
+        text,
+        position,
+        fonts.regular,
+        17.0,
+        Color::bgra(0x80_FFFFFF),
+    );
 }
 
 pub fn draw_slider(ctx: &mut Canvas, pos: f32, rect: Rect) {
@@ -682,31 +845,17 @@ pub fn draw_colorwheel(ctx: &mut Canvas, rr: Rect, time: f32) {
     let cy = y + h * 0.5;
     let r1 = if w < h { w } else { h } * 0.5 - 5.0;
     let r0 = r1 - 20.0;
-    let aeps = 0.5 / r1; // half a pixel arc length in radians (2pi cancels out).
 
-    let count = 12;
+    let stops: Vec<_> = (0..=6)
+        .map(|i| (i as f32 / 6.0, Color::hsla(i as f32 * 60.0, 1.0, 0.55, 1.0)))
+        .collect();
+    let paint = SweepGradient::new([cx, cy], 0.0, TAU, Color::WHITE, Color::WHITE).stops(&stops);
+
     let mut path = Path::new();
-    for i in 0..count {
-        let a0 = (i as f32) * TAU / count as f32 - aeps;
-        let a1 = ((i as f32) + 1.0) * TAU / count as f32 + aeps;
-        let ax = cx + a0.cos() * (r0 + r1) * 0.5;
-        let ay = cy + a0.sin() * (r0 + r1) * 0.5;
-        let bx = cx + a1.cos() * (r0 + r1) * 0.5;
-        let by = cy + a1.sin() * (r0 + r1) * 0.5;
-
-        let center = Offset::new(cx, cy);
-
-        path.clear();
-        path.arc(center, r0, a0, a1, Solidity::Hole);
-        path.arc(center, r1, a1, a0, Solidity::Solid);
-        path.close();
-
-        let inner_color = Color::hsla(a0.to_degrees(), 1.0, 0.55, 1.0);
-        let outer_color = Color::hsla(a1.to_degrees(), 1.0, 0.55, 1.0);
-
-        let paint = LinearGradient::new([ax, ay], [bx, by], inner_color, outer_color);
-        ctx.fill_path(&path, paint, FillRule::NonZero);
-    }
+    path.circle([cx, cy].into(), r1);
+    path.circle([cx, cy].into(), r0);
+    path.solidity(Solidity::Hole);
+    ctx.fill_path(&path, paint, FillRule::NonZero);
 
     path.clear();
     path.circle([cx, cy].into(), r0 - 0.5);
@@ -796,7 +945,7 @@ pub fn draw_colorwheel(ctx: &mut Canvas, rr: Rect, time: f32) {
     ctx.restore();
 }
 
-fn draw_rrect(canvas: &mut Canvas, x: f32, y: f32) {
+pub fn draw_rrect(canvas: &mut Canvas, x: f32, y: f32) {
     canvas.save();
     canvas.push_translate(x, y);
 
@@ -825,7 +974,80 @@ fn draw_rrect(canvas: &mut Canvas, x: f32, y: f32) {
     canvas.restore();
 }
 
-fn draw_fills(canvas: &mut Canvas, x: f32, y: f32) {
+pub fn draw_patterns(canvas: &mut Canvas, image: u32, x: f32, y: f32, time: f32) {
+    canvas.save();
+    canvas.push_translate(x, y);
+
+    // Avatar in a rounded rect
+    let rect = Rect::from_size(60.0, 60.0);
+    let pattern = ImagePattern::new(image, [0.0, 0.0], [60.0, 60.0], 0.0, 1.0);
+    canvas.fill_rrect(rect, Rounding::same(8.0), pattern);
+    canvas.stroke_rrect(
+        rect,
+        Rounding::same(8.0),
+        Color::bgra(0x40_000000),
+        Stroke::width(1.0),
+    );
+
+    // Texture rotating around the center of a circle
+    let center = Offset::new(110.0, 30.0);
+    let angle = time * 0.5;
+    let origin = center + Transform::rotate(angle).apply(Offset::new(-30.0, -30.0));
+    let pattern = ImagePattern::new(image, origin.into(), [60.0, 60.0], angle, 0.8);
+    canvas.fill_circle(center, 30.0, pattern);
+
+    // Marching ants selection
+    let marquee = Rect::from_center(center, 66.0, 66.0);
+    let stroke = Stroke::width(1.0).dash(&[4.0, 4.0], time * -8.0);
+    canvas.stroke_rect(marquee, Color::bgra(0xC0_FFFFFF), stroke);
+
+    // Textured stroke
+    let pattern = ImagePattern::new(image, [0.0, 70.0], [40.0, 40.0], 0.0, 1.0);
+    canvas.stroke(pattern, Stroke::width(8.0), |path| {
+        path.move_to(Offset::new(5.0, 95.0));
+        path.cubic_to(
+            Offset::new(40.0, 60.0),
+            Offset::new(100.0, 130.0),
+            Offset::new(150.0, 80.0),
+        );
+    });
+
+    canvas.restore();
+}
+
+pub fn draw_gradients(canvas: &mut Canvas, x: f32, y: f32) {
+    canvas.save();
+    canvas.push_translate(x, y);
+
+    let stops = [
+        (0.0, Color::bgra(0xFF_F44336)),
+        (0.25, Color::bgra(0xFF_FFC107)),
+        (0.5, Color::bgra(0xFF_4CAF50)),
+        (1.0, Color::bgra(0xFF_2196F3)),
+    ];
+
+    let rect = Rect::from_size(60.0, 60.0);
+    let paint =
+        LinearGradient::new([0.0, 0.0], [60.0, 60.0], Color::BLACK, Color::WHITE).stops(&stops);
+    canvas.fill_rrect(rect, Rounding::same(8.0), paint);
+
+    let rect = rect.translate(Offset::new(70.0, 0.0));
+    let paint = RadialGradient::new([100.0, 30.0], 0.0, 12.0, Color::BLACK, Color::WHITE)
+        .stops(&stops)
+        .spread(Spread::Reflect);
+    canvas.fill_rrect(rect, Rounding::same(8.0), paint);
+
+    // Group opacity, overlapping shapes do not show through each other
+    let rect = rect.translate(Offset::new(70.0, 0.0));
+    canvas.save_layer(rect, 0.5, BlendMode::SrcOver);
+    canvas.fill_rrect(rect, Rounding::same(8.0), Color::bgra(0xFF_2196F3));
+    canvas.fill_circle(rect.center(), 20.0, Color::bgra(0xFF_FFC107));
+    canvas.restore();
+
+    canvas.restore();
+}
+
+pub fn draw_fills(canvas: &mut Canvas, x: f32, y: f32) {
     canvas.save();
     canvas.push_translate(x, y);
     canvas.push_scale(0.5);
@@ -49,6 +49,7 @@ struct Demo {
     mouse: Offset,
     counter: crate::time::Counter,
     image: u32,
+    fonts: canvas::Fonts,
     blowup: bool,
 }
 
@@ -64,6 +65,22 @@ impl app::Application for Demo {
 
         let image = open_image(&mut renderer, device, queue, "examples/rust-jerk.jpg").unwrap();
 
+        let fonts = canvas::Fonts {
+            regular: renderer
+                .fonts
+                .load_file("assets/fonts/Roboto-Regular.ttf")
+                .unwrap(),
+            bold: renderer
+                .fonts
+                .load_file("assets/fonts/Roboto-Bold.ttf")
+                .unwrap(),
+            icons: renderer.fonts.load_file("assets/fonts/entypo.ttf").unwrap(),
+            emoji: renderer
+                .fonts
+                .load_file("assets/fonts/NotoEmoji-Regular.ttf")
+                .unwrap(),
+        };
+
         let staging_belt = wgpu::util::StagingBelt::new(0x20_0000);
 
         Self {
@@ -72,6 +89,7 @@ impl app::Application for Demo {
             mouse: Offset::zero(),
             counter: crate::time::Counter::new(),
             image,
+            fonts,
             blowup: false,
         }
     }
@@ -122,7 +140,15 @@ impl app::Application for Demo {
             let size = Offset::new(width as f32, height as f32) / scale;
 
             canvas.image_rect(self.image, Rect::from_size(size.x, size.y));
-            canvas::render_demo(&mut canvas, mouse, size, time, self.blowup);
+            canvas::render_demo(
+                &mut canvas,
+                self.fonts,
+                self.image,
+                mouse,
+                size,
+                time,
+                self.blowup,
+            );
         }
 
         let clear = reui::wgpu::Color {
@@ -136,6 +162,7 @@ impl app::Application for Demo {
             &mut encoder,
             &mut self.staging_belt,
             device,
+            queue,
             &view,
             Some(clear),
         );
@@ -31,6 +31,7 @@ impl Counter {
         time
     }
 
+    #[allow(dead_code)]
     pub fn average_ms(&self) -> f32 {
         let sum: f32 = self.deltas.iter().sum();
         sum / self.deltas.len() as f32 * 1000.0
@@ -0,0 +1,765 @@
+//! Golden image snapshots of the demo scenes.
+//!
+//! Every scene is rendered with [`reui::SoftwareRenderer`], so no adapter is needed,
+//! and compared against `tests/golden/<name>.png` with a per-channel tolerance.
+//! On mismatch the rendered image and a diff image are written into the target directory.
+//!
+//! The reference images are generated by the software renderer itself, so they only catch
+//! changes of its output. They are not recorded on a GPU, the software renderer is checked
+//! against [`reui::Renderer`] by the GPU comparison below.
+//! Run with `REUI_BLESS=1` to (re)generate the reference images after an intended change.
+//!
+//! Run with `REUI_GPU=1` to also render every scene with [`reui::Renderer`] on the first
+//! available adapter and compare it against the software output. The comparison fails
+//! if there is no adapter, so a missing one doesn't pass silently where it is expected.
+//! Only Vulkan, Metal and DX12 adapters are used unless `WGPU_BACKEND` selects others,
+//! since GL only emulates the instance offsets every draw relies on.
+
+#![warn(clippy::all)]
+#![allow(clippy::unusual_byte_groupings)]
+
+#[allow(dead_code)]
+#[path = "../src/blendish.rs"]
+mod blendish;
+#[allow(dead_code)]
+#[path = "../src/canvas.rs"]
+mod canvas;
+
+use reui::{
+    wgpu, BlendMode, Canvas, Color, ConicalGradient, FillRule, FontStore, Gradient, Image,
+    ImageFilter, LineCap, LineJoin, LinearGradient, Offset, Path as Shape, Pixels, Rect, Renderer,
+    Rounding, SoftwareRenderer, Spread, Stroke, SweepGradient,
+};
+use std::path::{Path, PathBuf};
+
+/// Maximum allowed difference of a single color channel.
+const TOLERANCE: u8 = 2;
+
+/// Maximum allowed difference of a single color channel between the GPU and software output.
+const GPU_TOLERANCE: u8 = 16;
+/// Share of pixels allowed to differ by more than [`GPU_TOLERANCE`],
+/// rasterization rules of edges are not exact across adapters.
+const GPU_MISMATCHES: f32 = 0.005;
+
+const CLEAR: wgpu::Color = wgpu::Color {
+    r: 0.3,
+    g: 0.3,
+    b: 0.32,
+    a: 1.0,
+};
+
+const TIME: f32 = 1.5;
+const MOUSE: Offset = Offset::new(600.0, 300.0);
+
+struct Scene {
+    renderer: SoftwareRenderer,
+    fonts: canvas::Fonts,
+    image: Image,
+}
+
+impl Scene {
+    fn new() -> Self {
+        let mut renderer = SoftwareRenderer::new();
+        let fonts = load_fonts(&mut renderer.fonts);
+        let m = load_image();
+        let image = renderer.upload_image(m.width(), m.height(), m.as_raw());
+
+        Self {
+            renderer,
+            fonts,
+            image,
+        }
+    }
+
+    fn check(
+        mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        draw: impl Fn(&mut Canvas<Image>, canvas::Fonts, Image),
+    ) {
+        let (fonts, image) = (self.fonts, self.image);
+        let actual = self
+            .renderer
+            .render_pixels(width, height, CLEAR, |ctx| draw(ctx, fonts, image));
+
+        compare(name, &actual);
+
+        if std::env::var_os("REUI_GPU").is_some() {
+            match render_gpu(width, height, draw) {
+                Some(gpu) => compare_gpu(name, &gpu, &actual),
+                None => panic!("{name}: REUI_GPU is set, but there is no adapter"),
+            }
+        }
+    }
+
+    /// Checks the canvas drawn by `draw` over the one drawn by `base` without clearing,
+    /// like a second picture rendered into the same target. `base` has to be opaque.
+    fn check_loaded(
+        mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        base: impl Fn(&mut Canvas<Image>),
+        draw: impl Fn(&mut Canvas<Image>),
+    ) {
+        let mut actual = self.renderer.render_pixels(width, height, CLEAR, &base);
+        self.renderer.render_into(&mut actual, None, &draw);
+
+        compare(name, &actual);
+
+        if std::env::var_os("REUI_GPU").is_some() {
+            match render_gpu_loaded(width, height, base, draw) {
+                Some(gpu) => compare_gpu(name, &gpu, &actual),
+                None => panic!("{name}: REUI_GPU is set, but there is no adapter"),
+            }
+        }
+    }
+}
+
+fn load_fonts(store: &mut FontStore) -> canvas::Fonts {
+    let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets/fonts");
+    let mut load = |name: &str| store.load_file(assets.join(name)).unwrap();
+    canvas::Fonts {
+        regular: load("Roboto-Regular.ttf"),
+        bold: load("Roboto-Bold.ttf"),
+        icons: load("entypo.ttf"),
+        emoji: load("NotoEmoji-Regular.ttf"),
+    }
+}
+
+fn load_image() -> image::RgbaImage {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../rust-jerk.jpg");
+    image::open(path).unwrap().to_rgba8()
+}
+
+/// Requests a device on the first available adapter.
+fn gpu_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::PRIMARY),
+        ..Default::default()
+    });
+    let options = wgpu::RequestAdapterOptions::default();
+    let adapter = pollster::block_on(instance.request_adapter(&options))?;
+    let desc = wgpu::DeviceDescriptor::default();
+    pollster::block_on(adapter.request_device(&desc, None)).ok()
+}
+
+/// Renders the scene with [`Renderer`] on the first available adapter.
+fn render_gpu(
+    width: u32,
+    height: u32,
+    draw: impl Fn(&mut Canvas<Image>, canvas::Fonts, Image),
+) -> Option<Pixels> {
+    let (device, queue) = gpu_device()?;
+
+    // Fonts and images take the same keys as in the software renderer.
+    let mut renderer = Renderer::new(&device, width, height);
+    let fonts = load_fonts(&mut renderer.fonts);
+    let m = load_image();
+    let (w, h) = m.dimensions();
+    let image = renderer.upload_image(&device, &queue, None, w, h, m.as_raw(), None);
+
+    let pixels = renderer.render_pixels(&device, &queue, width, height, CLEAR, |ctx| {
+        draw(ctx, fonts, image);
+    });
+    Some(pixels.unwrap())
+}
+
+/// Renders `base` and `draw` over it into the same texture with [`Renderer::flush_texture`].
+fn render_gpu_loaded(
+    width: u32,
+    height: u32,
+    base: impl Fn(&mut Canvas<Image>),
+    draw: impl Fn(&mut Canvas<Image>),
+) -> Option<Pixels> {
+    let (device, queue) = gpu_device()?;
+    let mut renderer = Renderer::new(&device, width, height);
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: renderer.target.color,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+
+    let flush = |renderer: &mut Renderer, clear_color| {
+        let desc = wgpu::CommandEncoderDescriptor::default();
+        let mut encoder = device.create_command_encoder(&desc);
+        let mut staging_belt = wgpu::util::StagingBelt::new(0x20_0000);
+        renderer.flush_texture(
+            &mut encoder,
+            &mut staging_belt,
+            &device,
+            &queue,
+            &texture,
+            clear_color,
+        );
+        staging_belt.finish();
+        queue.submit(Some(encoder.finish()));
+        staging_belt.recall();
+    };
+
+    base(&mut renderer.start(&device, &queue, width, height));
+    flush(&mut renderer, Some(CLEAR));
+    draw(&mut renderer.start(&device, &queue, width, height));
+    flush(&mut renderer, None);
+
+    Some(reui::read_texture(&device, &queue, &texture).unwrap())
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden")
+}
+
+fn compare(name: &str, actual: &Pixels) {
+    let expected_path = golden_dir().join(format!("{name}.png"));
+
+    if std::env::var_os("REUI_BLESS").is_some() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        actual.save_png(&expected_path).unwrap();
+        return;
+    }
+
+    let output = output_dir();
+    std::fs::create_dir_all(&output).unwrap();
+    let actual_path = output.join(format!("{name}.actual.png"));
+    let diff_path = output.join(format!("{name}.diff.png"));
+
+    let expected = match image::open(&expected_path) {
+        Ok(expected) => expected.to_rgba8(),
+        Err(err) => {
+            actual.save_png(&actual_path).unwrap();
+            panic!(
+                "failed to open {}: {err}\nrendered: {}\nrun with REUI_BLESS=1 to create it",
+                expected_path.display(),
+                actual_path.display(),
+            );
+        }
+    };
+
+    if (expected.width(), expected.height()) != (actual.width, actual.height) {
+        actual.save_png(&actual_path).unwrap();
+        panic!(
+            "{name}: size {}x{} differs from reference {}x{}\nrendered: {}",
+            actual.width,
+            actual.height,
+            expected.width(),
+            expected.height(),
+            actual_path.display(),
+        );
+    }
+
+    let (mismatches, max_delta, diff) = diff(actual, expected.as_raw(), TOLERANCE);
+    if mismatches > 0 {
+        actual.save_png(&actual_path).unwrap();
+        diff.save_png(&diff_path).unwrap();
+        panic!(
+            "{name}: {mismatches} pixels differ by more than {TOLERANCE} (max {max_delta})\n\
+             rendered: {}\ndiff: {}",
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+fn compare_gpu(name: &str, gpu: &Pixels, software: &Pixels) {
+    let (mismatches, max_delta, diff) = diff(gpu, &software.data, GPU_TOLERANCE);
+    let allowed = (software.data.len() / 4) as f32 * GPU_MISMATCHES;
+    if mismatches as f32 > allowed {
+        let output = output_dir();
+        std::fs::create_dir_all(&output).unwrap();
+        let gpu_path = output.join(format!("{name}.gpu.png"));
+        let diff_path = output.join(format!("{name}.gpu.diff.png"));
+        gpu.save_png(&gpu_path).unwrap();
+        diff.save_png(&diff_path).unwrap();
+        panic!(
+            "{name}: {mismatches} GPU pixels differ by more than {GPU_TOLERANCE} (max {max_delta})\n\
+             rendered: {}\ndiff: {}",
+            gpu_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+/// Counts pixels differing by more than `tolerance` in any channel.
+///
+/// Returns the count, the largest difference and an image with mismatched pixels in red.
+fn diff(actual: &Pixels, expected: &[u8], tolerance: u8) -> (usize, u8, Pixels) {
+    let mut diff = Pixels {
+        width: actual.width,
+        height: actual.height,
+        data: Vec::with_capacity(actual.data.len()),
+    };
+
+    let mut mismatches = 0;
+    let mut max_delta = 0;
+    for (a, e) in actual.data.chunks(4).zip(expected.chunks(4)) {
+        let delta = (0..4).map(|i| a[i].abs_diff(e[i])).max().unwrap_or(0);
+        max_delta = max_delta.max(delta);
+
+        if delta > tolerance {
+            mismatches += 1;
+            diff.data.extend([0xFF, 0x00, 0x00, 0xFF]);
+        } else {
+            // Dimmed reference as a backdrop for the mismatched pixels.
+            let luma = (u32::from(e[0]) * 2 + u32::from(e[1]) * 5 + u32::from(e[2])) / 8;
+            let luma = (luma / 3) as u8;
+            diff.data.extend([luma, luma, luma, 0xFF]);
+        }
+    }
+    (mismatches, max_delta, diff)
+}
+
+#[test]
+fn demo() {
+    Scene::new().check("demo", 1000, 600, |ctx, fonts, image| {
+        let size = Offset::new(1000.0, 600.0);
+        canvas::render_demo(ctx, fonts, image, MOUSE, size, TIME, false);
+    });
+}
+
+#[test]
+fn blowup() {
+    Scene::new().check("blowup", 500, 400, |ctx, fonts, image| {
+        let size = Offset::new(1000.0, 600.0);
+        canvas::render_demo(ctx, fonts, image, MOUSE, size, TIME, true);
+    });
+}
+
+#[test]
+fn lines() {
+    Scene::new().check("lines", 420, 160, |ctx, _, _| {
+        canvas::draw_lines(ctx, 10.0, 60.0, 600.0, 50.0, TIME);
+    });
+}
+
+#[test]
+fn widths_and_caps() {
+    Scene::new().check("widths_and_caps", 110, 320, |ctx, _, _| {
+        // The hairlines are barely visible on the default background.
+        ctx.fill_rect(Rect::from_ltwh(0.0, 0.0, 50.0, 320.0), reui::Color::BLACK);
+        canvas::draw_widths(ctx, 10.0, 10.0, 30.0);
+        canvas::draw_caps(ctx, 65.0, 10.0, 30.0);
+    });
+}
+
+#[test]
+fn fills() {
+    Scene::new().check("fills", 140, 120, |ctx, _, _| {
+        canvas::draw_fills(ctx, 20.0, 20.0);
+        canvas::draw_rrect(ctx, 40.0, 80.0);
+    });
+}
+
+#[test]
+fn paints() {
+    Scene::new().check("paints", 400, 250, |ctx, _, image| {
+        canvas::draw_blending(ctx, 10.0, 10.0, 85.0, 40.0);
+        canvas::draw_palette(ctx, Offset::new(110.0, 10.0));
+        canvas::draw_gradients(ctx, 10.0, 60.0);
+        canvas::draw_patterns(ctx, image, 230.0, 60.0, TIME);
+    });
+}
+
+#[test]
+fn eyes_and_graph() {
+    Scene::new().check("eyes_and_graph", 400, 220, |ctx, _, _| {
+        let rect = Rect::from_ltwh(10.0, 10.0, 150.0, 100.0);
+        canvas::draw_eyes(ctx, rect, MOUSE, TIME);
+        canvas::draw_graph(ctx, 0.0, 120.0, 400.0, 100.0, TIME);
+    });
+}
+
+#[test]
+fn colorwheel() {
+    Scene::new().check("colorwheel", 260, 260, |ctx, _, _| {
+        let rect = Rect::from_ltwh(5.0, 5.0, 250.0, 250.0);
+        canvas::draw_colorwheel(ctx, rect, TIME);
+    });
+}
+
+#[test]
+fn widgets() {
+    Scene::new().check("widgets", 320, 230, |ctx, fonts, _| {
+        let win = Rect::from_ltwh(10.0, 10.0, 300.0, 210.0);
+        canvas::draw_window(ctx, fonts, "Widgets", win, |ctx, window| {
+            let window = window.deflate(10.0);
+            let row = Rect::from_size(window.dx(), 28.0).translate(window.min);
+            let half = Rect::from_size(window.dx() / 2.0, 28.0);
+
+            canvas::draw_search_box(ctx, fonts, "Search", row);
+            let row = row.translate(Offset::new(0.0, 40.0));
+            canvas::draw_drop_down(ctx, row);
+            let row = row.translate(Offset::new(0.0, 40.0));
+            let rect = half.translate(row.min).deflate(3.0);
+            canvas::draw_checkbox(ctx, fonts, "Remember me", rect);
+            let rect = rect.translate(Offset::new(half.dx(), 0.0));
+            canvas::draw_edit_box_num(ctx, fonts, "123.00", "px", rect);
+            let row = row.translate(Offset::new(0.0, 40.0));
+            let rect = half.translate(row.min).deflate(3.0);
+            canvas::draw_slider(ctx, 0.4, rect);
+            let rect = rect.translate(Offset::new(half.dx(), 0.0));
+            canvas::draw_button(ctx, fonts, "Sign in", rect, 0xFF_006080);
+        });
+    });
+}
+
+#[test]
+fn blendish() {
+    Scene::new().check("blendish", 220, 220, |ctx, _, _| {
+        blendish::run(ctx, Rect::from_ltwh(10.0, 10.0, 200.0, 200.0));
+    });
+}
+
+fn star(center: Offset, outer: f32, inner: f32) -> Shape {
+    let mut path = Shape::new();
+    for i in 0..10 {
+        let radius = if i % 2 == 0 { outer } else { inner };
+        let angle = i as f32 * std::f32::consts::PI / 5.0 - std::f32::consts::FRAC_PI_2;
+        let point = center + Offset::new(angle.cos(), angle.sin()) * radius;
+        if i == 0 {
+            path.move_to(point);
+        } else {
+            path.line_to(point);
+        }
+    }
+    path.close();
+    path
+}
+
+fn stripes(ctx: &mut Canvas<Image>, rect: Rect) {
+    let colors = [0xFF_E04040, 0xFF_F0C040, 0xFF_40A040, 0xFF_4060E0];
+    let mut x = rect.min.x;
+    for color in colors.iter().cycle() {
+        if x >= rect.max.x {
+            break;
+        }
+        let stripe = Rect::from_ltrb(x, rect.min.y, (x + 8.0).min(rect.max.x), rect.max.y);
+        ctx.fill_rect(stripe, Color::bgra(*color));
+        x += 8.0;
+    }
+}
+
+#[test]
+fn clips() {
+    Scene::new().check("clips", 400, 110, |ctx, _, _| {
+        let fill = LinearGradient::new(
+            [0.0, 0.0],
+            [400.0, 110.0],
+            Color::bgra(0xFF_F0C040),
+            Color::bgra(0xFF_4060E0),
+        );
+        let all = Rect::from_ltwh(0.0, 0.0, 400.0, 110.0);
+
+        ctx.save();
+        ctx.clip_rect(Rect::from_ltwh(10.0, 10.0, 80.0, 90.0));
+        ctx.fill_rect(all, fill);
+        ctx.restore();
+
+        ctx.save();
+        ctx.clip_rrect(
+            Rect::from_ltwh(100.0, 10.0, 90.0, 90.0),
+            Rounding::same(25.0),
+        );
+        ctx.fill_rect(all, fill);
+        ctx.restore();
+
+        // Self-intersecting star with both fill rules.
+        ctx.save();
+        let mut path = Shape::new();
+        for i in 0..5 {
+            let angle = i as f32 * 4.0 * std::f32::consts::PI / 5.0 - std::f32::consts::FRAC_PI_2;
+            let point = Offset::new(245.0, 57.0) + Offset::new(angle.cos(), angle.sin()) * 45.0;
+            if i == 0 {
+                path.move_to(point);
+            } else {
+                path.line_to(point);
+            }
+        }
+        path.close();
+        ctx.clip_path(&path, FillRule::EvenOdd);
+        ctx.fill_rect(all, fill);
+        ctx.restore();
+
+        // Rotated path clip nested in a rectangle clip.
+        ctx.save();
+        ctx.clip_rect(Rect::from_ltwh(300.0, 10.0, 90.0, 60.0));
+        ctx.push_translate(345.0, 55.0);
+        ctx.push_rotate(0.3);
+        ctx.clip_path(&star(Offset::zero(), 45.0, 20.0), FillRule::NonZero);
+        ctx.fill_rect(Rect::from_ltwh(-60.0, -60.0, 120.0, 120.0), fill);
+        ctx.restore();
+    });
+}
+
+#[test]
+fn layers() {
+    Scene::new().check("layers", 300, 110, |ctx, _, _| {
+        // Overlapping shapes share the opacity of the layer.
+        let bounds = Rect::from_ltwh(0.0, 0.0, 100.0, 110.0);
+        ctx.save_layer(bounds, 0.5, BlendMode::SrcOver);
+        ctx.fill_circle(Offset::new(40.0, 45.0), 30.0, Color::bgra(0xFF_E04040));
+        ctx.fill_circle(Offset::new(60.0, 65.0), 30.0, Color::bgra(0xFF_4060E0));
+        ctx.restore();
+
+        let bounds = Rect::from_ltwh(100.0, 0.0, 100.0, 110.0);
+        ctx.save_layer(bounds, 1.0, BlendMode::SrcOver);
+        ctx.blur_layer(4.0);
+        ctx.fill_path(
+            &star(Offset::new(150.0, 55.0), 40.0, 18.0),
+            Color::WHITE,
+            FillRule::NonZero,
+        );
+        ctx.restore();
+
+        // Blurred layer nested in a translucent one, cut off at the inner bounds.
+        ctx.save_layer(
+            Rect::from_ltwh(200.0, 0.0, 100.0, 110.0),
+            0.7,
+            BlendMode::SrcOver,
+        );
+        ctx.fill_rect(
+            Rect::from_ltwh(210.0, 10.0, 80.0, 90.0),
+            Color::bgra(0xFF_40A040),
+        );
+        ctx.save_layer(
+            Rect::from_ltwh(215.0, 15.0, 70.0, 80.0),
+            1.0,
+            BlendMode::SrcOver,
+        );
+        ctx.blur_layer(6.0);
+        ctx.fill_circle(Offset::new(250.0, 55.0), 25.0, Color::bgra(0xFF_F0C040));
+        ctx.restore();
+        ctx.restore();
+    });
+}
+
+#[test]
+fn shadows() {
+    Scene::new().check("shadows", 300, 110, |ctx, _, _| {
+        let mut card = Shape::new();
+        card.rrect(Rect::from_ltwh(15.0, 15.0, 80.0, 70.0), Rounding::same(8.0));
+        ctx.shadow(
+            &card,
+            Offset::new(0.0, 6.0),
+            6.0,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        ctx.fill_path(&card, Color::WHITE, FillRule::NonZero);
+
+        let mut circle = Shape::new();
+        circle.circle(Offset::new(150.0, 50.0), 30.0);
+        ctx.shadow(
+            &circle,
+            Offset::new(8.0, 8.0),
+            2.0,
+            Color::bgra(0xC0_202040),
+        );
+        ctx.fill_path(&circle, Color::bgra(0xFF_F0C040), FillRule::NonZero);
+
+        // Colored glow without an offset, under a rotated shape.
+        ctx.push_translate(240.0, 55.0);
+        ctx.push_rotate(0.4);
+        let shape = star(Offset::zero(), 38.0, 16.0);
+        ctx.shadow(&shape, Offset::zero(), 8.0, Color::bgra(0xFF_40E0FF));
+        ctx.fill_path(&shape, Color::bgra(0xFF_4060E0), FillRule::NonZero);
+    });
+}
+
+#[test]
+fn backdrop_filter() {
+    Scene::new().check("backdrop_filter", 300, 110, |ctx, _, _| {
+        stripes(ctx, Rect::from_ltwh(0.0, 0.0, 300.0, 110.0));
+        ctx.fill_circle(Offset::new(60.0, 55.0), 30.0, Color::WHITE);
+
+        let glass = Rect::from_ltwh(20.0, 20.0, 120.0, 70.0);
+        ctx.backdrop_filter(glass, Rounding::same(12.0), ImageFilter::Blur(5.0));
+        ctx.fill_rrect(glass, Rounding::same(12.0), Color::new(1.0, 1.0, 1.0, 0.2));
+
+        // Filters inside a layer only read the layer.
+        let bounds = Rect::from_ltwh(160.0, 10.0, 130.0, 90.0);
+        ctx.save_layer(bounds, 1.0, BlendMode::SrcOver);
+        stripes(ctx, bounds);
+        ctx.push_translate(225.0, 55.0);
+        ctx.push_rotate(0.3);
+        let glass = Rect::from_ltwh(-40.0, -25.0, 80.0, 50.0);
+        ctx.backdrop_filter(glass, Rounding::same(25.0), ImageFilter::Blur(3.0));
+        ctx.restore();
+    });
+}
+
+#[test]
+fn blend_modes() {
+    Scene::new().check("blend_modes", 440, 100, |ctx, _, _| {
+        let src = Color::new(0.2, 0.4, 1.0, 0.8);
+        let dst = Color::new(1.0, 0.6, 0.1, 1.0);
+
+        // Every mode in its own layer, so clearing modes don't reach the background.
+        for (i, &mode) in BlendMode::ALL.iter().enumerate() {
+            let x = 5.0 + i as f32 * 39.0;
+            let bounds = Rect::from_ltwh(x, 5.0, 36.0, 40.0);
+            ctx.save_layer(bounds, 1.0, BlendMode::SrcOver);
+            ctx.fill_circle(Offset::new(x + 14.0, 20.0), 13.0, dst);
+            ctx.set_blend_mode(mode);
+            ctx.fill_rect(Rect::from_ltwh(x + 10.0, 16.0, 24.0, 26.0), src);
+            ctx.restore();
+        }
+
+        // Modes reading the destination outside of layers go through the root layer.
+        stripes(ctx, Rect::from_ltwh(5.0, 55.0, 430.0, 40.0));
+        let advanced = [
+            BlendMode::Screen,
+            BlendMode::Multiply,
+            BlendMode::Overlay,
+            BlendMode::Darken,
+            BlendMode::Lighten,
+        ];
+        for (i, &mode) in advanced.iter().enumerate() {
+            let x = 15.0 + i as f32 * 85.0;
+            ctx.save();
+            ctx.set_blend_mode(mode);
+            ctx.fill_rrect(
+                Rect::from_ltwh(x, 60.0, 70.0, 30.0),
+                Rounding::same(8.0),
+                src,
+            );
+            ctx.restore();
+        }
+    });
+}
+
+#[test]
+fn blend_over_loaded_target() {
+    let src = Color::new(0.2, 0.4, 1.0, 0.8);
+    let base = |ctx: &mut Canvas<Image>| stripes(ctx, Rect::from_ltwh(5.0, 5.0, 350.0, 50.0));
+
+    // The root layer starts with the stripes that are already in the target.
+    let draw = |ctx: &mut Canvas<Image>| {
+        let advanced = [
+            BlendMode::Multiply,
+            BlendMode::Overlay,
+            BlendMode::Darken,
+            BlendMode::Lighten,
+        ];
+        for (i, &mode) in advanced.iter().enumerate() {
+            let x = 15.0 + i as f32 * 85.0;
+            ctx.save();
+            ctx.set_blend_mode(mode);
+            ctx.fill_rrect(
+                Rect::from_ltwh(x, 10.0, 70.0, 40.0),
+                Rounding::same(8.0),
+                src,
+            );
+            ctx.restore();
+        }
+    };
+
+    Scene::new().check_loaded("blend_over_loaded_target", 360, 60, base, draw);
+}
+
+#[test]
+fn gradients() {
+    Scene::new().check("gradients", 400, 100, |ctx, _, _| {
+        let stops = [
+            (0.0, Color::bgra(0xFF_E04040)),
+            (0.5, Color::bgra(0xFF_F0C040)),
+            (1.0, Color::bgra(0xFF_4060E0)),
+        ];
+
+        // Focal point inside the end circle and two disjoint circles forming a cone.
+        let rect = Rect::from_ltwh(5.0, 5.0, 90.0, 90.0);
+        let paint = ConicalGradient::new(
+            [35.0, 35.0],
+            0.0,
+            [50.0, 50.0],
+            45.0,
+            Color::WHITE,
+            Color::BLACK,
+        )
+        .stops(&stops);
+        ctx.fill_rect(rect, paint);
+        let rect = Rect::from_ltwh(105.0, 5.0, 90.0, 90.0);
+        let paint = ConicalGradient::new(
+            [120.0, 50.0],
+            5.0,
+            [170.0, 50.0],
+            20.0,
+            Color::WHITE,
+            Color::BLACK,
+        )
+        .stops(&stops)
+        .spread(Spread::Reflect);
+        ctx.fill_rect(rect, paint);
+
+        let paint = SweepGradient::new(
+            [250.0, 50.0],
+            0.0,
+            std::f32::consts::TAU,
+            Color::WHITE,
+            Color::BLACK,
+        )
+        .stops(&stops);
+        ctx.fill_circle(Offset::new(250.0, 50.0), 45.0, paint);
+
+        // Partial sweep repeated around the circle.
+        let paint = SweepGradient::new([350.0, 50.0], -1.0, 0.5, Color::WHITE, Color::BLACK)
+            .stops(&stops)
+            .spread(Spread::Repeat);
+        ctx.fill_circle(Offset::new(350.0, 50.0), 45.0, paint);
+    });
+}
+
+#[test]
+fn dashes() {
+    Scene::new().check("dashes", 300, 130, |ctx, _, _| {
+        let color = Color::bgra(0xFF_F0F0F0);
+
+        let caps = [LineCap::Butt, LineCap::Round, LineCap::Square];
+        for (i, &cap) in caps.iter().enumerate() {
+            let y = 15.0 + i as f32 * 15.0;
+            let stroke = Stroke::width(6.0).cap(cap).dash(&[12.0, 8.0], 0.0);
+            ctx.stroke_line(Offset::new(15.0, y), Offset::new(135.0, y), color, stroke);
+        }
+
+        // The pattern continues around corners of closed contours.
+        let stroke = Stroke::width(4.0)
+            .joint(LineJoin::Miter)
+            .dash(&[20.0, 6.0, 4.0, 6.0], 5.0);
+        ctx.stroke_rect(Rect::from_ltwh(15.0, 65.0, 120.0, 50.0), color, stroke);
+
+        let stroke = Stroke::width(3.0)
+            .cap(LineCap::Round)
+            .dash(&[1.0, 7.0], 0.0);
+        ctx.stroke_circle(
+            Offset::new(200.0, 45.0),
+            30.0,
+            Color::bgra(0xFF_F0C040),
+            stroke,
+        );
+
+        let mut curve = Shape::new();
+        curve.move_to(Offset::new(160.0, 115.0));
+        curve.cubic_to(
+            Offset::new(190.0, 40.0),
+            Offset::new(250.0, 150.0),
+            Offset::new(290.0, 70.0),
+        );
+        let stroke = Stroke::width(5.0)
+            .cap(LineCap::Round)
+            .joint(LineJoin::Round)
+            .dash(&[16.0, 10.0], 8.0);
+        ctx.stroke_path(&curve, Color::bgra(0xFF_40A0E0), stroke);
+    });
+}
@@ -1,13 +1,26 @@
-use crate::pipeline::{Instance, Vertex};
+use crate::{
+    pipeline::{Instance, Vertex},
+    Color,
+};
 use core::{marker::PhantomData, mem::size_of, ops::RangeBounds};
-use std::ops::Range;
+use std::{collections::HashMap, ops::Range};
 use wgpu::util::DeviceExt as _;
 
+/// Number of texels in a gradient ramp row.
+pub const RAMP_WIDTH: u32 = 256;
+
+/// Maximum number of gradient ramp rows in a batch.
+///
+/// The smallest texture height wgpu guarantees, for downlevel WebGL2 limits.
+pub const MAX_RAMP_ROWS: u32 = 2048;
+
 #[derive(Default)]
 pub struct Batch {
     instances: Vec<Instance>,
     indices: Vec<u32>,
     vertices: Vec<Vertex>,
+    ramps: Vec<[u8; 4]>,
+    ramp_rows: HashMap<Vec<[u32; 5]>, u32>,
 }
 
 impl std::ops::Index<i32> for Batch {
@@ -31,6 +44,8 @@ impl Batch {
         self.vertices.clear();
         self.indices.clear();
         self.instances.clear();
+        self.ramps.clear();
+        self.ramp_rows.clear();
     }
 
     #[inline(always)]
@@ -45,6 +60,74 @@ impl Batch {
         index as u32
     }
 
+    /// Rasterizes color stops into a gradient ramp row and returns its index.
+    ///
+    /// Identical stops share a row.
+    /// Returns `None` once the batch has `MAX_RAMP_ROWS` rows.
+    pub fn ramp(&mut self, stops: &[(f32, Color)]) -> Option<u32> {
+        let mut stops = stops.to_vec();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let key: Vec<[u32; 5]> = stops
+            .iter()
+            .map(|&(offset, c)| [offset, c.red, c.green, c.blue, c.alpha].map(f32::to_bits))
+            .collect();
+        if let Some(&row) = self.ramp_rows.get(&key) {
+            return Some(row);
+        }
+        let index = self.ramp_rows.len() as u32;
+        if index == MAX_RAMP_ROWS {
+            return None;
+        }
+
+        let last = (RAMP_WIDTH - 1) as f32;
+        let row = (0..RAMP_WIDTH).map(|i| {
+            let t = i as f32 / last;
+            let next = stops.iter().position(|&(offset, _)| t < offset);
+            let color = match next {
+                Some(0) => stops[0].1,
+                Some(index) => {
+                    let (a, from) = stops[index - 1];
+                    let (b, to) = stops[index];
+                    let f = (t - a) / (b - a).max(f32::EPSILON);
+                    Color::new(
+                        from.red + (to.red - from.red) * f,
+                        from.green + (to.green - from.green) * f,
+                        from.blue + (to.blue - from.blue) * f,
+                        from.alpha + (to.alpha - from.alpha) * f,
+                    )
+                }
+                None => stops.last().map_or(Color::TRANSPARENT, |&(_, color)| color),
+            };
+            <[u8; 4]>::from(color)
+        });
+
+        self.ramps.extend(row);
+        self.ramp_rows.insert(key, index);
+        Some(index)
+    }
+
+    #[inline]
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    #[inline]
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    #[inline]
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    /// Gradient ramp rows of 256 texels each.
+    #[inline]
+    pub fn ramps(&self) -> &[[u8; 4]] {
+        &self.ramps
+    }
+
     #[inline]
     pub fn base_vertex(&self) -> i32 {
         self.vertices.len() as i32
@@ -88,6 +171,7 @@ pub struct GpuBatch {
     pub indices: UploadBuffer<u32>,
     pub vertices: UploadBuffer<Vertex>,
     pub instances: UploadBuffer<Instance>,
+    pub ramps: RampTexture,
 }
 
 impl GpuBatch {
@@ -96,6 +180,7 @@ impl GpuBatch {
             indices: UploadBuffer::new(device, wgpu::BufferUsages::INDEX, 128),
             vertices: UploadBuffer::new(device, wgpu::BufferUsages::VERTEX, 128),
             instances: UploadBuffer::new(device, wgpu::BufferUsages::VERTEX, 128),
+            ramps: RampTexture::new(device),
         }
     }
 
@@ -103,6 +188,7 @@ impl GpuBatch {
         self.indices.queue(queue, device, &batch.indices);
         self.vertices.queue(queue, device, &batch.vertices);
         self.instances.queue(queue, device, &batch.instances);
+        self.ramps.queue(queue, device, &batch.ramps);
     }
 
     pub fn staging(
@@ -117,6 +203,7 @@ impl GpuBatch {
             .staging(encoder, belt, device, &batch.vertices);
         self.instances
             .staging(encoder, belt, device, &batch.instances);
+        self.ramps.staging(encoder, belt, device, &batch.ramps);
     }
 
     pub fn bind<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
@@ -126,6 +213,98 @@ impl GpuBatch {
     }
 }
 
+/// Gradient ramps, one row of [`RAMP_WIDTH`] texels per gradient.
+pub struct RampTexture {
+    pub view: wgpu::TextureView,
+    texture: wgpu::Texture,
+    buffer: UploadBuffer<[u8; 4]>,
+    rows: u32,
+}
+
+impl RampTexture {
+    fn new(device: &wgpu::Device) -> Self {
+        let rows = 16;
+        let texture = Self::create_texture(device, rows);
+        Self {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            texture,
+            buffer: UploadBuffer::new(device, wgpu::BufferUsages::COPY_SRC, 0x1000),
+            rows,
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, rows: u32) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("reui::RampTexture"),
+            size: wgpu::Extent3d {
+                width: RAMP_WIDTH,
+                height: rows,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+    }
+
+    /// Grows the texture to fit the ramps, returns the size of the copy.
+    fn reserve(&mut self, device: &wgpu::Device, data: &[[u8; 4]]) -> Option<wgpu::Extent3d> {
+        let rows = data.len() as u32 / RAMP_WIDTH;
+        if rows == 0 {
+            return None;
+        }
+
+        if rows > self.rows {
+            self.rows = rows.next_power_of_two();
+            self.texture = Self::create_texture(device, self.rows);
+            self.view = self
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
+        }
+
+        Some(wgpu::Extent3d {
+            width: RAMP_WIDTH,
+            height: rows,
+            depth_or_array_layers: 1,
+        })
+    }
+
+    fn layout() -> wgpu::ImageDataLayout {
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(RAMP_WIDTH * 4),
+            rows_per_image: None,
+        }
+    }
+
+    fn queue(&mut self, queue: &wgpu::Queue, device: &wgpu::Device, data: &[[u8; 4]]) {
+        if let Some(size) = self.reserve(device, data) {
+            let texture = self.texture.as_image_copy();
+            queue.write_texture(texture, bytemuck::cast_slice(data), Self::layout(), size);
+        }
+    }
+
+    fn staging(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        belt: &mut wgpu::util::StagingBelt,
+        device: &wgpu::Device,
+        data: &[[u8; 4]],
+    ) {
+        if let Some(size) = self.reserve(device, data) {
+            self.buffer.staging(encoder, belt, device, data);
+            let buffer = wgpu::ImageCopyBuffer {
+                buffer: self.buffer.as_ref(),
+                layout: Self::layout(),
+            };
+            encoder.copy_buffer_to_texture(buffer, self.texture.as_image_copy(), size);
+        }
+    }
+}
+
 pub struct UploadBuffer<T> {
     buffer: wgpu::Buffer,
     usage: wgpu::BufferUsages,
@@ -171,7 +350,7 @@ impl<T: bytemuck::Pod> UploadBuffer<T> {
         }
     }
 
-    pub fn slice<S: RangeBounds<wgpu::BufferAddress>>(&self, bounds: S) -> wgpu::BufferSlice {
+    pub fn slice<S: RangeBounds<wgpu::BufferAddress>>(&self, bounds: S) -> wgpu::BufferSlice<'_> {
         self.buffer.slice(bounds)
     }
 
@@ -1,15 +1,25 @@
 use crate::{
-    internals::ImageBind, FillRule, Images, IntoPaint, Offset, Path, Recorder, Rect, Rounding,
-    Stroke, Transform,
+    BlendMode, Color, FillRule, FontId, FontStore, GlyphCache, ImageFilter, ImageSize, IntoPaint,
+    Offset, Paragraph, Path, Recorder, Rect, Rounding, Scissor, Stroke, TextMetrics, Transform,
 };
 
+/// Canvas state saved and restored by [`Canvas::save`] and [`Canvas::restore`].
+#[derive(Clone, Copy, Default)]
+pub struct State {
+    pub transform: Transform,
+    pub scissor: Scissor,
+    pub clip_depth: usize,
+    pub layer_depth: usize,
+    pub blend_mode: BlendMode,
+}
+
 #[derive(Default)]
-pub struct TransformStack(Transform, Vec<Transform>);
+pub struct StateStack(State, Vec<State>);
 
-impl TransformStack {
+impl StateStack {
     #[inline]
     pub fn save(&mut self) {
-        self.1.push(self.transform());
+        self.1.push(self.state());
     }
 
     #[inline]
@@ -18,29 +28,71 @@ impl TransformStack {
     }
 
     #[inline]
-    pub fn transform(&self) -> Transform {
+    pub fn state(&self) -> State {
         *self.1.last().unwrap_or(&self.0)
     }
 
+    #[inline]
+    pub fn state_mut(&mut self) -> &mut State {
+        self.1.last_mut().unwrap_or(&mut self.0)
+    }
+
+    #[inline]
+    pub fn transform(&self) -> Transform {
+        self.state().transform
+    }
+
     #[inline]
     pub fn pre_transform(&mut self, m: Transform) {
-        *self.1.last_mut().unwrap_or(&mut self.0) *= m;
+        self.state_mut().transform *= m;
     }
 }
 
 pub struct Canvas<'a, Key = u32> {
     recorder: &'a mut Recorder<Key>,
-    images: &'a Images<Key>,
-    states: TransformStack,
+    images: &'a dyn ImageSize<Key>,
+    fonts: &'a FontStore,
+    glyphs: Option<&'a mut GlyphCache<Key>>,
+    states: StateStack,
     path: Path,
 }
 
-impl<'a, Key: Eq + std::hash::Hash> Canvas<'a, Key> {
-    pub fn new(recorder: &'a mut Recorder<Key>, images: &'a Images<Key>) -> Self {
+impl<'a, Key: Clone + Eq + std::hash::Hash> Canvas<'a, Key> {
+    /// Creates a canvas without fonts, text is neither measured nor drawn.
+    pub fn new(recorder: &'a mut Recorder<Key>, images: &'a dyn ImageSize<Key>) -> Self {
+        static NO_FONTS: FontStore = FontStore::new();
+        Self::with_fonts(recorder, images, &NO_FONTS, None)
+    }
+
+    /// Creates a canvas that lays out text with `fonts` and caches glyphs in `glyphs`.
+    pub fn with_text(
+        recorder: &'a mut Recorder<Key>,
+        images: &'a dyn ImageSize<Key>,
+        fonts: &'a FontStore,
+        glyphs: &'a mut GlyphCache<Key>,
+    ) -> Self {
+        Self::with_fonts(recorder, images, fonts, Some(glyphs))
+    }
+
+    fn with_fonts(
+        recorder: &'a mut Recorder<Key>,
+        images: &'a dyn ImageSize<Key>,
+        fonts: &'a FontStore,
+        glyphs: Option<&'a mut GlyphCache<Key>>,
+    ) -> Self {
+        let state = State {
+            clip_depth: recorder.clip_depth(),
+            layer_depth: recorder.layer_depth(),
+            ..State::default()
+        };
+        recorder.set_scissor(state.scissor);
+
         Self {
             recorder,
             images,
-            states: TransformStack(Transform::default(), Vec::with_capacity(16)),
+            fonts,
+            glyphs,
+            states: StateStack(state, Vec::with_capacity(16)),
             path: Path::new(),
         }
     }
@@ -50,9 +102,121 @@ impl<'a, Key: Eq + std::hash::Hash> Canvas<'a, Key> {
         self.states.save();
     }
 
+    /// Saves the state like [`Canvas::save`] and redirects drawing into an offscreen layer.
+    ///
+    /// The matching [`Canvas::restore`] composites the layer with `opacity` and `blend_mode`,
+    /// so overlapping shapes inside it share a single opacity without seams.
+    /// Drawing outside of `bounds` is discarded.
+    pub fn save_layer(&mut self, bounds: Rect, opacity: f32, blend_mode: BlendMode) {
+        self.states.save();
+
+        let transform = self.states.transform();
+        let corners = [
+            bounds.min,
+            Offset::new(bounds.max.x, bounds.min.y),
+            bounds.max,
+            Offset::new(bounds.min.x, bounds.max.y),
+        ];
+        let bounds = corners
+            .map(|corner| transform.apply(corner))
+            .into_iter()
+            .fold(
+                Rect::new(Offset::infinity(), -Offset::infinity()),
+                |rect, p| Rect::new(rect.min.min(p), rect.max.max(p)),
+            );
+
+        self.recorder.save_layer(bounds, opacity, blend_mode);
+        self.states.state_mut().layer_depth = self.recorder.layer_depth();
+    }
+
     /// Pops the current save stack, if there is anything to pop. Otherwise, does nothing. [...]
+    ///
+    /// Layers opened with [`Canvas::save_layer`] are composited here.
     pub fn restore(&mut self) {
-        self.states.restore();
+        if self.states.restore() {
+            let state = self.states.state();
+            self.recorder.set_scissor(state.scissor);
+            self.recorder.restore_layer(state.layer_depth);
+            self.recorder.reset_clip(state.clip_depth);
+        }
+    }
+
+    /// Blurs the layer opened by the last [`Canvas::save_layer`] when it is restored.
+    ///
+    /// `sigma` is the standard deviation of the Gaussian in local units,
+    /// content blurred past the layer bounds is cut off. Does nothing outside of layers.
+    pub fn blur_layer(&mut self, sigma: f32) {
+        let sigma = sigma * self.states.transform().average_scale();
+        self.recorder.blur_layer(sigma);
+    }
+
+    /// Draws a blurred copy of the path offset by `offset`.
+    ///
+    /// Draw the shape itself afterwards, so the shadow ends up under it.
+    pub fn shadow(&mut self, path: &Path, offset: Offset, blur_sigma: f32, color: Color) {
+        let state = self.states.state();
+        self.recorder.shadow(
+            path,
+            color,
+            state.transform,
+            offset,
+            blur_sigma,
+            state.blend_mode,
+        );
+    }
+
+    /// Replaces what is already drawn under the rounded rectangle with its filtered copy,
+    /// like CSS `backdrop-filter`.
+    ///
+    /// Draws outside of layers are rendered through an offscreen root layer
+    /// from the start of the frame, since the target itself can't be read.
+    pub fn backdrop_filter(&mut self, rect: Rect, radius: Rounding, filter: ImageFilter) {
+        self.path.clear();
+        self.path.rrect(rect, radius);
+        let transform = self.states.transform();
+        self.recorder.backdrop_filter(&self.path, transform, filter);
+    }
+
+    /// Sets the blend mode of the following fills and strokes.
+    ///
+    /// Multiply, overlay, darken and lighten read the destination,
+    /// which splits rendering at every such draw.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.states.state_mut().blend_mode = blend_mode;
+    }
+
+    /// Reduces the clip region to the intersection of the current clip and the given rectangle.
+    ///
+    /// Uses a cheap scissor instead of the stencil buffer.
+    /// If the current transform is rotated relative to a previous rectangle clip,
+    /// the bounding box of the previous clip is used.
+    pub fn clip_rect(&mut self, rect: Rect) {
+        let state = self.states.state_mut();
+        state.scissor = state.scissor.intersect(rect, state.transform);
+        self.recorder.set_scissor(state.scissor);
+    }
+
+    /// Reduces the clip region to the intersection of the current clip and the given rounded rectangle.
+    pub fn clip_rrect(&mut self, rect: Rect, radius: Rounding) {
+        if radius == Rounding::zero() {
+            self.clip_rect(rect);
+        } else {
+            self.path.clear();
+            self.path.rrect(rect, radius);
+            let transform = self.states.transform();
+            self.recorder
+                .clip_path(&self.path, transform, FillRule::NonZero);
+            self.states.state_mut().clip_depth = self.recorder.clip_depth();
+        }
+    }
+
+    /// Reduces the clip region to the intersection of the current clip and the given [`Path`].
+    ///
+    /// Path clips use the stencil buffer and are not antialiased.
+    pub fn clip_path(&mut self, path: &Path, rule: FillRule) {
+        let transform = self.states.transform();
+        self.recorder.clip_path(path, transform, rule);
+        self.states.state_mut().clip_depth = self.recorder.clip_depth();
     }
 
     /// Add a rotation to the current transform. The argument is in radians clockwise.
@@ -83,18 +247,69 @@ impl<'a, Key: Eq + std::hash::Hash> Canvas<'a, Key> {
     }
 
     pub fn image(&mut self, image: Key, offset: Offset) {
-        if let Some(ImageBind { size, .. }) = self.images.get(&image) {
+        if let Some((width, height)) = self.images.image_size(&image) {
             let rect = Rect {
                 min: offset,
-                max: offset + Offset::new(size.width as f32, size.height as f32),
+                max: offset + Offset::new(width as f32, height as f32),
             };
             self.image_rect(image, rect);
         }
     }
 
+    /// Draws a single line of text with the baseline starting at `position`.
+    ///
+    /// Returns the horizontal advance of the text.
+    pub fn fill_text(
+        &mut self,
+        text: &str,
+        position: Offset,
+        font: FontId,
+        size: f32,
+        paint: impl IntoPaint<Key>,
+    ) -> f32 {
+        let Some(glyphs) = self.glyphs.as_deref_mut() else {
+            return 0.0;
+        };
+        let transform = self.states.transform();
+        self.recorder.fill_text(
+            self.fonts, glyphs, text, position, font, size, paint, transform,
+        )
+    }
+
+    /// Draws a paragraph with the top left corner at `position`.
+    pub fn fill_paragraph(
+        &mut self,
+        paragraph: &Paragraph,
+        position: Offset,
+        paint: impl IntoPaint<Key>,
+    ) {
+        let Some(glyphs) = self.glyphs.as_deref_mut() else {
+            return;
+        };
+        let transform = self.states.transform();
+        self.recorder
+            .fill_paragraph(self.fonts, glyphs, paragraph, position, paint, transform);
+    }
+
+    /// Measures a single line of text as drawn by [`Canvas::fill_text`].
+    pub fn measure_text(&self, text: &str, font: FontId, size: f32) -> TextMetrics {
+        crate::measure_text(self.fonts, text, font, size)
+    }
+
+    /// Fonts available for text layout.
+    pub fn fonts(&self) -> &FontStore {
+        self.fonts
+    }
+
     /// Draws a line between the given points using the given paint.
     #[inline]
-    pub fn stroke_line(&mut self, p0: Offset, p1: Offset, paint: impl IntoPaint, stroke: Stroke) {
+    pub fn stroke_line(
+        &mut self,
+        p0: Offset,
+        p1: Offset,
+        paint: impl IntoPaint<Key>,
+        stroke: Stroke,
+    ) {
         self.stroke(paint, stroke, |path| path.line(p0, p1));
     }
 
@@ -103,7 +318,7 @@ impl<'a, Key: Eq + std::hash::Hash> Canvas<'a, Key> {
         &mut self,
         points: &[Offset],
         close: bool,
-        paint: impl IntoPaint,
+        paint: impl IntoPaint<Key>,
         stroke: Stroke,
     ) {
         self.stroke(paint, stroke, |path| path.polyline(points, close))
@@ -114,19 +329,19 @@ impl<'a, Key: Eq + std::hash::Hash> Canvas<'a, Key> {
         &mut self,
         center: Offset,
         radius: f32,
-        paint: impl IntoPaint,
+        paint: impl IntoPaint<Key>,
         stroke: Stroke,
     ) {
         self.stroke(paint, stroke, |path| path.circle(center, radius));
     }
 
     #[inline]
-    pub fn stroke_oval(&mut self, rect: Rect, paint: impl IntoPaint, stroke: Stroke) {
+    pub fn stroke_oval(&mut self, rect: Rect, paint: impl IntoPaint<Key>, stroke: Stroke) {
         self.stroke(paint, stroke, |path| path.oval(rect));
     }
 
     #[inline]
-    pub fn stroke_rect(&mut self, rect: Rect, paint: impl IntoPaint, stroke: Stroke) {
+    pub fn stroke_rect(&mut self, rect: Rect, paint: impl IntoPaint<Key>, stroke: Stroke) {
         self.stroke(paint, stroke, |path| path.rect(rect));
     }
 
@@ -135,72 +350,98 @@ impl<'a, Key: Eq + std::hash::Hash> Canvas<'a, Key> {
         &mut self,
         rect: Rect,
         radius: Rounding,
-        paint: impl IntoPaint,
+        paint: impl IntoPaint<Key>,
         stroke: Stroke,
     ) {
         self.stroke(paint, stroke, |path| path.rrect(rect, radius));
     }
 
     #[inline]
-    pub fn fill_polyline(&mut self, points: &[Offset], rule: FillRule, paint: impl IntoPaint) {
+    pub fn fill_polyline(&mut self, points: &[Offset], rule: FillRule, paint: impl IntoPaint<Key>) {
         self.fill(paint, rule, |path| path.polyline(points, true))
     }
 
     #[inline]
-    pub fn fill_circle(&mut self, center: Offset, radius: f32, paint: impl IntoPaint) {
+    pub fn fill_circle(&mut self, center: Offset, radius: f32, paint: impl IntoPaint<Key>) {
         self.fill_non_zero(paint, |path| path.circle(center, radius));
     }
 
     #[inline]
-    pub fn fill_oval(&mut self, rect: Rect, paint: impl IntoPaint) {
+    pub fn fill_oval(&mut self, rect: Rect, paint: impl IntoPaint<Key>) {
         self.fill_non_zero(paint, |path| path.oval(rect));
     }
 
     #[inline]
-    pub fn fill_rect(&mut self, rect: Rect, paint: impl IntoPaint) {
+    pub fn fill_rect(&mut self, rect: Rect, paint: impl IntoPaint<Key>) {
         self.fill_non_zero(paint, |path| path.rect(rect));
     }
 
     #[inline]
-    pub fn fill_rrect(&mut self, rect: Rect, radius: Rounding, paint: impl IntoPaint) {
+    pub fn fill_rrect(&mut self, rect: Rect, radius: Rounding, paint: impl IntoPaint<Key>) {
         self.fill_non_zero(paint, |path| path.rrect(rect, radius));
     }
 
     #[inline]
-    pub fn fill_non_zero(&mut self, paint: impl IntoPaint, path: impl FnOnce(&mut Path)) {
+    pub fn fill_non_zero(&mut self, paint: impl IntoPaint<Key>, path: impl FnOnce(&mut Path)) {
         self.fill(paint, FillRule::NonZero, path)
     }
 
     #[inline]
-    pub fn fill_even_odd(&mut self, paint: impl IntoPaint, path: impl FnOnce(&mut Path)) {
+    pub fn fill_even_odd(&mut self, paint: impl IntoPaint<Key>, path: impl FnOnce(&mut Path)) {
         self.fill(paint, FillRule::EvenOdd, path)
     }
 
     #[inline]
-    pub fn stroke_path(&mut self, path: &Path, paint: impl IntoPaint, stroke: Stroke) {
+    pub fn stroke_path(&mut self, path: &Path, paint: impl IntoPaint<Key>, stroke: Stroke) {
+        let state = self.states.state();
         self.recorder
-            .stroke(path, paint, stroke, self.states.transform(), true)
+            .stroke(path, paint, stroke, state.transform, state.blend_mode, true)
     }
 
     #[inline]
-    pub fn stroke(&mut self, paint: impl IntoPaint, stroke: Stroke, path: impl FnOnce(&mut Path)) {
+    pub fn stroke(
+        &mut self,
+        paint: impl IntoPaint<Key>,
+        stroke: Stroke,
+        path: impl FnOnce(&mut Path),
+    ) {
         self.path.clear();
         path(&mut self.path);
-        self.recorder
-            .stroke(&self.path, paint, stroke, self.states.transform(), true)
+        let state = self.states.state();
+        self.recorder.stroke(
+            &self.path,
+            paint,
+            stroke,
+            state.transform,
+            state.blend_mode,
+            true,
+        )
     }
 
     #[inline]
-    pub fn fill_path(&mut self, path: &Path, paint: impl IntoPaint, rule: FillRule) {
+    pub fn fill_path(&mut self, path: &Path, paint: impl IntoPaint<Key>, rule: FillRule) {
+        let state = self.states.state();
         self.recorder
-            .fill(path, paint, self.states.transform(), rule, true);
+            .fill(path, paint, state.transform, rule, state.blend_mode, true);
     }
 
     #[inline]
-    pub fn fill(&mut self, paint: impl IntoPaint, rule: FillRule, path: impl FnOnce(&mut Path)) {
+    pub fn fill(
+        &mut self,
+        paint: impl IntoPaint<Key>,
+        rule: FillRule,
+        path: impl FnOnce(&mut Path),
+    ) {
         self.path.clear();
         path(&mut self.path);
-        self.recorder
-            .fill(&self.path, paint, self.states.transform(), rule, true);
+        let state = self.states.state();
+        self.recorder.fill(
+            &self.path,
+            paint,
+            state.transform,
+            rule,
+            state.blend_mode,
+            true,
+        );
     }
 }
@@ -0,0 +1,78 @@
+use crate::{picture::DrawIndexed, FillRule, Rect, Transform};
+
+/// Axis-aligned (in its own space) clip rectangle evaluated in the fragment shader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scissor {
+    pub transform: Transform,
+    pub extent: [f32; 2],
+}
+
+impl Default for Scissor {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl Scissor {
+    /// Scissor that does not clip anything.
+    pub const fn none() -> Self {
+        Self {
+            transform: Transform::identity(),
+            extent: [-1.0, -1.0],
+        }
+    }
+
+    pub fn new(rect: Rect, transform: Transform) -> Self {
+        let center = rect.center();
+        Self {
+            transform: transform * Transform::translate(center.x, center.y),
+            extent: [rect.dx().max(0.0) * 0.5, rect.dy().max(0.0) * 0.5],
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.extent[0] < 0.0
+    }
+
+    /// Intersects the scissor with a rectangle specified in the `transform` space.
+    ///
+    /// If the current scissor is rotated relative to `transform` the result is
+    /// the intersection of `rect` with the bounding box of the current scissor.
+    pub fn intersect(&self, rect: Rect, transform: Transform) -> Self {
+        if self.is_none() {
+            return Self::new(rect, transform);
+        }
+
+        // Transform the current scissor rect into current transform space.
+        // If there is difference in rotation, this will be approximation.
+        let m = transform.inverse() * self.transform;
+        let [ex, ey] = self.extent;
+        let tex = ex * m.sx.abs() + ey * m.shx.abs();
+        let tey = ex * m.shy.abs() + ey * m.sy.abs();
+        let prev = Rect::from_ltrb(m.tx - tex, m.ty - tey, m.tx + tex, m.ty + tey);
+
+        Self::new(Rect::intersect(prev, rect), transform)
+    }
+
+    /// Returns inverse transform, extent and scale as expected by the shader.
+    pub(crate) fn to_uniform(self) -> (Transform, [f32; 2], [f32; 2]) {
+        if self.is_none() {
+            let zero = Transform::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+            return (zero, [1.0, 1.0], [1.0, 1.0]);
+        }
+
+        let Transform {
+            sx, shy, shx, sy, ..
+        } = self.transform;
+        let scale = [sx.hypot(shy), shx.hypot(sy)];
+        (self.transform.inverse(), self.extent, scale)
+    }
+}
+
+/// Stencil clip geometry recorded once and replayed whenever the clip stack is rebuilt.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ClipDraw {
+    pub stencil: DrawIndexed,
+    pub cover: DrawIndexed,
+    pub fill_rule: FillRule,
+}
@@ -478,6 +478,13 @@ impl Transform {
         }
     }
 
+    /// Average length of the transformed unit vectors.
+    pub fn average_scale(&self) -> f32 {
+        let sx = (self.sx * self.sx + self.shx * self.shx).sqrt();
+        let sy = (self.shy * self.shy + self.sy * self.sy).sqrt();
+        (sx + sy) * 0.5
+    }
+
     #[inline]
     pub fn apply<I: Into<[f32; 2]>, F: From<[f32; 2]>>(&self, coord: I) -> F {
         self.apply_impl(coord.into()).into()
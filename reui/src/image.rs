@@ -6,6 +6,12 @@ pub struct ImageBind {
     pub size: wgpu::Extent3d,
 }
 
+/// Registered images drawn by [`crate::Canvas::image`] at their own size.
+pub trait ImageSize<Key> {
+    /// Size of the image in pixels, if it exists.
+    fn image_size(&self, image: &Key) -> Option<(u32, u32)>;
+}
+
 #[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
 pub struct Images<Key> {
     pub images: HashMap<Key, ImageBind>,
@@ -20,6 +26,13 @@ impl<Key: Eq + std::hash::Hash> Index<&Key> for Images<Key> {
     }
 }
 
+impl<Key: Eq + std::hash::Hash> ImageSize<Key> for Images<Key> {
+    fn image_size(&self, image: &Key) -> Option<(u32, u32)> {
+        let size = self.images.get(image)?.size;
+        Some((size.width, size.height))
+    }
+}
+
 impl<Key: Eq + std::hash::Hash> Images<Key> {
     pub fn new(device: &wgpu::Device) -> Self {
         Self {
@@ -0,0 +1,241 @@
+use crate::{picture::DrawIndexed, BlendMode, Images, Rect, TargetDescriptor};
+
+/// Offscreen layer opened by [`crate::Recorder::save_layer`] and not yet composited.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Layer {
+    pub index: usize,
+    pub bounds: Rect,
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    /// Standard deviation of the Gaussian blur applied before compositing, in pixels.
+    pub blur: f32,
+}
+
+/// Blurs a layer in two separable passes through a scratch layer target.
+#[derive(Clone, Copy, Debug)]
+pub struct LayerBlur {
+    /// Layer target the horizontal pass is rendered into.
+    pub scratch: usize,
+    pub horizontal: DrawIndexed,
+    pub vertical: DrawIndexed,
+}
+
+/// Blurs the content of the current layer into the backdrop target.
+///
+/// The layer is blurred horizontally into the scratch target over `source`
+/// and vertically into the backdrop target over `bounds`.
+#[derive(Clone, Copy, Debug)]
+pub struct BackdropFilter {
+    pub backdrop: usize,
+    pub scratch: usize,
+    pub source: Rect,
+    pub bounds: Rect,
+    pub horizontal: DrawIndexed,
+    pub vertical: DrawIndexed,
+}
+
+/// Composites a finished layer into its parent with a quad covering the layer bounds.
+#[derive(Clone, Copy, Debug)]
+pub struct LayerComposite {
+    pub index: usize,
+    pub blend_mode: BlendMode,
+    /// Layer target the parent is copied into for advanced blend modes.
+    pub backdrop: Option<usize>,
+    /// Replaces the parent with straight alpha colors, ignoring `blend_mode`.
+    pub unpremultiply: bool,
+    pub draw: DrawIndexed,
+}
+
+struct LayerTarget<Key> {
+    key: Key,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    multisampled: Option<wgpu::TextureView>,
+    depth_stencil: wgpu::TextureView,
+}
+
+/// Intermediate textures for layers.
+///
+/// Every texture is registered in [`Images`] with [`Images::insert`],
+/// so layers are composited like any other image.
+pub struct LayerTargets<Key> {
+    targets: Vec<LayerTarget<Key>>,
+    target: TargetDescriptor,
+    width: u32,
+    height: u32,
+}
+
+impl<Key> Default for LayerTargets<Key> {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            target: TargetDescriptor::default(),
+            width: 0,
+            height: 0,
+        }
+    }
+}
+
+impl<Key: Clone + Eq + std::hash::Hash> LayerTargets<Key> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes sure there are at least `count` targets of the given size and formats.
+    ///
+    /// `key` is called for every new target with its index.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        images: &mut Images<Key>,
+        target: TargetDescriptor,
+        count: usize,
+        width: u32,
+        height: u32,
+        mut key: impl FnMut(usize) -> Key,
+    ) {
+        if self.target != target || self.width != width || self.height != height {
+            self.target = target;
+            self.width = width;
+            self.height = height;
+
+            let targets = std::mem::take(&mut self.targets);
+            for layer in targets {
+                let layer = Self::create(device, images, layer.key, target, width, height);
+                self.targets.push(layer);
+            }
+        }
+
+        while self.targets.len() < count {
+            let key = key(self.targets.len());
+            let layer = Self::create(device, images, key, target, width, height);
+            self.targets.push(layer);
+        }
+    }
+
+    pub fn target(&self) -> TargetDescriptor {
+        self.target
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn key(&self, index: usize) -> Option<&Key> {
+        self.targets.get(index).map(|target| &target.key)
+    }
+
+    pub fn texture(&self, index: usize) -> Option<&wgpu::Texture> {
+        self.targets.get(index).map(|target| &target.texture)
+    }
+
+    pub fn view(&self, index: usize) -> Option<&wgpu::TextureView> {
+        self.targets.get(index).map(|target| &target.view)
+    }
+
+    /// Color attachment of the layer and the view it is resolved into.
+    ///
+    /// Multisampled layers are resolved after every pass,
+    /// so [`LayerTargets::texture`] and [`LayerTargets::view`] are always up to date.
+    pub fn attachment(
+        &self,
+        index: usize,
+    ) -> Option<(&wgpu::TextureView, Option<&wgpu::TextureView>)> {
+        self.targets
+            .get(index)
+            .map(|target| match &target.multisampled {
+                Some(multisampled) => (multisampled, Some(&target.view)),
+                None => (&target.view, None),
+            })
+    }
+
+    /// Every layer target has its own depth-stencil, since a layer keeps
+    /// its clip while nested layers are rendered.
+    pub fn depth_stencil(&self, index: usize) -> Option<&wgpu::TextureView> {
+        self.targets.get(index).map(|target| &target.depth_stencil)
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        images: &mut Images<Key>,
+        key: Key,
+        target: TargetDescriptor,
+        width: u32,
+        height: u32,
+    ) -> LayerTarget<Key> {
+        let texture = create_texture(device, "reui::Layer", target.color, 1, width, height);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let multisampled = (target.sample_count > 1).then(|| {
+            let label = "reui::LayerMultisampled";
+            let texture = create_texture(
+                device,
+                label,
+                target.color,
+                target.sample_count,
+                width,
+                height,
+            );
+            texture.create_view(&wgpu::TextureViewDescriptor::default())
+        });
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        drop(images.insert(device, key.clone(), &view, size, None));
+
+        let depth_stencil = create_texture(
+            device,
+            "reui::LayerDepthStencil",
+            target.depth_stencil,
+            target.sample_count,
+            width,
+            height,
+        );
+        let depth_stencil = depth_stencil.create_view(&wgpu::TextureViewDescriptor::default());
+
+        LayerTarget {
+            key,
+            texture,
+            view,
+            multisampled,
+            depth_stencil,
+        }
+    }
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    label: &str,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    width: u32,
+    height: u32,
+) -> wgpu::Texture {
+    // Color targets are copied into each other to read the backdrop of advanced blend modes.
+    let usage = if format.has_stencil_aspect() || sample_count > 1 {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+    } else {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::COPY_DST
+    };
+
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    })
+}
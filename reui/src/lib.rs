@@ -12,19 +12,28 @@
     clippy::cast_lossless
 )]
 
+use crate::pass::PassLoad;
+
+pub use ttf_parser;
 pub use wgpu;
 
 mod batch;
 mod canvas;
+mod clip;
 mod color;
 mod geom;
 mod image;
+mod layer;
 mod paint;
+mod pass;
 mod path;
 mod picture;
 mod pipeline;
+mod readback;
 mod renderer;
+mod software;
 mod tessellator;
+mod text;
 
 #[cfg(feature = "bevy")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bevy")))]
@@ -32,58 +41,263 @@ pub mod plugin;
 
 pub use crate::{
     canvas::Canvas,
+    clip::Scissor,
     color::Color,
     geom::{Offset, Rect, Rounding, Transform},
-    image::Images,
+    image::{ImageSize, Images},
+    layer::LayerTargets,
     paint::{
-        BoxGradient, IntoPaint, LineCap, LineJoin, LinearGradient, Paint, RadialGradient, Stroke,
+        BlendMode, BoxGradient, ConicalGradient, Dash, Gradient, GradientShape, GradientStops,
+        ImageFilter, ImagePattern, IntoPaint, LineCap, LineJoin, LinearGradient, Paint,
+        RadialGradient, Spread, Stroke, SweepGradient,
+    },
+    path::{
+        Command, ContourMeasure, FillRule, ParseError, ParseErrorKind, Path, PathMeasure, PathOp,
+        Solidity,
     },
-    path::{Command, FillRule, Path, Solidity},
-    picture::{Picture, Recorder},
-    pipeline::Pipeline,
+    picture::{Picture, Recorder, MAX_BLUR_SIGMA},
+    pipeline::{Pipeline, PipelineCache, TargetDescriptor},
+    readback::{read_texture, Pixels},
     renderer::{Image, Renderer},
+    software::{rasterize, rasterize_into, SoftwareImages, SoftwareRenderer},
+    text::{
+        measure_text, Cluster, Font, FontId, FontMetrics, FontStore, GlyphCache, GlyphEntry,
+        GlyphKey, LineMetrics, Paragraph, ParagraphStyle, PositionedGlyph, TextAlign,
+        TextDirection, TextMetrics,
+    },
 };
 
 pub mod internals {
     pub use crate::{
         batch::{Batch, GpuBatch},
-        canvas::TransformStack,
+        canvas::StateStack,
         image::ImageBind,
+        layer::{BackdropFilter, LayerBlur, LayerComposite},
         path::{PathIter, PathTransformIter},
         picture::DrawCall,
-        pipeline::{Instance, Vertex},
+        pipeline::{DrawPipelines, Instance, Vertex},
         tessellator::{Draw, Tessellator},
     };
 }
 
-pub fn render_pictures<'a>(
+/// Renders pictures into the target.
+///
+/// Passes of every picture are rendered in order, interleaving layer targets and the target.
+/// A layer is copied into the backdrop target wherever advanced blend modes read it.
+/// A multisampled `color_view` is resolved into `resolve_view` after every pass.
+///
+/// Draws outside of layers that read the backdrop are redirected into a root layer,
+/// which starts with the contents of the target and is composited onto it at the end.
+/// It is filled with `clear_color` of the first picture, the target under it is cleared
+/// to transparent. Otherwise it is copied from `target`, the texture of `resolve_view`,
+/// or of `color_view` without multisampling, which needs [`wgpu::TextureUsages::COPY_SRC`].
+/// Without `target` the root layer starts transparent.
+pub fn render_pictures<'a, Key: Clone + Eq + std::hash::Hash>(
     encoder: &'a mut wgpu::CommandEncoder,
     color_view: &'a wgpu::TextureView,
+    resolve_view: Option<&'a wgpu::TextureView>,
+    target: Option<&'a wgpu::Texture>,
     depth_view: &'a wgpu::TextureView,
-    bundles: impl IntoIterator<Item = &'a wgpu::RenderBundle> + 'a,
+    layers: &'a LayerTargets<Key>,
+    pictures: impl IntoIterator<Item = &'a Picture> + 'a,
     clear_color: Option<wgpu::Color>,
     clear: bool,
 ) {
-    let cload = clear_color.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear);
-    let dload = clear.then_some(wgpu::LoadOp::Load).unwrap_or_default();
-    let sload = clear.then_some(wgpu::LoadOp::Load).unwrap_or_default();
+    let mut cload = clear_color.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear);
+    let mut dload = if clear {
+        wgpu::LoadOp::Clear(1.0)
+    } else {
+        wgpu::LoadOp::Load
+    };
+    let mut sload = if clear {
+        wgpu::LoadOp::Clear(0)
+    } else {
+        wgpu::LoadOp::Load
+    };
     let store = true;
 
-    let desc = wgpu::RenderPassDescriptor {
-        label: Some("reui"),
-        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: color_view,
-            resolve_target: None,
-            ops: wgpu::Operations { load: cload, store },
-        })],
-        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-            view: depth_view,
-            depth_ops: Some(wgpu::Operations { load: dload, store }),
-            stencil_ops: Some(wgpu::Operations { load: sload, store }),
-        }),
+    for picture in pictures {
+        // The root layer takes the clear color of the target, so it isn't blended twice.
+        let seeded = picture.passes.iter().any(|pass| pass.seed.is_some());
+        let root_clear = match cload {
+            wgpu::LoadOp::Clear(color) if seeded => {
+                cload = wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT);
+                Some(wgpu::Color {
+                    r: color.r * color.a,
+                    g: color.g * color.a,
+                    b: color.b * color.a,
+                    a: color.a,
+                })
+            }
+            _ => None,
+        };
+
+        for pass in &picture.passes {
+            let Some((index, bounds)) = pass.layer else {
+                let (color, depth, stencil) = match pass.load {
+                    PassLoad::Clear => (cload, dload, sload),
+                    PassLoad::Load | PassLoad::Backdrop(_) => {
+                        (wgpu::LoadOp::Load, wgpu::LoadOp::Load, wgpu::LoadOp::Load)
+                    }
+                };
+
+                let desc = wgpu::RenderPassDescriptor {
+                    label: Some("reui"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: color_view,
+                        resolve_target: resolve_view,
+                        ops: wgpu::Operations { load: color, store },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: depth_view,
+                        depth_ops: Some(wgpu::Operations { load: depth, store }),
+                        stencil_ops: Some(wgpu::Operations {
+                            load: stencil,
+                            store,
+                        }),
+                    }),
+                };
+
+                encoder
+                    .begin_render_pass(&desc)
+                    .execute_bundles(std::iter::once(&pass.draws));
+
+                // Following passes and pictures are drawn over the first one.
+                cload = wgpu::LoadOp::Load;
+                dload = wgpu::LoadOp::Load;
+                sload = wgpu::LoadOp::Load;
+                continue;
+            };
+
+            let (Some((view, resolve_target)), Some(depth)) =
+                (layers.attachment(index), layers.depth_stencil(index))
+            else {
+                continue;
+            };
+
+            let (width, height) = layers.size();
+            let Rect { min, max } = bounds;
+            let x0 = min.x.clamp(0.0, width as f32) as u32;
+            let y0 = min.y.clamp(0.0, height as f32) as u32;
+            let x1 = max.x.clamp(0.0, width as f32) as u32;
+            let y1 = max.y.clamp(0.0, height as f32) as u32;
+
+            if let PassLoad::Backdrop(backdrop) = pass.load {
+                let source = layers.texture(index);
+                let destination = layers.texture(backdrop);
+                if let (Some(source), Some(destination), true) =
+                    (source, destination, x0 < x1 && y0 < y1)
+                {
+                    let origin = wgpu::Origin3d { x: x0, y: y0, z: 0 };
+                    encoder.copy_texture_to_texture(
+                        wgpu::ImageCopyTexture {
+                            origin,
+                            ..source.as_image_copy()
+                        },
+                        wgpu::ImageCopyTexture {
+                            origin,
+                            ..destination.as_image_copy()
+                        },
+                        wgpu::Extent3d {
+                            width: x1 - x0,
+                            height: y1 - y0,
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                }
+            }
+
+            if let Some(seed) = pass.seed {
+                seed_root(encoder, layers, seed, target, root_clear);
+            }
+
+            // Split passes continue the previous one.
+            let (color, depth_load, stencil_load) = match pass.load {
+                PassLoad::Clear => {
+                    let color = wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT);
+                    (color, wgpu::LoadOp::Clear(1.0), wgpu::LoadOp::Clear(0))
+                }
+                PassLoad::Load | PassLoad::Backdrop(_) => {
+                    (wgpu::LoadOp::Load, wgpu::LoadOp::Load, wgpu::LoadOp::Load)
+                }
+            };
+
+            let desc = wgpu::RenderPassDescriptor {
+                label: Some("reui::Layer"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target,
+                    ops: wgpu::Operations { load: color, store },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load,
+                        store,
+                    }),
+                    stencil_ops: Some(wgpu::Operations {
+                        load: stencil_load,
+                        store,
+                    }),
+                }),
+            };
+
+            let mut rpass = encoder.begin_render_pass(&desc);
+            if x0 < x1 && y0 < y1 {
+                rpass.set_scissor_rect(x0, y0, x1 - x0, y1 - y0);
+                rpass.execute_bundles(std::iter::once(&pass.draws));
+            }
+        }
+    }
+}
+
+/// Fills the seed of the root layer with the clear color or a copy of the target.
+fn seed_root<Key: Clone + Eq + std::hash::Hash>(
+    encoder: &mut wgpu::CommandEncoder,
+    layers: &LayerTargets<Key>,
+    seed: usize,
+    target: Option<&wgpu::Texture>,
+    clear_color: Option<wgpu::Color>,
+) {
+    let Some(destination) = layers.texture(seed) else {
+        return;
     };
 
-    encoder.begin_render_pass(&desc).execute_bundles(bundles)
+    let source = target.filter(|source| {
+        let format = source.format().remove_srgb_suffix();
+        format == destination.format().remove_srgb_suffix() && source.sample_count() == 1
+    });
+
+    if let (None, Some(source)) = (clear_color, source) {
+        let (width, height) = layers.size();
+        encoder.copy_texture_to_texture(
+            source.as_image_copy(),
+            destination.as_image_copy(),
+            wgpu::Extent3d {
+                width: width.min(source.width()),
+                height: height.min(source.height()),
+                depth_or_array_layers: 1,
+            },
+        );
+        return;
+    }
+
+    let Some((view, resolve_target)) = layers.attachment(seed) else {
+        return;
+    };
+    let color = clear_color.unwrap_or(wgpu::Color::TRANSPARENT);
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("reui::Seed"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(color),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: None,
+    });
 }
 
 pub fn combine_viewport(width: u32, height: u32) -> [f32; 4] {
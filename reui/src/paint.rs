@@ -1,33 +1,113 @@
-use crate::{internals::Instance, Color, Rect, Transform};
+use crate::{internals::Instance, Color, Rect, Scissor, Transform};
 
-pub trait IntoPaint {
-    fn into_paint(self, transform: Transform) -> Paint;
+pub trait IntoPaint<Key> {
+    fn into_paint(self, transform: Transform) -> Paint<Key>;
 }
 
-#[derive(Clone, Copy)]
-pub struct Paint {
+/// How a gradient is extended outside of its `[0, 1]` range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Spread {
+    /// Uses the color of the closest end.
+    #[default]
+    Pad,
+    /// Restarts the gradient.
+    Repeat,
+    /// Mirrors the gradient on every repetition.
+    Reflect,
+}
+
+/// Shape of a gradient evaluated in the paint space.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GradientShape {
+    /// Rounded box defined by `extent`, `radius` and `feather`.
+    ///
+    /// Linear and radial gradients are special cases of it.
+    #[default]
+    Box,
+    /// Clockwise angle from the positive x axis divided by `span`.
+    Sweep { span: f32 },
+    /// Circles interpolated from the origin to `(distance, 0)`.
+    Conical {
+        distance: f32,
+        start_radius: f32,
+        end_radius: f32,
+    },
+}
+
+#[derive(Clone)]
+pub struct Paint<Key> {
     pub transform: Transform,
     pub extent: [f32; 2],
     pub radius: f32,
     pub feather: f32,
     pub inner_color: Color,
     pub outer_color: Color,
+    /// Image sampled instead of the gradient, `extent` is the size of the image.
+    pub image: Option<Key>,
+    /// Color stops used instead of `inner_color` and `outer_color` if not empty.
+    pub stops: Vec<(f32, Color)>,
+    pub spread: Spread,
+    pub shape: GradientShape,
 }
 
-impl Paint {
-    pub fn to_instance(self, width: f32, fringe: f32, stroke_thr: f32) -> Instance {
+impl<Key> Paint<Key> {
+    pub fn to_instance(
+        &self,
+        width: f32,
+        fringe: f32,
+        stroke_thr: f32,
+        scissor: Scissor,
+    ) -> Instance {
+        let (scissor_mat, scissor_ext, scissor_scale) = scissor.to_uniform();
+
+        // Other shapes reuse the box parameters.
+        let (extent, radius, inv_feather) = match self.shape {
+            GradientShape::Box => (self.extent, self.radius, self.feather.recip()),
+            GradientShape::Sweep { span } => ([span, 0.0], 0.0, 0.0),
+            GradientShape::Conical {
+                distance,
+                start_radius,
+                end_radius,
+            } => ([distance, start_radius], end_radius, 0.0),
+        };
+
         Instance {
             paint_mat: self.transform.inverse(),
 
             inner_color: self.inner_color.into(),
             outer_color: self.outer_color.into(),
 
-            extent: self.extent,
-            radius: self.radius,
-            inv_feather: self.feather.recip(),
+            extent,
+            radius,
+            inv_feather,
 
             stroke_mul: (width + fringe) / fringe * 0.5,
             stroke_thr,
+
+            scissor_mat,
+            scissor_ext,
+            scissor_scale,
+
+            ramp: [0.0, 0.0, 0.0],
+            blend: 0.0,
+        }
+    }
+
+    /// Returns `true` if the paint is drawn with a gradient ramp.
+    pub fn has_ramp(&self) -> bool {
+        self.image.is_none()
+            && (!self.stops.is_empty()
+                || self.spread != Spread::Pad
+                || self.shape != GradientShape::Box)
+    }
+
+    /// Returns color stops of the gradient.
+    pub fn color_stops(&self) -> Vec<(f32, Color)> {
+        if self.stops.is_empty() {
+            vec![(0.0, self.inner_color), (1.0, self.outer_color)]
+        } else {
+            self.stops.clone()
         }
     }
 }
@@ -50,6 +130,177 @@ pub enum LineJoin {
     Miter,
 }
 
+/// Operator used to composite a source over the destination.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum BlendMode {
+    /// Source over the destination.
+    #[default]
+    SrcOver,
+    /// Replaces the destination with the source.
+    Src,
+    /// Clears the destination.
+    Clear,
+    /// Keeps the destination where the source is opaque.
+    DstIn,
+    /// Keeps the destination where the source is transparent.
+    DstOut,
+    /// Keeps the source and the destination where they do not overlap.
+    Xor,
+    /// Inverts, multiplies and inverts again, the result is never darker.
+    Screen,
+    /// Multiplies the source and the destination, the result is never lighter.
+    Multiply,
+    /// Multiplies or screens depending on the destination, keeping its highlights and shadows.
+    Overlay,
+    /// Keeps the darker of the source and the destination.
+    Darken,
+    /// Keeps the lighter of the source and the destination.
+    Lighten,
+}
+
+impl BlendMode {
+    pub const ALL: [Self; 11] = [
+        Self::SrcOver,
+        Self::Src,
+        Self::Clear,
+        Self::DstIn,
+        Self::DstOut,
+        Self::Xor,
+        Self::Screen,
+        Self::Multiply,
+        Self::Overlay,
+        Self::Darken,
+        Self::Lighten,
+    ];
+
+    /// Modes done with fixed-function blending.
+    pub const FIXED: [Self; 7] = [
+        Self::SrcOver,
+        Self::Src,
+        Self::Clear,
+        Self::DstIn,
+        Self::DstOut,
+        Self::Xor,
+        Self::Screen,
+    ];
+
+    /// Returns `true` if the mode blends in the shader and has to read the destination.
+    pub const fn is_advanced(self) -> bool {
+        self as u8 >= Self::Multiply as u8
+    }
+
+    /// Blend state for a premultiplied source.
+    ///
+    /// Advanced modes replace the destination with the result blended in the shader.
+    pub(crate) fn blend_state(self) -> wgpu::BlendState {
+        use wgpu::BlendFactor::{
+            One, OneMinusDstAlpha, OneMinusSrc, OneMinusSrcAlpha, SrcAlpha, Zero,
+        };
+
+        let (src, dst) = match self {
+            Self::SrcOver => (One, OneMinusSrcAlpha),
+            Self::Src => (One, Zero),
+            Self::Clear => (Zero, Zero),
+            Self::DstIn => (Zero, SrcAlpha),
+            Self::DstOut => (Zero, OneMinusSrcAlpha),
+            Self::Xor => (OneMinusDstAlpha, OneMinusSrcAlpha),
+            Self::Screen => (One, OneMinusSrc),
+            Self::Multiply | Self::Overlay | Self::Darken | Self::Lighten => (One, Zero),
+        };
+
+        let alpha_dst = if dst == OneMinusSrc {
+            OneMinusSrcAlpha
+        } else {
+            dst
+        };
+
+        wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: src,
+                dst_factor: dst,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent {
+                src_factor: src,
+                dst_factor: alpha_dst,
+                operation: wgpu::BlendOperation::Add,
+            },
+        }
+    }
+}
+
+/// Filter applied to content already drawn under a region by [`crate::Canvas::backdrop_filter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFilter {
+    /// Gaussian blur with the standard deviation in local units.
+    Blur(f32),
+}
+
+/// Dash pattern of alternating on and off lengths with a phase offset into it.
+///
+/// Odd patterns are repeated twice as in SVG, so `[4.0]` means `[4.0, 4.0]`.
+/// An empty pattern or a pattern without a positive length is solid.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Dash {
+    intervals: [f32; Self::MAX],
+    len: usize,
+    pub phase: f32,
+}
+
+impl Dash {
+    /// Maximum number of intervals after repeating an odd pattern.
+    pub const MAX: usize = 16;
+
+    pub fn new(pattern: &[f32], phase: f32) -> Self {
+        let mut dash = Self {
+            phase,
+            ..Self::default()
+        };
+
+        if pattern.iter().any(|&len| !(len >= 0.0 && len.is_finite()))
+            || pattern.iter().sum::<f32>() <= 0.0
+        {
+            return dash;
+        }
+
+        let repeat = if pattern.len() % 2 == 1 { 2 } else { 1 };
+        for &len in pattern.iter().cycle().take(pattern.len() * repeat) {
+            if dash.len == Self::MAX {
+                break;
+            }
+            dash.intervals[dash.len] = len;
+            dash.len += 1;
+        }
+
+        // Truncation may leave an unpaired on interval.
+        dash.len -= dash.len % 2;
+        dash
+    }
+
+    pub fn intervals(&self) -> &[f32] {
+        &self.intervals[..self.len]
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Length of a single repetition of the pattern.
+    pub fn length(&self) -> f32 {
+        self.intervals().iter().sum()
+    }
+
+    pub(crate) fn scale(self, factor: f32) -> Self {
+        let mut dash = self;
+        for len in &mut dash.intervals[..dash.len] {
+            *len *= factor;
+        }
+        dash.phase *= factor;
+        dash
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Stroke {
     pub start: LineCap,
@@ -57,6 +308,7 @@ pub struct Stroke {
     pub join: LineJoin,
     pub miter: f32,
     pub width: f32,
+    pub dash: Dash,
 }
 
 impl Stroke {
@@ -86,6 +338,12 @@ impl Stroke {
     pub fn stroke_width(self, width: f32) -> Self {
         Self { width, ..self }
     }
+
+    /// Dashes the stroke with alternating on and off lengths, starting `phase` into the pattern.
+    pub fn dash(self, pattern: &[f32], phase: f32) -> Self {
+        let dash = Dash::new(pattern, phase);
+        Self { dash, ..self }
+    }
 }
 
 impl Default for Stroke {
@@ -96,12 +354,13 @@ impl Default for Stroke {
             join: LineJoin::Miter,
             miter: 2.4,
             width: 1.0,
+            dash: Dash::default(),
         }
     }
 }
 
-impl IntoPaint for Color {
-    fn into_paint(self, transform: Transform) -> Paint {
+impl<Key> IntoPaint<Key> for Color {
+    fn into_paint(self, transform: Transform) -> Paint<Key> {
         Paint {
             transform,
             extent: [0.0, 0.0],
@@ -109,36 +368,82 @@ impl IntoPaint for Color {
             feather: 1.0,
             inner_color: self,
             outer_color: self,
+            image: None,
+            stops: Vec::new(),
+            spread: Spread::Pad,
+            shape: GradientShape::Box,
+        }
+    }
+}
+
+/// Color stops of a gradient and how it is extended past them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GradientStops<'a> {
+    /// Stops at offsets in `[0, 1]`, `inner` and `outer` colors are used if empty.
+    pub stops: &'a [(f32, Color)],
+    pub spread: Spread,
+}
+
+/// Builders shared by gradients with color stops.
+pub trait Gradient<'a>: Sized {
+    /// Returns the `inner` and `outer` colors and the stops of the gradient.
+    fn colors_mut(&mut self) -> (&mut Color, &mut Color, &mut GradientStops<'a>);
+
+    /// Replaces `inner` and `outer` colors with color stops at offsets in `[0, 1]`.
+    ///
+    /// `inner` and `outer` are set to the first and the last stop,
+    /// they are still used where ramps are not supported.
+    fn stops(mut self, stops: &'a [(f32, Color)]) -> Self {
+        let (inner, outer, gradient) = self.colors_mut();
+        if let (Some(&(_, first)), Some(&(_, last))) = (stops.first(), stops.last()) {
+            *inner = first;
+            *outer = last;
         }
+        gradient.stops = stops;
+        self
+    }
+
+    fn spread(mut self, spread: Spread) -> Self {
+        self.colors_mut().2.spread = spread;
+        self
     }
 }
 
 #[derive(Clone, Copy)]
-pub struct LinearGradient {
+pub struct LinearGradient<'a> {
     pub from: [f32; 2],
     pub to: [f32; 2],
     pub inner: Color,
     pub outer: Color,
+    pub stops: GradientStops<'a>,
 }
 
-impl LinearGradient {
+impl LinearGradient<'_> {
     pub fn new(from: [f32; 2], to: [f32; 2], inner: Color, outer: Color) -> Self {
         Self {
             from,
             to,
             inner,
             outer,
+            stops: GradientStops::default(),
         }
     }
 }
 
-impl IntoPaint for LinearGradient {
-    fn into_paint(self, transform: Transform) -> Paint {
+impl<'a> Gradient<'a> for LinearGradient<'a> {
+    fn colors_mut(&mut self) -> (&mut Color, &mut Color, &mut GradientStops<'a>) {
+        (&mut self.inner, &mut self.outer, &mut self.stops)
+    }
+}
+
+impl<Key> IntoPaint<Key> for LinearGradient<'_> {
+    fn into_paint(self, transform: Transform) -> Paint<Key> {
         let Self {
             from,
             to,
             inner,
             outer,
+            stops: GradientStops { stops, spread },
         } = self;
         let [sx, sy] = from;
         let [ex, ey] = to;
@@ -165,20 +470,25 @@ impl IntoPaint for LinearGradient {
             feather: d.max(1.0),
             inner_color: inner,
             outer_color: outer,
+            image: None,
+            stops: stops.to_vec(),
+            spread,
+            shape: GradientShape::Box,
         }
     }
 }
 
 #[derive(Clone, Copy)]
-pub struct BoxGradient {
+pub struct BoxGradient<'a> {
     pub rect: Rect,
     pub radius: f32,
     pub feather: f32,
     pub inner: Color,
     pub outer: Color,
+    pub stops: GradientStops<'a>,
 }
 
-impl BoxGradient {
+impl BoxGradient<'_> {
     pub fn new(rect: Rect, radius: f32, feather: f32, inner: Color, outer: Color) -> Self {
         Self {
             rect,
@@ -186,18 +496,26 @@ impl BoxGradient {
             feather,
             inner,
             outer,
+            stops: GradientStops::default(),
         }
     }
 }
 
-impl IntoPaint for BoxGradient {
-    fn into_paint(self, transform: Transform) -> Paint {
+impl<'a> Gradient<'a> for BoxGradient<'a> {
+    fn colors_mut(&mut self) -> (&mut Color, &mut Color, &mut GradientStops<'a>) {
+        (&mut self.inner, &mut self.outer, &mut self.stops)
+    }
+}
+
+impl<Key> IntoPaint<Key> for BoxGradient<'_> {
+    fn into_paint(self, transform: Transform) -> Paint<Key> {
         let Self {
             rect,
             radius,
             feather,
             inner,
             outer,
+            stops: GradientStops { stops, spread },
         } = self;
         let center = rect.center();
         Paint {
@@ -207,20 +525,25 @@ impl IntoPaint for BoxGradient {
             feather: feather.max(1.0),
             inner_color: inner,
             outer_color: outer,
+            image: None,
+            stops: stops.to_vec(),
+            spread,
+            shape: GradientShape::Box,
         }
     }
 }
 
 #[derive(Clone, Copy)]
-pub struct RadialGradient {
+pub struct RadialGradient<'a> {
     pub center: [f32; 2],
     pub inr: f32,
     pub outr: f32,
     pub inner: Color,
     pub outer: Color,
+    pub stops: GradientStops<'a>,
 }
 
-impl RadialGradient {
+impl RadialGradient<'_> {
     pub fn new(center: [f32; 2], inr: f32, outr: f32, inner: Color, outer: Color) -> Self {
         Self {
             center,
@@ -228,18 +551,26 @@ impl RadialGradient {
             outr,
             inner,
             outer,
+            stops: GradientStops::default(),
         }
     }
 }
 
-impl IntoPaint for RadialGradient {
-    fn into_paint(self, transform: Transform) -> Paint {
+impl<'a> Gradient<'a> for RadialGradient<'a> {
+    fn colors_mut(&mut self) -> (&mut Color, &mut Color, &mut GradientStops<'a>) {
+        (&mut self.inner, &mut self.outer, &mut self.stops)
+    }
+}
+
+impl<Key> IntoPaint<Key> for RadialGradient<'_> {
+    fn into_paint(self, transform: Transform) -> Paint<Key> {
         let Self {
             center,
             inr,
             outr,
             inner,
             outer,
+            stops: GradientStops { stops, spread },
         } = self;
         let radius = (inr + outr) * 0.5;
         Paint {
@@ -249,6 +580,203 @@ impl IntoPaint for RadialGradient {
             feather: (outr - inr).max(1.0),
             inner_color: inner,
             outer_color: outer,
+            image: None,
+            stops: stops.to_vec(),
+            spread,
+            shape: GradientShape::Box,
+        }
+    }
+}
+
+/// Colors swept clockwise around the center from `start_angle` to `end_angle`.
+#[derive(Clone, Copy)]
+pub struct SweepGradient<'a> {
+    pub center: [f32; 2],
+    pub start_angle: f32,
+    pub end_angle: f32,
+    pub inner: Color,
+    pub outer: Color,
+    pub stops: GradientStops<'a>,
+}
+
+impl SweepGradient<'_> {
+    pub fn new(
+        center: [f32; 2],
+        start_angle: f32,
+        end_angle: f32,
+        inner: Color,
+        outer: Color,
+    ) -> Self {
+        Self {
+            center,
+            start_angle,
+            end_angle,
+            inner,
+            outer,
+            stops: GradientStops::default(),
+        }
+    }
+}
+
+impl<'a> Gradient<'a> for SweepGradient<'a> {
+    fn colors_mut(&mut self) -> (&mut Color, &mut Color, &mut GradientStops<'a>) {
+        (&mut self.inner, &mut self.outer, &mut self.stops)
+    }
+}
+
+impl<Key> IntoPaint<Key> for SweepGradient<'_> {
+    fn into_paint(self, transform: Transform) -> Paint<Key> {
+        let Self {
+            center,
+            start_angle,
+            end_angle,
+            inner,
+            outer,
+            stops: GradientStops { stops, spread },
+        } = self;
+        let [cx, cy] = center;
+        let span = end_angle - start_angle;
+        Paint {
+            transform: transform * Transform::translate(cx, cy) * Transform::rotate(start_angle),
+            extent: [0.0, 0.0],
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: inner,
+            outer_color: outer,
+            image: None,
+            stops: stops.to_vec(),
+            spread,
+            shape: GradientShape::Sweep { span },
+        }
+    }
+}
+
+/// Two-point conical gradient, as `createRadialGradient` in SVG and canvas.
+///
+/// Colors are interpolated between the start and the end circle.
+/// A start circle with zero radius is the focal point.
+#[derive(Clone, Copy)]
+pub struct ConicalGradient<'a> {
+    pub start: [f32; 2],
+    pub start_radius: f32,
+    pub end: [f32; 2],
+    pub end_radius: f32,
+    pub inner: Color,
+    pub outer: Color,
+    pub stops: GradientStops<'a>,
+}
+
+impl ConicalGradient<'_> {
+    pub fn new(
+        start: [f32; 2],
+        start_radius: f32,
+        end: [f32; 2],
+        end_radius: f32,
+        inner: Color,
+        outer: Color,
+    ) -> Self {
+        Self {
+            start,
+            start_radius,
+            end,
+            end_radius,
+            inner,
+            outer,
+            stops: GradientStops::default(),
+        }
+    }
+}
+
+impl<'a> Gradient<'a> for ConicalGradient<'a> {
+    fn colors_mut(&mut self) -> (&mut Color, &mut Color, &mut GradientStops<'a>) {
+        (&mut self.inner, &mut self.outer, &mut self.stops)
+    }
+}
+
+impl<Key> IntoPaint<Key> for ConicalGradient<'_> {
+    fn into_paint(self, transform: Transform) -> Paint<Key> {
+        let Self {
+            start,
+            start_radius,
+            end,
+            end_radius,
+            inner,
+            outer,
+            stops: GradientStops { stops, spread },
+        } = self;
+        let [sx, sy] = start;
+        let [ex, ey] = end;
+
+        // Align the x axis with the line between centers.
+        let (dx, dy) = (ex - sx, ey - sy);
+        let distance = dx.hypot(dy);
+        let angle = dy.atan2(dx);
+
+        Paint {
+            transform: transform * Transform::translate(sx, sy) * Transform::rotate(angle),
+            extent: [0.0, 0.0],
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: inner,
+            outer_color: outer,
+            image: None,
+            stops: stops.to_vec(),
+            spread,
+            shape: GradientShape::Conical {
+                distance,
+                start_radius,
+                end_radius,
+            },
+        }
+    }
+}
+
+/// Image repeated over the filled area, like `nvgImagePattern`.
+///
+/// The image is sampled with its own sampler, so tiling follows its address mode.
+#[derive(Clone, Copy)]
+pub struct ImagePattern<Key> {
+    pub image: Key,
+    pub origin: [f32; 2],
+    pub size: [f32; 2],
+    pub angle: f32,
+    pub alpha: f32,
+}
+
+impl<Key> ImagePattern<Key> {
+    pub fn new(image: Key, origin: [f32; 2], size: [f32; 2], angle: f32, alpha: f32) -> Self {
+        Self {
+            image,
+            origin,
+            size,
+            angle,
+            alpha,
+        }
+    }
+}
+
+impl<Key> IntoPaint<Key> for ImagePattern<Key> {
+    fn into_paint(self, transform: Transform) -> Paint<Key> {
+        let Self {
+            image,
+            origin,
+            size,
+            angle,
+            alpha,
+        } = self;
+        let [ox, oy] = origin;
+        let tint = Color::new(1.0, 1.0, 1.0, alpha);
+        Paint {
+            transform: transform * Transform::translate(ox, oy) * Transform::rotate(angle),
+            extent: size,
+            radius: 0.0,
+            feather: 1.0,
+            inner_color: tint,
+            outer_color: tint,
+            image: Some(image),
+            stops: Vec::new(),
+            spread: Spread::Pad,
+            shape: GradientShape::Box,
         }
     }
 }
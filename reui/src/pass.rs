@@ -0,0 +1,397 @@
+use crate::{
+    internals::DrawCall,
+    layer::{BackdropFilter, LayerBlur, LayerComposite},
+    picture::DrawIndexed,
+    BlendMode, Pipeline, Rect,
+};
+
+/// How a render pass starts.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum PassLoad {
+    /// Clears the target, the root layer is seeded from the contents of the target.
+    Clear,
+    /// Continues the previous pass into the same target.
+    Load,
+    /// Copies the layer into the given layer target and continues the previous pass.
+    Backdrop(usize),
+}
+
+/// Render pass recorded into the target or a layer target.
+pub(crate) struct Pass<Draws> {
+    /// Layer target with its bounds, `None` for the target of the picture.
+    pub layer: Option<(usize, Rect)>,
+    /// Draws finished by the [`PassEncoder`], a render bundle for [`crate::Picture`].
+    pub draws: Draws,
+    pub load: PassLoad,
+    /// Layer target the root layer is seeded from in its first pass,
+    /// filled with the contents of the target before the pass.
+    pub seed: Option<usize>,
+}
+
+/// Records the draws of a single pass, implemented by every backend.
+///
+/// A new encoder starts with the gradient ramps bound as the image and no backdrop.
+pub(crate) trait PassEncoder {
+    /// Reference to a pipeline of [`Pipeline`].
+    type Pipeline: Copy;
+    /// Reference to an image key.
+    type Image: Copy;
+    type Draws;
+
+    fn bind_image(&mut self, image: Self::Image);
+    fn bind_ramps(&mut self);
+    /// Binds the layer target as the image, returns `false` if there is no such target.
+    fn bind_layer(&mut self, index: usize) -> bool;
+    /// Binds the layer target read by the following advanced blend draws.
+    fn bind_backdrop(&mut self, index: usize);
+    fn draw(&mut self, pipeline: Self::Pipeline, call: DrawIndexed);
+    fn finish(self) -> Self::Draws;
+}
+
+/// Draws of the target or a layer, split into passes wherever
+/// a nested layer is composited or the backdrop is read.
+struct Stream<Encoder: PassEncoder> {
+    layer: Option<(usize, Rect)>,
+    root: Option<LayerComposite>,
+    encoder: Encoder,
+    load: PassLoad,
+    empty: bool,
+    passes: Vec<Pass<Encoder::Draws>>,
+}
+
+impl<Encoder: PassEncoder> Stream<Encoder> {
+    fn new(layer: Option<(usize, Rect)>, encoder: Encoder) -> Self {
+        Self {
+            layer,
+            root: None,
+            encoder,
+            load: PassLoad::Clear,
+            empty: true,
+            passes: Vec::new(),
+        }
+    }
+
+    fn draw(&mut self, pipeline: Encoder::Pipeline, call: DrawIndexed) {
+        self.encoder.draw(pipeline, call);
+        self.empty = false;
+    }
+
+    /// Finishes the current pass and starts a new one.
+    fn split(&mut self, encoder: Encoder, load: PassLoad) {
+        let encoder = std::mem::replace(&mut self.encoder, encoder);
+        let load = std::mem::replace(&mut self.load, load);
+        let empty = std::mem::replace(&mut self.empty, true);
+        let pass = Self::pass(self.layer, load, empty, self.seed(), encoder);
+        self.passes.extend(pass);
+    }
+
+    /// Finishes the last pass and returns all of them with passes of nested layers.
+    fn finish(self) -> Vec<Pass<Encoder::Draws>> {
+        let seed = self.seed();
+        let Self {
+            layer,
+            encoder,
+            load,
+            empty,
+            mut passes,
+            ..
+        } = self;
+        passes.extend(Self::pass(layer, load, empty, seed, encoder));
+        passes
+    }
+
+    /// The seed of the root layer, only for its first pass.
+    fn seed(&self) -> Option<usize> {
+        let root = self.root.filter(|_| self.passes.is_empty());
+        root.and_then(|root| root.backdrop)
+    }
+
+    fn pass(
+        layer: Option<(usize, Rect)>,
+        load: PassLoad,
+        empty: bool,
+        seed: Option<usize>,
+        encoder: Encoder,
+    ) -> Option<Pass<Encoder::Draws>> {
+        // An empty pass still has to clear the target.
+        if empty && !matches!(load, PassLoad::Clear) {
+            return None;
+        }
+
+        Some(Pass {
+            layer,
+            draws: encoder.finish(),
+            load,
+            seed,
+        })
+    }
+}
+
+/// Splits draw calls into passes in the order they have to be rendered, see [`crate::Picture::new`].
+///
+/// The root layer covers the whole layer target of the given size.
+pub(crate) fn passes<'a, Key, P, Encoder>(
+    calls: &'a [DrawCall<Key>],
+    pipeline: &'a Pipeline<P>,
+    (width, height): (u32, u32),
+    encoder: impl Fn() -> Encoder,
+) -> Vec<Pass<Encoder::Draws>>
+where
+    Encoder: PassEncoder<Pipeline = &'a P, Image = &'a Key>,
+{
+    let mut stack = vec![Stream::new(None, encoder())];
+    let mut blend_mode = BlendMode::SrcOver;
+
+    let mut calls = calls;
+    if let Some(DrawCall::Root(composite)) = calls.first() {
+        let bounds = Rect::from_size(width as f32, height as f32);
+        let mut root = Stream::new(Some((composite.index, bounds)), encoder());
+        root.root = Some(*composite);
+        // Advanced blend modes read the root layer instead of the target, so it starts as a copy.
+        if let Some(seed) = composite.backdrop {
+            if root.encoder.bind_layer(seed) {
+                root.draw(pipeline.layer(BlendMode::Src), composite.draw);
+            }
+            root.encoder.bind_ramps();
+        }
+        stack.push(root);
+        calls = &calls[1..];
+    }
+
+    for call in calls {
+        let stream = stack.last_mut().unwrap();
+        match call {
+            &DrawCall::BeginLayer { index, bounds } => {
+                stack.push(Stream::new(Some((index, bounds)), encoder()));
+            }
+            DrawCall::EndLayer(composite) => {
+                // The main stream and the root layer stay at the bottom of the stack.
+                if stream.root.is_some() || stack.len() < 2 {
+                    continue;
+                }
+                let layer = stack.pop().unwrap();
+                let parent = stack.last_mut().unwrap();
+                composite_layer(parent, layer, composite, pipeline, &encoder);
+            }
+
+            &DrawCall::Blend(mode) => blend_mode = mode,
+            &DrawCall::Backdrop(index) => {
+                stream.split(encoder(), PassLoad::Backdrop(index));
+                stream.encoder.bind_backdrop(index);
+            }
+            DrawCall::Blur(blur) => blur_layer(stream, blur, pipeline, &encoder),
+            DrawCall::Filter(filter) => filter_layer(stream, filter, pipeline, &encoder),
+            DrawCall::Root(..) => {}
+
+            _ => encode(stream, call, pipeline, blend_mode),
+        }
+    }
+
+    // Layers that are never ended are dropped, the root layer is composited.
+    while stack.len() > 1 {
+        let layer = stack.pop().unwrap();
+        if let Some(composite) = layer.root {
+            // The seed of the root layer is not a backdrop to composite it with.
+            let composite = LayerComposite {
+                backdrop: None,
+                ..composite
+            };
+            let parent = stack.last_mut().unwrap();
+            composite_layer(parent, layer, &composite, pipeline, &encoder);
+        }
+    }
+
+    stack.pop().unwrap().finish()
+}
+
+/// Draws the finished layer into its parent after the passes of the layer.
+fn composite_layer<'a, P, Encoder: PassEncoder<Pipeline = &'a P>>(
+    parent: &mut Stream<Encoder>,
+    layer: Stream<Encoder>,
+    composite: &LayerComposite,
+    pipeline: &'a Pipeline<P>,
+    encoder: &impl Fn() -> Encoder,
+) {
+    let load = composite
+        .backdrop
+        .map_or(PassLoad::Load, PassLoad::Backdrop);
+    parent.split(encoder(), load);
+    parent.passes.extend(layer.finish());
+
+    if let Some(index) = composite.backdrop {
+        parent.encoder.bind_backdrop(index);
+    }
+    if parent.encoder.bind_layer(composite.index) {
+        let layer = if composite.unpremultiply {
+            &pipeline.layer_unpremultiply
+        } else {
+            pipeline.layer(composite.blend_mode)
+        };
+        parent.draw(layer, composite.draw);
+    }
+}
+
+/// Blurs the layer horizontally into the scratch target and vertically back.
+fn blur_layer<'a, P, Encoder: PassEncoder<Pipeline = &'a P>>(
+    stream: &mut Stream<Encoder>,
+    blur: &LayerBlur,
+    pipeline: &'a Pipeline<P>,
+    encoder: &impl Fn() -> Encoder,
+) {
+    let Some((index, bounds)) = stream.layer else {
+        return;
+    };
+
+    stream.split(encoder(), PassLoad::Clear);
+
+    let target = (blur.scratch, bounds);
+    let pass = blur_pass(index, target, blur.horizontal, pipeline, encoder());
+    stream.passes.push(pass);
+
+    if stream.encoder.bind_layer(blur.scratch) {
+        stream.draw(&pipeline.blur, blur.vertical);
+    }
+}
+
+/// Blurs the layer into the backdrop target and binds it for the following filter draws.
+fn filter_layer<'a, P, Encoder: PassEncoder<Pipeline = &'a P>>(
+    stream: &mut Stream<Encoder>,
+    filter: &BackdropFilter,
+    pipeline: &'a Pipeline<P>,
+    encoder: &impl Fn() -> Encoder,
+) {
+    // The target itself can't be read, the recorder redirects it into the root layer.
+    let Some((index, _)) = stream.layer else {
+        return;
+    };
+
+    stream.split(encoder(), PassLoad::Load);
+
+    let target = (filter.scratch, filter.source);
+    let pass = blur_pass(index, target, filter.horizontal, pipeline, encoder());
+    stream.passes.push(pass);
+
+    let target = (filter.backdrop, filter.bounds);
+    let pass = blur_pass(filter.scratch, target, filter.vertical, pipeline, encoder());
+    stream.passes.push(pass);
+
+    // Following filter draws read the backdrop target.
+    stream.encoder.bind_layer(filter.backdrop);
+}
+
+/// Pass of the separable blur from the source layer target into the cleared target.
+fn blur_pass<'a, P, Encoder: PassEncoder<Pipeline = &'a P>>(
+    source: usize,
+    target: (usize, Rect),
+    call: DrawIndexed,
+    pipeline: &'a Pipeline<P>,
+    mut encoder: Encoder,
+) -> Pass<Encoder::Draws> {
+    if encoder.bind_layer(source) {
+        encoder.draw(&pipeline.blur, call);
+    }
+    Pass {
+        layer: Some(target),
+        draws: encoder.finish(),
+        load: PassLoad::Clear,
+        seed: None,
+    }
+}
+
+/// Records the draws of a call with the pipelines used for it.
+fn encode<'a, Key, P, Encoder>(
+    stream: &mut Stream<Encoder>,
+    call: &'a DrawCall<Key>,
+    pipeline: &'a Pipeline<P>,
+    blend_mode: BlendMode,
+) where
+    Encoder: PassEncoder<Pipeline = &'a P, Image = &'a Key>,
+{
+    let draws = pipeline.draws(blend_mode);
+    match call {
+        DrawCall::BindImage(image) => stream.encoder.bind_image(image),
+        DrawCall::BindRamps => stream.encoder.bind_ramps(),
+
+        &DrawCall::Convex(draw) => stream.draw(&draws.convex, draw),
+        &DrawCall::FilterConvex(draw) => {
+            let [clear, filter] = &pipeline.filter_convex;
+            stream.draw(clear, draw);
+            stream.draw(filter, draw);
+        }
+        &DrawCall::FilterFringes(draw) => {
+            let [clear, filter] = &pipeline.filter_fringes;
+            stream.draw(clear, draw);
+            stream.draw(filter, draw);
+        }
+        &DrawCall::FilterQuad(draw) => {
+            let [clear, filter] = &pipeline.filter_quad;
+            stream.draw(clear, draw);
+            stream.draw(filter, draw);
+        }
+        &DrawCall::ConvexSimple(draw) => stream.draw(&draws.convex_simple, draw),
+        &DrawCall::Stencil(draw) => stream.draw(&pipeline.fill_stencil, draw),
+        &DrawCall::QuadNonZero(draw) => stream.draw(&draws.fill_quad_non_zero, draw),
+        &DrawCall::QuadEvenOdd(draw) => stream.draw(&draws.fill_quad_even_odd, draw),
+        &DrawCall::FringesNonZero(draw) => stream.draw(&draws.fringes_non_zero, draw),
+        &DrawCall::FringesEvenOdd(draw) => stream.draw(&draws.fringes_even_odd, draw),
+        &DrawCall::ImagePremultiplied(draw) => stream.draw(&pipeline.premultiplied, draw),
+        &DrawCall::ImageUnmultiplied(draw) => stream.draw(&pipeline.unmultiplied, draw),
+        &DrawCall::ImageFont(draw) => stream.draw(&pipeline.font, draw),
+
+        &DrawCall::PatternConvex(draw) => stream.draw(&draws.pattern_convex, draw),
+        &DrawCall::PatternFringesNonZero(draw) => {
+            stream.draw(&draws.pattern_fringes_non_zero, draw)
+        }
+        &DrawCall::PatternFringesEvenOdd(draw) => {
+            stream.draw(&draws.pattern_fringes_even_odd, draw)
+        }
+        &DrawCall::PatternQuadNonZero(draw) => stream.draw(&draws.pattern_quad_non_zero, draw),
+        &DrawCall::PatternQuadEvenOdd(draw) => stream.draw(&draws.pattern_quad_even_odd, draw),
+
+        &DrawCall::ClipStencil(draw) => stream.draw(&pipeline.fill_stencil, draw),
+        &DrawCall::ClipNonZero(draw) => {
+            stream.draw(&pipeline.clip_non_zero, draw);
+            stream.draw(&pipeline.clip_clear, draw);
+        }
+        &DrawCall::ClipEvenOdd(draw) => {
+            stream.draw(&pipeline.clip_even_odd, draw);
+            stream.draw(&pipeline.clip_clear, draw);
+        }
+        &DrawCall::ClipReset(draw) => stream.draw(&pipeline.clip_reset, draw),
+
+        &DrawCall::Stroke {
+            start,
+            end,
+            base_vertex,
+            instance,
+        } => {
+            let draw = DrawIndexed::new(start, end, base_vertex, instance);
+            stream.draw(&draws.stroke_base, draw);
+            let draw = DrawIndexed::new(start, end, base_vertex, instance + 1);
+            stream.draw(&draws.fringes_non_zero, draw);
+            let draw = DrawIndexed::new(start, end, base_vertex, 0);
+            stream.draw(&pipeline.stroke_stencil, draw);
+        }
+        &DrawCall::PatternStroke {
+            start,
+            end,
+            base_vertex,
+            instance,
+        } => {
+            let draw = DrawIndexed::new(start, end, base_vertex, instance);
+            stream.draw(&draws.pattern_stroke, draw);
+            let draw = DrawIndexed::new(start, end, base_vertex, instance + 1);
+            stream.draw(&draws.pattern_fringes_non_zero, draw);
+            let draw = DrawIndexed::new(start, end, base_vertex, 0);
+            stream.draw(&pipeline.stroke_stencil, draw);
+        }
+
+        DrawCall::BeginLayer { .. }
+        | DrawCall::EndLayer(..)
+        | DrawCall::Blend(..)
+        | DrawCall::Backdrop(..)
+        | DrawCall::Blur(..)
+        | DrawCall::Filter(..)
+        | DrawCall::Root(..) => {}
+    }
+}
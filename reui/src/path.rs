@@ -1,41 +1,41 @@
-use crate::{Offset, Rect, Rounding, Transform};
+mod measure;
+mod op;
+mod stroke;
+mod svg;
+
+pub use self::{
+    measure::{ContourMeasure, PathMeasure},
+    op::PathOp,
+    svg::{ParseError, ParseErrorKind},
+};
 
-// Length proportional to radius of a cubic bezier handle for 90deg arcs.
-const KAPPA90: f32 = 0.552_284_8; // 0.5522847493
+use crate::{Offset, Rect, Rounding, Transform};
+use std::f32::consts::FRAC_1_SQRT_2;
 
 /// The fill rule used when filling paths: `EvenOdd`, `NonZero`.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 #[repr(u32)]
 pub enum FillRule {
+    #[default]
     NonZero,
     EvenOdd,
 }
 
-impl Default for FillRule {
-    fn default() -> Self {
-        Self::NonZero
-    }
-}
-
 /// Used to specify Solid/Hole when adding shapes to a path.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum Solidity {
+    #[default]
     Solid,
     Hole,
 }
 
-impl Default for Solidity {
-    fn default() -> Self {
-        Self::Solid
-    }
-}
-
 #[derive(Copy, Clone, Debug)]
 #[repr(u8)]
 enum Raw {
     MoveTo,
     LineTo,
     QuadTo,
+    ConicTo,
     CubicTo,
     Solid,
     Hole,
@@ -46,7 +46,7 @@ impl Raw {
     fn num_points(self) -> usize {
         match self {
             Self::MoveTo | Self::LineTo => 1,
-            Self::QuadTo => 2,
+            Self::QuadTo | Self::ConicTo => 2,
             Self::CubicTo => 3,
             Self::Solid | Self::Hole | Self::Close => 0,
         }
@@ -59,45 +59,18 @@ pub enum Command {
     MoveTo(Offset),
     LineTo(Offset),
     QuadTo(Offset, Offset),
+    /// Rational quadratic Bézier curve with the weight of the control point.
+    ConicTo(Offset, Offset, f32),
     CubicTo(Offset, Offset, Offset),
     Solid,
     Hole,
     Close,
 }
 
-impl Command {
-    #[inline]
-    fn to_raw(self, array: &mut [Offset; 3]) -> (Raw, usize) {
-        match self {
-            Command::MoveTo(p) => {
-                array[0] = p;
-                (Raw::MoveTo, 1)
-            }
-            Command::LineTo(p) => {
-                array[0] = p;
-                (Raw::LineTo, 1)
-            }
-            Command::QuadTo(p1, p2) => {
-                array[0] = p1;
-                array[1] = p2;
-                (Raw::QuadTo, 2)
-            }
-            Command::CubicTo(p1, p2, p3) => {
-                array[0] = p1;
-                array[1] = p2;
-                array[2] = p3;
-                (Raw::CubicTo, 3)
-            }
-            Command::Solid => (Raw::Solid, 0),
-            Command::Hole => (Raw::Hole, 0),
-            Command::Close => (Raw::Close, 0),
-        }
-    }
-}
-
 pub struct PathIter<'a> {
     index: std::slice::Iter<'a, Raw>,
     coord: &'a [Offset],
+    weight: &'a [f32],
 }
 
 impl<'a> Iterator for PathIter<'a> {
@@ -115,6 +88,11 @@ impl<'a> Iterator for PathIter<'a> {
                 Raw::MoveTo => Command::MoveTo(coords[0]),
                 Raw::LineTo => Command::LineTo(coords[0]),
                 Raw::QuadTo => Command::QuadTo(coords[0], coords[1]),
+                Raw::ConicTo => {
+                    let (&weight, next) = self.weight.split_first()?;
+                    self.weight = next;
+                    Command::ConicTo(coords[0], coords[1], weight)
+                }
                 Raw::CubicTo => Command::CubicTo(coords[0], coords[1], coords[2]),
                 Raw::Solid => Command::Solid,
                 Raw::Hole => Command::Hole,
@@ -130,6 +108,7 @@ pub struct PathTransformIter<'a> {
     transform: Transform,
     index: std::slice::Iter<'a, Raw>,
     coord: &'a [Offset],
+    weight: &'a [f32],
 }
 
 impl<'a> Iterator for PathTransformIter<'a> {
@@ -150,6 +129,16 @@ impl<'a> Iterator for PathTransformIter<'a> {
                     self.transform.apply(coords[0]),
                     self.transform.apply(coords[1]),
                 ),
+                // Conics are invariant under affine transforms, the weight is kept.
+                Raw::ConicTo => {
+                    let (&weight, next) = self.weight.split_first()?;
+                    self.weight = next;
+                    Command::ConicTo(
+                        self.transform.apply(coords[0]),
+                        self.transform.apply(coords[1]),
+                        weight,
+                    )
+                }
                 Raw::CubicTo => Command::CubicTo(
                     self.transform.apply(coords[0]),
                     self.transform.apply(coords[1]),
@@ -170,6 +159,7 @@ impl<'a> Iterator for PathTransformIter<'a> {
 pub struct Path {
     index: Vec<Raw>,
     coord: Vec<Offset>,
+    weight: Vec<f32>,
 }
 
 impl<'a> IntoIterator for &'a Path {
@@ -180,41 +170,46 @@ impl<'a> IntoIterator for &'a Path {
         PathIter {
             index: self.index.iter(),
             coord: &self.coord,
+            weight: &self.weight,
         }
     }
 }
 
 impl std::iter::Extend<Command> for Path {
     fn extend<T: IntoIterator<Item = Command>>(&mut self, iter: T) {
-        let mut coord = [Offset::zero(); 3];
         for cmd in iter {
-            let (cmd, len) = cmd.to_raw(&mut coord);
-            self.index.push(cmd);
-            self.coord.extend_from_slice(&coord[..len]);
+            self.push(cmd);
         }
     }
 }
 
 impl<'a> std::iter::Extend<&'a Command> for Path {
     fn extend<T: IntoIterator<Item = &'a Command>>(&mut self, iter: T) {
-        let mut coord = [Offset::zero(); 3];
-        for cmd in iter {
-            let (cmd, len) = cmd.to_raw(&mut coord);
-            self.index.push(cmd);
-            self.coord.extend_from_slice(&coord[..len]);
+        for &cmd in iter {
+            self.push(cmd);
         }
     }
 }
 
 impl Path {
     /// Returns a copy of the [`Path`] with all the segments of every sub-path transformed by the given matrix.
-    pub(crate) fn transform_iter(&self, transform: Transform) -> PathTransformIter {
+    pub(crate) fn transform_iter(&self, transform: Transform) -> PathTransformIter<'_> {
         PathTransformIter {
             transform,
             index: self.index.iter(),
             coord: &self.coord,
+            weight: &self.weight,
         }
     }
+
+    /// Bounds of the transformed control points, which contain the whole path.
+    pub(crate) fn control_bounds(&self, transform: Transform) -> Rect {
+        let empty = Rect::new(Offset::infinity(), -Offset::infinity());
+        self.coord.iter().fold(empty, |rect, &p| {
+            let p = transform.apply(p);
+            Rect::new(rect.min.min(p), rect.max.max(p))
+        })
+    }
 }
 
 impl Path {
@@ -223,6 +218,7 @@ impl Path {
         Self {
             index: Vec::new(),
             coord: Vec::new(),
+            weight: Vec::new(),
         }
     }
 
@@ -230,6 +226,7 @@ impl Path {
     pub fn clear(&mut self) {
         self.index.clear();
         self.coord.clear();
+        self.weight.clear();
     }
 
     /// Returns `true` if the [`Path`] does not contain any drawing commands.
@@ -255,6 +252,24 @@ impl Path {
         self.coord.extend([p1, p2]);
     }
 
+    /// Adds a conic, a quadratic Bézier curve with the given weight of its control point, to the [`Path`].
+    ///
+    /// The curve is an elliptical arc for weights less than 1, a parabola for 1 and a hyperbola above.
+    pub fn conic_to(&mut self, p1: Offset, p2: Offset, weight: f32) {
+        if weight.is_nan() || weight <= 0.0 {
+            self.line_to(p2);
+        } else if weight.is_infinite() {
+            self.line_to(p1);
+            self.line_to(p2);
+        } else if weight == 1.0 {
+            self.quad_to(p1, p2);
+        } else {
+            self.index.push(Raw::ConicTo);
+            self.coord.extend([p1, p2]);
+            self.weight.push(weight);
+        }
+    }
+
     /// Adds a cubic Bézier curve to the [`Path`] given its two control points and its end point.
     pub fn cubic_to(&mut self, p1: Offset, p2: Offset, p3: Offset) {
         self.index.push(Raw::CubicTo);
@@ -273,17 +288,37 @@ impl Path {
             Solidity::Hole => self.index.push(Raw::Hole),
         }
     }
+
+    /// Appends the drawing command.
+    fn push(&mut self, cmd: Command) {
+        match cmd {
+            Command::MoveTo(p) => self.move_to(p),
+            Command::LineTo(p) => self.line_to(p),
+            Command::QuadTo(p1, p2) => self.quad_to(p1, p2),
+            Command::ConicTo(p1, p2, weight) => self.conic_to(p1, p2, weight),
+            Command::CubicTo(p1, p2, p3) => self.cubic_to(p1, p2, p3),
+            Command::Solid => self.solidity(Solidity::Solid),
+            Command::Hole => self.solidity(Solidity::Hole),
+            Command::Close => self.close(),
+        }
+    }
 }
 
 impl Path {
-    /*
-    /// Adds a new sub-path with one arc segment that consists of the arc that follows the edge of the oval
-    /// bounded by the given rectangle,
-    /// from startAngle radians around the oval up to startAngle + sweepAngle radians around the oval,
-    /// with zero radians being the point on the right hand side of the oval that crosses the horizontal line
-    /// that intersects the center of the rectangle and with positive angles going clockwise around the oval.
-    pub fn add_arc(Rect oval, double startAngle, double sweepAngle) -> void
-    */
+    /// Adds a new sub-path with one arc segment that follows the edge of the oval bounded by the given rectangle,
+    /// from `start_angle` radians around the oval up to `start_angle + sweep_angle` radians,
+    /// with zero radians being the point on the right hand side of the oval
+    /// and positive angles going clockwise around the oval.
+    pub fn add_arc(&mut self, oval: Rect, start_angle: f32, sweep_angle: f32) {
+        use std::f32::consts::TAU;
+
+        let center = oval.center();
+        let radii = oval.size() / 2.0;
+        let (dy, dx) = start_angle.sin_cos();
+        self.move_to(center + Offset::new(dx, dy).scale(radii.x, radii.y));
+        let sweep_angle = sweep_angle.clamp(-TAU, TAU);
+        self.ellipse_arc(center, radii, 0.0, start_angle, sweep_angle);
+    }
 
     pub fn line(&mut self, p0: Offset, p1: Offset) {
         self.move_to(p0);
@@ -334,103 +369,308 @@ impl Path {
     /// Adds [`Rect`] with [`Rounding`] to the [`Path`].
     pub fn rrect(&mut self, rect: Rect, radius: Rounding) {
         let Rect { min, max } = rect;
-        let kappa = 1.0 - KAPPA90;
 
-        let sw = Offset::new(radius.sw, radius.sw);
-        let se = Offset::new(radius.se, radius.se);
-        let ne = Offset::new(radius.ne, radius.ne);
-        let nw = Offset::new(radius.nw, radius.nw);
-
-        self.move_to(Offset::new(min.x, min.y + nw.y));
-
-        self.line_to(Offset::new(min.x, max.y - sw.y));
+        self.move_to(Offset::new(min.x, min.y + radius.nw));
 
+        self.line_to(Offset::new(min.x, max.y - radius.sw));
         if radius.sw > 0.0 {
-            self.cubic_to(
-                Offset::new(min.x, max.y - sw.y * kappa),
-                Offset::new(min.x + sw.x * kappa, max.y),
-                Offset::new(min.x + sw.x, max.y),
+            self.conic_to(
+                Offset::new(min.x, max.y),
+                Offset::new(min.x + radius.sw, max.y),
+                FRAC_1_SQRT_2,
             );
         }
 
-        self.line_to(Offset::new(max.x - se.x, max.y));
+        self.line_to(Offset::new(max.x - radius.se, max.y));
         if radius.se > 0.0 {
-            self.cubic_to(
-                Offset::new(max.x - se.x * kappa, max.y),
-                Offset::new(max.x, max.y - se.y * kappa),
-                Offset::new(max.x, max.y - se.y),
+            self.conic_to(
+                Offset::new(max.x, max.y),
+                Offset::new(max.x, max.y - radius.se),
+                FRAC_1_SQRT_2,
             );
         }
 
-        self.line_to(Offset::new(max.x, min.y + ne.y));
+        self.line_to(Offset::new(max.x, min.y + radius.ne));
         if radius.ne > 0.0 {
-            self.cubic_to(
-                Offset::new(max.x, min.y + ne.y * kappa),
-                Offset::new(max.x - ne.x * kappa, min.y),
-                Offset::new(max.x - ne.x, min.y),
+            self.conic_to(
+                Offset::new(max.x, min.y),
+                Offset::new(max.x - radius.ne, min.y),
+                FRAC_1_SQRT_2,
             );
         }
 
-        self.line_to(Offset::new(min.x + nw.x, min.y));
+        self.line_to(Offset::new(min.x + radius.nw, min.y));
         if radius.nw > 0.0 {
-            self.cubic_to(
-                Offset::new(min.x + nw.x * kappa, min.y),
-                Offset::new(min.x, min.y + nw.y * kappa),
-                Offset::new(min.x, min.y + nw.y),
+            self.conic_to(
+                Offset::new(min.x, min.y),
+                Offset::new(min.x, min.y + radius.nw),
+                FRAC_1_SQRT_2,
             );
         }
 
         self.close();
     }
 
+    /// Adds an exact ellipse as four quarter conics.
     #[inline]
     fn ellipse(&mut self, center: Offset, radius: Offset) {
-        let kappa = radius * KAPPA90;
-        let (positive_radius, positive_kappa) = (center + radius, center + kappa);
-        let (negative_radius, negative_kappa) = (center - radius, center - kappa);
+        let (min, max) = (center - radius, center + radius);
 
         self.index.extend([
             Raw::MoveTo,
-            Raw::CubicTo,
-            Raw::CubicTo,
-            Raw::CubicTo,
-            Raw::CubicTo,
+            Raw::ConicTo,
+            Raw::ConicTo,
+            Raw::ConicTo,
+            Raw::ConicTo,
             Raw::Close,
         ]);
 
         self.coord.extend([
-            Offset::new(negative_radius.x, center.y),         // move
-            Offset::new(negative_radius.x, positive_kappa.y), // curve
-            Offset::new(negative_kappa.x, positive_radius.y),
-            Offset::new(center.x, positive_radius.y),
-            Offset::new(positive_kappa.x, positive_radius.y), // curve
-            Offset::new(positive_radius.x, positive_kappa.y),
-            Offset::new(positive_radius.x, center.y),
-            Offset::new(positive_radius.x, negative_kappa.y), // curve
-            Offset::new(positive_kappa.x, negative_radius.y),
-            Offset::new(center.x, negative_radius.y),
-            Offset::new(negative_kappa.x, negative_radius.y), // curve
-            Offset::new(negative_radius.x, negative_kappa.y),
-            Offset::new(negative_radius.x, center.y),
+            Offset::new(min.x, center.y), // move
+            Offset::new(min.x, max.y),    // conic
+            Offset::new(center.x, max.y),
+            Offset::new(max.x, max.y), // conic
+            Offset::new(max.x, center.y),
+            Offset::new(max.x, min.y), // conic
+            Offset::new(center.x, min.y),
+            Offset::new(min.x, min.y), // conic
+            Offset::new(min.x, center.y),
         ]);
+
+        self.weight.extend([FRAC_1_SQRT_2; 4]);
     }
 
-    /*
-    /// Appends up to four conic curves weighted to describe an oval of radius and rotated by rotation. [...]
-    pub fn relative_arc_to_point(Offset arcEndDelta, { Radius radius: Radius.zero, double rotation: 0.0, bool largeArc: false, bool clockwise: true }) -> void
-    /// Adds a bezier segment that curves from the current point to the point at the offset (x2,y2) from the current point, using the control point at the offset (x1,y1) from the current point and the weight w. If the weight is greater than 1, then the curve is a hyperbola; if the weight equals 1, it's a parabola; and if it is less than 1, it is an ellipse.
-    pub fn relative_conic_to(double x1, double y1, double x2, double y2, double w) -> void
-    /// Adds a cubic bezier segment that curves from the current point to the point at the offset (x3,y3) from the current point, using the control points at the offsets (x1,y1) and (x2,y2) from the current point.
-    pub fn relative_cubic_to(double x1, double y1, double x2, double y2, double x3, double y3) -> void
-    /// Adds a quadratic bezier segment that curves from the current point to the point at the offset (x2,y2) from the current point, using the control point at the offset (x1,y1) from the current point.
-    pub fn relative_quadratic_bezier_to(double x1, double y1, double x2, double y2) -> void
-    */
+    /// Returns the point where the next segment starts,
+    /// which is the start of the sub-path after [`Path::close`].
+    pub fn current_point(&self) -> Option<Offset> {
+        let mut end = self.coord.len();
+        let mut closed = false;
+        for &raw in self.index.iter().rev() {
+            match raw {
+                Raw::Close => closed = true,
+                Raw::MoveTo => return Some(self.coord[end - 1]),
+                _ if !closed && raw.num_points() > 0 => return Some(self.coord[end - 1]),
+                _ => (),
+            }
+            end -= raw.num_points();
+        }
+        None
+    }
+
+    /// Offset of relative commands, the current point or the origin for an empty [`Path`].
+    fn relative_origin(&self) -> Offset {
+        self.current_point().unwrap_or(Offset::zero())
+    }
+
+    /// Starts a new sub-path at the given offset from the current point.
+    pub fn relative_move_to(&mut self, delta: Offset) {
+        self.move_to(self.relative_origin() + delta);
+    }
+
+    /// Adds a straight line segment from the current point to the point at the given offset from it.
+    pub fn relative_line_to(&mut self, delta: Offset) {
+        self.line_to(self.relative_origin() + delta);
+    }
+
+    /// Adds a quadratic Bézier curve with points at the given offsets from the current point.
+    pub fn relative_quad_to(&mut self, p1: Offset, p2: Offset) {
+        let origin = self.relative_origin();
+        self.quad_to(origin + p1, origin + p2);
+    }
+
+    /// Adds a conic like [`Path::conic_to`] with points at the given offsets from the current point.
+    pub fn relative_conic_to(&mut self, p1: Offset, p2: Offset, weight: f32) {
+        let origin = self.relative_origin();
+        self.conic_to(origin + p1, origin + p2, weight);
+    }
+
+    /// Adds a cubic Bézier curve with points at the given offsets from the current point.
+    pub fn relative_cubic_to(&mut self, p1: Offset, p2: Offset, p3: Offset) {
+        let origin = self.relative_origin();
+        self.cubic_to(origin + p1, origin + p2, origin + p3);
+    }
+
+    /// Adds an elliptical arc from the current point to `to`, as conics.
+    ///
+    /// The ellipse has the given radii and is rotated by `rotation` radians.
+    /// Of the four candidate arcs, `large_arc` and `clockwise` pick one, like the SVG arc command.
+    /// Radii too small to reach `to` are scaled up, zero radii produce a straight line.
+    pub fn arc_to_point(
+        &mut self,
+        to: Offset,
+        radius: Offset,
+        rotation: f32,
+        large_arc: bool,
+        clockwise: bool,
+    ) {
+        let from = self.relative_origin();
+        self.svg_arc(from, radius, rotation, large_arc, clockwise, to);
+    }
+
+    /// Adds an elliptical arc like [`Path::arc_to_point`] to the point at the given offset from the current point.
+    pub fn relative_arc_to_point(
+        &mut self,
+        delta: Offset,
+        radius: Offset,
+        rotation: f32,
+        large_arc: bool,
+        clockwise: bool,
+    ) {
+        let from = self.relative_origin();
+        self.svg_arc(from, radius, rotation, large_arc, clockwise, from + delta);
+    }
+
+    /// Adds an arc segment tangent to the lines from the current point to `p1` and from `p1` to `p2`,
+    /// connected to the current point with a straight line.
+    ///
+    /// Degenerate corners and a zero radius produce a straight line to `p1`.
+    pub fn arc_to(&mut self, p1: Offset, p2: Offset, radius: f32) {
+        const DIST_TOL: f32 = 0.01;
+
+        let Some(p0) = self.current_point() else {
+            return;
+        };
+
+        let collinear = (p1 - p0).cross(p2 - p1).abs() < DIST_TOL * DIST_TOL;
+        if (p0 - p1).magnitude() < DIST_TOL
+            || (p2 - p1).magnitude() < DIST_TOL
+            || collinear
+            || radius < DIST_TOL
+        {
+            self.line_to(p1);
+            return;
+        }
+
+        // Tangential circle to lines (p0, p1) and (p1, p2).
+        let d0 = p0 - p1;
+        let d0 = d0 / d0.magnitude();
+        let d1 = p2 - p1;
+        let d1 = d1 / d1.magnitude();
+        let a = (d0.x * d1.x + d0.y * d1.y).clamp(-1.0, 1.0).acos();
+        let d = radius / (a / 2.0).tan();
+
+        if d > 10000.0 {
+            self.line_to(p1);
+            return;
+        }
+
+        let (center, a0, a1, dir) = if d1.cross(d0) > 0.0 {
+            let center = p1 + d0 * d + Offset::new(d0.y, -d0.x) * radius;
+            let a0 = d0.x.atan2(-d0.y);
+            let a1 = (-d1.x).atan2(d1.y);
+            (center, a0, a1, Solidity::Hole)
+        } else {
+            let center = p1 + d0 * d + Offset::new(-d0.y, d0.x) * radius;
+            let a0 = (-d0.x).atan2(d0.y);
+            let a1 = d1.x.atan2(-d1.y);
+            (center, a0, a1, Solidity::Solid)
+        };
+
+        self.arc(center, radius, a0, a1, dir);
+    }
+
+    /// Adds an elliptical arc from `from` to `to` as conics,
+    /// parameterized like the SVG arc command. The x-axis rotation is in radians.
+    ///
+    /// Out-of-range radii are scaled up to fit, zero radii produce a straight line.
+    fn svg_arc(
+        &mut self,
+        from: Offset,
+        radii: Offset,
+        x_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        to: Offset,
+    ) {
+        use std::f32::consts::TAU;
+
+        if from == to {
+            return;
+        }
+
+        let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+        if rx <= f32::EPSILON || ry <= f32::EPSILON {
+            self.line_to(to);
+            return;
+        }
+
+        // Endpoint to center parameterization, see SVG 1.1 implementation notes F.6.5.
+        let (sin, cos) = x_rotation.sin_cos();
+        let half = (from - to) / 2.0;
+        let x1 = cos * half.x + sin * half.y;
+        let y1 = -sin * half.x + cos * half.y;
+
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            let scale = lambda.sqrt();
+            rx *= scale;
+            ry *= scale;
+        }
+
+        let (rx2, ry2) = (rx * rx, ry * ry);
+        let num = rx2 * ry2 - rx2 * y1 * y1 - ry2 * x1 * x1;
+        let den = rx2 * y1 * y1 + ry2 * x1 * x1;
+        let coef = (num / den).max(0.0).sqrt();
+        let coef = if large_arc == sweep { -coef } else { coef };
+        let cx1 = coef * rx * y1 / ry;
+        let cy1 = -coef * ry * x1 / rx;
+
+        let mid = (from + to) / 2.0;
+        let center = Offset::new(cos * cx1 - sin * cy1 + mid.x, sin * cx1 + cos * cy1 + mid.y);
+
+        let angle = |u: Offset, v: Offset| u.cross(v).atan2(u.x * v.x + u.y * v.y);
+        let u = Offset::new((x1 - cx1) / rx, (y1 - cy1) / ry);
+        let v = Offset::new((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+        let a0 = angle(Offset::new(1.0, 0.0), u);
+        let mut da = angle(u, v);
+        if sweep && da < 0.0 {
+            da += TAU;
+        } else if !sweep && da > 0.0 {
+            da -= TAU;
+        }
+
+        self.ellipse_arc(center, Offset::new(rx, ry), x_rotation, a0, da);
+
+        // Rounding of the center parameterization must not move the end point off `to`.
+        if let Some(end) = self.coord.last_mut() {
+            *end = to;
+        }
+    }
+
+    /// Appends conics along the ellipse from angle `a0` swept by `da`,
+    /// split into max 90 degree segments.
+    fn ellipse_arc(&mut self, center: Offset, radii: Offset, rotation: f32, a0: f32, da: f32) {
+        use std::f32::consts::FRAC_PI_2;
+
+        let (sin, cos) = rotation.sin_cos();
+
+        // Maps a point of the unit circle onto the ellipse.
+        let map = |p: Offset| {
+            let p = p.scale(radii.x, radii.y);
+            Offset::new(cos * p.x - sin * p.y, sin * p.x + cos * p.y) + center
+        };
+
+        let ndivs = (da.abs() / FRAC_PI_2 - 1e-3).ceil().max(1.0) as i32;
+        let step = da / ndivs as f32;
+        let weight = (step / 2.0).cos();
+
+        for i in 1..ndivs + 1 {
+            // The control point is where the tangents at both ends meet.
+            let (my, mx) = (a0 + step * (i as f32 - 0.5)).sin_cos();
+            let (ey, ex) = (a0 + step * i as f32).sin_cos();
+            self.conic_to(
+                map(Offset::new(mx, my) / weight),
+                map(Offset::new(ex, ey)),
+                weight,
+            );
+        }
+    }
 
     /// Creates new circle arc shaped sub-path. The arc center is at cx,cy, the arc radius is r,
     /// and the arc is drawn from angle a0 to a1, and swept in direction dir (Winding)
     /// Angles are specified in radians.
     pub fn arc(&mut self, center: Offset, radius: f32, a0: f32, a1: f32, dir: Solidity) {
-        use std::f32::consts::{FRAC_PI_2, TAU};
+        use std::f32::consts::TAU;
 
         // Clamp angles
         let mut da = a1 - a0;
@@ -450,40 +690,14 @@ impl Path {
             }
         }
 
-        // Split arc into max 90 degree segments.
-        let ndivs = ((da.abs() / FRAC_PI_2 + 0.5) as i32).clamp(1, 5);
-        let (sin, cos) = ((da / ndivs as f32) / 2.0).sin_cos();
-        let kappa = (4.0 / 3.0 * (1.0 - cos) / sin).abs();
-        let kappa = if let Solidity::Solid = dir {
-            -kappa
+        let (dy, dx) = a0.sin_cos();
+        let point = center + Offset::new(dx, dy) * radius;
+        if self.is_empty() {
+            self.move_to(point);
         } else {
-            kappa
-        };
-
-        let (mut last_point, mut last_tan) = {
-            let (dy, dx) = a0.sin_cos();
-            let point = Offset::new(center.x + dx * radius, center.y + dy * radius);
-            let tan = Offset::new(-dy * radius * kappa, dx * radius * kappa);
-            if self.is_empty() {
-                self.move_to(point);
-            } else {
-                self.line_to(point);
-            }
-            (point, tan)
-        };
-
-        let inv_ndivs = (ndivs as f32).recip();
-
-        for i in 1..ndivs + 1 {
-            let a = a0 + da * (i as f32 * inv_ndivs);
-            let (dy, dx) = a.sin_cos();
-            let point = Offset::new(center.x + dx * radius, center.y + dy * radius);
-            let tan = Offset::new(-dy * radius * kappa, dx * radius * kappa);
-
-            self.cubic_to(last_point + last_tan, point - tan, point);
-
-            last_point = point;
-            last_tan = tan;
+            self.line_to(point);
         }
+
+        self.ellipse_arc(center, Offset::new(radius, radius), 0.0, a0, da);
     }
 }
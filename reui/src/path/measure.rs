@@ -0,0 +1,155 @@
+use super::Path;
+use crate::{internals::Tessellator, Offset};
+
+/// Measures lengths of the contours of a [`Path`] flattened with the given tolerance.
+///
+/// Contours without length are skipped.
+pub struct PathMeasure {
+    contours: Vec<ContourMeasure>,
+}
+
+impl PathMeasure {
+    /// Flattens curves of the path with the tessellation tolerance,
+    /// where `0.25` matches drawing at unit scale.
+    pub fn new(path: &Path, tolerance: f32) -> Self {
+        let mut tess = Tessellator::new();
+        tess.flatten_commands(path.into_iter(), tolerance, 0.01);
+
+        let contours = tess
+            .contours()
+            .filter_map(|(points, closed)| ContourMeasure::new(points.collect(), closed))
+            .collect();
+
+        Self { contours }
+    }
+
+    /// Measured contours in the order of the path.
+    pub fn contours(&self) -> &[ContourMeasure] {
+        &self.contours
+    }
+
+    /// Total length of all contours.
+    pub fn length(&self) -> f32 {
+        // `Iterator::sum` of floats starts from -0.0, so an empty path would report -0.0,
+        // see the `length_of_empty_path_is_positive_zero` test.
+        self.contours
+            .iter()
+            .fold(0.0, |length, contour| length + contour.length())
+    }
+
+    /// Returns the part of the path between the distances along all contours one after another,
+    /// like trimming the path.
+    pub fn extract_segment(&self, start: f32, end: f32) -> Path {
+        let mut path = Path::new();
+        let mut offset = 0.0;
+        for contour in &self.contours {
+            contour.append_segment(start - offset, end - offset, &mut path);
+            offset += contour.length();
+        }
+        path
+    }
+}
+
+/// Length of a single contour and positions along it.
+pub struct ContourMeasure {
+    /// Flattened points, closed contours repeat the first point at the end.
+    points: Vec<Offset>,
+    /// Distance along the contour for every point.
+    distances: Vec<f32>,
+    closed: bool,
+}
+
+impl ContourMeasure {
+    fn new(mut points: Vec<Offset>, closed: bool) -> Option<Self> {
+        if closed && points.len() > 1 && points.first() != points.last() {
+            points.push(points[0]);
+        }
+
+        let mut length = 0.0;
+        let mut prev = *points.first()?;
+        let distances: Vec<f32> = points
+            .iter()
+            .map(|&p| {
+                length += (p - prev).magnitude();
+                prev = p;
+                length
+            })
+            .collect();
+
+        (length > 0.0).then_some(Self {
+            points,
+            distances,
+            closed,
+        })
+    }
+
+    /// Length of the contour, including the closing segment.
+    pub fn length(&self) -> f32 {
+        self.distances[self.distances.len() - 1]
+    }
+
+    /// Returns `true` if the contour was closed with [`Path::close`].
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Returns the position and the unit tangent at the distance along the contour.
+    ///
+    /// The distance is clamped to the length of the contour.
+    pub fn pos_tan(&self, distance: f32) -> (Offset, Offset) {
+        let distance = distance.clamp(0.0, self.length());
+        let (i, t) = self.segment(distance);
+        let (p0, p1) = (self.points[i - 1], self.points[i]);
+        let delta = p1 - p0;
+        (Offset::lerp(p0, p1, t), delta / delta.magnitude())
+    }
+
+    /// Returns the part of the contour between the distances, clamped to the length of the contour.
+    ///
+    /// The segment is empty unless `start < end`. The whole closed contour stays closed.
+    pub fn extract_segment(&self, start: f32, end: f32) -> Path {
+        let mut path = Path::new();
+        self.append_segment(start, end, &mut path);
+        path
+    }
+
+    fn append_segment(&self, start: f32, end: f32, path: &mut Path) {
+        let length = self.length();
+        let (start, end) = (start.max(0.0), end.min(length));
+        if start >= end {
+            return;
+        }
+
+        let (first, t) = self.segment(start);
+        path.move_to(Offset::lerp(self.points[first - 1], self.points[first], t));
+
+        let (last, t) = self.segment(end);
+        // Points at the start, when it lands on a vertex, are already there.
+        let first = first
+            + self.distances[first..last]
+                .iter()
+                .take_while(|&&d| d <= start)
+                .count();
+        for &p in &self.points[first..last] {
+            path.line_to(p);
+        }
+
+        if self.closed && start == 0.0 && end == length {
+            path.close();
+        } else {
+            path.line_to(Offset::lerp(self.points[last - 1], self.points[last], t));
+        }
+    }
+
+    /// Returns the index of the end of the segment with the distance and the position on it.
+    fn segment(&self, distance: f32) -> (usize, f32) {
+        let i = (self.distances.partition_point(|&d| d < distance)).clamp(1, self.points.len() - 1);
+        let (d0, d1) = (self.distances[i - 1], self.distances[i]);
+        let t = if d1 > d0 {
+            (distance - d0) / (d1 - d0)
+        } else {
+            0.0
+        };
+        (i, t)
+    }
+}
@@ -0,0 +1,421 @@
+use super::Path;
+use crate::{internals::Tessellator, Offset, Solidity};
+use std::collections::HashMap;
+
+/// Boolean operation of [`Path::op`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PathOp {
+    /// Subtracts the second path from the first one.
+    #[default]
+    Difference,
+    /// Keeps the area covered by both paths.
+    Intersect,
+    /// Keeps the area covered by either path.
+    Union,
+    /// Keeps the area covered by exactly one path.
+    Xor,
+    /// Subtracts the first path from the second one.
+    ReverseDifference,
+}
+
+impl PathOp {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            Self::Difference => a && !b,
+            Self::Intersect => a && b,
+            Self::Union => a || b,
+            Self::Xor => a != b,
+            Self::ReverseDifference => b && !a,
+        }
+    }
+}
+
+impl Path {
+    /// Returns a new path covering the area selected by `op` from the areas of `a` and `b`.
+    ///
+    /// Both paths are flattened like fills at unit scale and filled with [`crate::FillRule::NonZero`],
+    /// with contour windings set by their [`Solidity`].
+    /// The result consists of polygons, its holes are marked with [`Solidity::Hole`].
+    pub fn op(a: &Self, b: &Self, op: PathOp) -> Self {
+        let mut graph = Graph::default();
+        let mut tess = Tessellator::new();
+        for (source, path) in [a, b].into_iter().enumerate() {
+            tess.flatten(path.into_iter(), 0.25, 0.01);
+            for (points, _) in tess.contours() {
+                graph.add_polygon(source, points);
+            }
+        }
+
+        graph.split();
+        let edges = graph.classify(op);
+
+        let mut path = Self::new();
+        for polygon in graph.link(&edges) {
+            let Some((first, rest)) = polygon.split_first() else {
+                continue;
+            };
+            path.move_to(graph.offset(*first));
+            for &p in rest {
+                path.line_to(graph.offset(p));
+            }
+            path.close();
+            if area(&polygon) < 0 {
+                path.solidity(Solidity::Hole);
+            }
+        }
+        path
+    }
+}
+
+/// Point snapped to the grid, all predicates are exact.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+impl Point {
+    fn sub(self, other: Self) -> (i128, i128) {
+        ((self.x - other.x) as i128, (self.y - other.y) as i128)
+    }
+}
+
+/// Twice the signed area, positive for solid contours.
+fn area(polygon: &[Point]) -> i128 {
+    let mut area = 0;
+    let mut prev = polygon[polygon.len() - 1];
+    for &p in polygon {
+        area += (p.x - prev.x) as i128 * (p.y + prev.y) as i128;
+        prev = p;
+    }
+    area
+}
+
+/// Orientation of `c` relative to the line from `a` to `b`.
+fn orient(a: Point, b: Point, c: Point) -> i128 {
+    let (abx, aby) = b.sub(a);
+    let (acx, acy) = c.sub(a);
+    abx * acy - aby * acx
+}
+
+/// Whether `p` lies strictly between `a` and `b` on their line.
+fn strictly_between(a: Point, b: Point, p: Point) -> bool {
+    let (abx, aby) = b.sub(a);
+    let (apx, apy) = p.sub(a);
+    let dot = abx * apx + aby * apy;
+    dot > 0 && dot < abx * abx + aby * aby
+}
+
+#[derive(Default)]
+struct Graph {
+    /// Grid cells per unit.
+    scale: f64,
+    input: Vec<(usize, Vec<Offset>)>,
+    /// Edges of both inputs as `(source, from, to)`.
+    edges: Vec<(usize, Point, Point)>,
+}
+
+impl Graph {
+    fn add_polygon(&mut self, source: usize, points: impl Iterator<Item = Offset>) {
+        let points: Vec<Offset> = points.collect();
+        if points.len() >= 3 {
+            self.input.push((source, points));
+        }
+    }
+
+    fn offset(&self, p: Point) -> Offset {
+        let scale = 2.0 * self.scale;
+        Offset::new((p.x as f64 / scale) as f32, (p.y as f64 / scale) as f32)
+    }
+
+    /// Coordinates are doubled to keep midpoints of edges exact.
+    fn snap(&self, x: f64, y: f64) -> Point {
+        Point {
+            x: 2 * (x * self.scale).round() as i64,
+            y: 2 * (y * self.scale).round() as i64,
+        }
+    }
+
+    /// Snaps polygons and splits their edges at all intersections.
+    fn split(&mut self) {
+        // Keep products of coordinates in `i128` exact.
+        let max = self
+            .input
+            .iter()
+            .flat_map(|(_, points)| points)
+            .fold(1.0_f32, |max, p| max.max(p.x.abs()).max(p.y.abs()));
+        self.scale = 1024.0_f64.min((1 << 26) as f64 / f64::from(max)).max(1e-6);
+
+        let mut segments = Vec::new();
+        for (source, points) in &self.input {
+            let points: Vec<Point> = points
+                .iter()
+                .map(|p| self.snap(f64::from(p.x), f64::from(p.y)))
+                .collect();
+            for (i, &from) in points.iter().enumerate() {
+                let to = points[(i + 1) % points.len()];
+                if from != to {
+                    segments.push((*source, from, to));
+                }
+            }
+        }
+
+        // Sweep over segments sorted by their left end.
+        segments.sort_by_key(|&(_, a, b)| a.x.min(b.x));
+        let mut splits: Vec<Vec<Point>> = vec![Vec::new(); segments.len()];
+
+        for i in 0..segments.len() {
+            let (_, a, b) = segments[i];
+            let max_x = a.x.max(b.x);
+            let (min_y, max_y) = (a.y.min(b.y), a.y.max(b.y));
+
+            for j in i + 1..segments.len() {
+                let (_, c, d) = segments[j];
+                if c.x.min(d.x) > max_x {
+                    break;
+                }
+                if c.y.max(d.y) < min_y || c.y.min(d.y) > max_y {
+                    continue;
+                }
+
+                let d1 = orient(a, b, c);
+                let d2 = orient(a, b, d);
+                let d3 = orient(c, d, a);
+                let d4 = orient(c, d, b);
+
+                // Touching and overlapping ends split the other segment.
+                for (p, on_ab) in [(c, d1 == 0), (d, d2 == 0)] {
+                    if on_ab && strictly_between(a, b, p) {
+                        splits[i].push(p);
+                    }
+                }
+                for (p, on_cd) in [(a, d3 == 0), (b, d4 == 0)] {
+                    if on_cd && strictly_between(c, d, p) {
+                        splits[j].push(p);
+                    }
+                }
+
+                // Proper crossing.
+                if d1.signum() * d2.signum() < 0 && d3.signum() * d4.signum() < 0 {
+                    let t = d3 as f64 / (d3 - d4) as f64;
+                    let scale = 2.0 * self.scale;
+                    let p = self.snap(
+                        (a.x as f64 + (b.x - a.x) as f64 * t) / scale,
+                        (a.y as f64 + (b.y - a.y) as f64 * t) / scale,
+                    );
+                    splits[i].push(p);
+                    splits[j].push(p);
+                }
+            }
+        }
+
+        self.edges.clear();
+        for ((source, a, b), mut points) in segments.into_iter().zip(splits) {
+            let (dx, dy) = b.sub(a);
+            points.sort_by_key(|&p| {
+                let (px, py) = p.sub(a);
+                px * dx + py * dy
+            });
+
+            let mut from = a;
+            for to in points.into_iter().chain(Some(b)) {
+                if to != from {
+                    self.edges.push((source, from, to));
+                    from = to;
+                }
+            }
+        }
+    }
+
+    /// Returns edges between the selected area and the rest, oriented like solid contours.
+    fn classify(&self, op: PathOp) -> Vec<(Point, Point)> {
+        let transpose = |p: Point| Point { x: p.y, y: p.x };
+        let mut result = Vec::new();
+
+        // The area below an edge is on the side of its normal.
+        for ((lo, hi), above, below) in sweep(&windings(&self.edges, |p| p), op) {
+            match (below, above) {
+                (true, false) => result.push((hi, lo)),
+                (false, true) => result.push((lo, hi)),
+                _ => (),
+            }
+        }
+
+        // Vertical edges are horizontal after transposing, with the normal side above.
+        for ((lo, hi), above, below) in sweep(&windings(&self.edges, transpose), op) {
+            if lo.y != hi.y {
+                continue;
+            }
+            let (lo, hi) = (transpose(lo), transpose(hi));
+            match (above, below) {
+                (true, false) => result.push((hi, lo)),
+                (false, true) => result.push((lo, hi)),
+                _ => (),
+            }
+        }
+
+        // Deterministic output regardless of the hash order.
+        result.sort_unstable();
+        result
+    }
+
+    /// Links edges into closed polygons.
+    fn link(&self, edges: &[(Point, Point)]) -> Vec<Vec<Point>> {
+        let mut outgoing: HashMap<Point, Vec<usize>> = HashMap::new();
+        for (i, &(from, _)) in edges.iter().enumerate() {
+            outgoing.entry(from).or_default().push(i);
+        }
+
+        let mut used = vec![false; edges.len()];
+        let mut polygons = Vec::new();
+
+        for start in 0..edges.len() {
+            if used[start] {
+                continue;
+            }
+
+            let mut polygon = Vec::new();
+            let mut current = start;
+            loop {
+                used[current] = true;
+                let (from, to) = edges[current];
+                polygon.push(from);
+
+                // Take the sharpest turn towards the area to separate touching polygons.
+                let dir = to.sub(from);
+                let next = outgoing.get(&to).and_then(|candidates| {
+                    candidates
+                        .iter()
+                        .copied()
+                        .filter(|&i| !used[i])
+                        .max_by(|&i, &j| compare_turns(dir, edges[i], edges[j]))
+                });
+
+                match next {
+                    Some(next) => current = next,
+                    None => break,
+                }
+            }
+
+            remove_collinear(&mut polygon);
+            if polygon.len() >= 3 {
+                polygons.push(polygon);
+            }
+        }
+
+        polygons
+    }
+}
+
+/// Sums the windings of coincident edges per source, keyed by their ends in ascending order.
+fn windings(
+    edges: &[(usize, Point, Point)],
+    map: impl Fn(Point) -> Point,
+) -> HashMap<(Point, Point), [i32; 2]> {
+    let mut groups: HashMap<(Point, Point), [i32; 2]> = HashMap::new();
+    for &(source, a, b) in edges {
+        let (a, b) = (map(a), map(b));
+        groups.entry((a.min(b), a.max(b))).or_default()[source] += if a < b { 1 } else { -1 };
+    }
+    groups
+}
+
+/// Sweeps a vertical line over the edges that are not vertical
+/// and returns whether the area selected by `op` is above and below each of them.
+///
+/// Edges only meet at their ends, so their order stays the same between consecutive ends.
+/// Windings are summed from the top of every such slab where edges start.
+fn sweep(
+    groups: &HashMap<(Point, Point), [i32; 2]>,
+    op: PathOp,
+) -> Vec<((Point, Point), bool, bool)> {
+    let mut edges: Vec<((Point, Point), [i32; 2])> = groups
+        .iter()
+        .filter(|((lo, hi), _)| lo.x < hi.x)
+        .map(|(&edge, &winding)| (edge, winding))
+        .collect();
+    edges.sort_unstable_by_key(|&(edge, _)| edge);
+
+    let mut xs: Vec<i64> = edges
+        .iter()
+        .flat_map(|((lo, hi), _)| [lo.x, hi.x])
+        .collect();
+    xs.sort_unstable();
+    xs.dedup();
+
+    let mut result = Vec::with_capacity(edges.len());
+    let mut active: Vec<usize> = Vec::new();
+    let mut next = 0;
+    for slab in xs.windows(2) {
+        let (x0, x1) = (slab[0], slab[1]);
+        active.retain(|&i| edges[i].0 .1.x > x0);
+
+        let first = next;
+        while next < edges.len() && edges[next].0 .0.x == x0 {
+            active.push(next);
+            next += 1;
+        }
+        if first == next {
+            continue;
+        }
+
+        // Nearly sorted already, only the new edges move.
+        active.sort_by(|&i, &j| compare_at(edges[i].0, edges[j].0, x0 + x1));
+
+        let mut winding = [0, 0];
+        for &i in &active {
+            let (edge, delta) = edges[i];
+            let above = op.apply(winding[0] != 0, winding[1] != 0);
+            winding[0] += delta[0];
+            winding[1] += delta[1];
+            if i >= first {
+                result.push((edge, above, op.apply(winding[0] != 0, winding[1] != 0)));
+            }
+        }
+    }
+    result
+}
+
+/// Orders edges by their height at the doubled x coordinate, from top to bottom.
+fn compare_at((a0, a1): (Point, Point), (b0, b1): (Point, Point), x2: i64) -> std::cmp::Ordering {
+    // Height is `num / den` with a positive `den`.
+    let height = |lo: Point, hi: Point| {
+        let (dx, dy) = hi.sub(lo);
+        let num = 2 * lo.y as i128 * dx + dy * (x2 - 2 * lo.x) as i128;
+        (num, 2 * dx)
+    };
+    let (an, ad) = height(a0, a1);
+    let (bn, bd) = height(b0, b1);
+    (an * bd)
+        .cmp(&(bn * ad))
+        .then_with(|| (a0, a1).cmp(&(b0, b1)))
+}
+
+/// Orders candidate edges by how far they turn left from `dir`.
+fn compare_turns(
+    dir: (i128, i128),
+    (a0, a1): (Point, Point),
+    (b0, b1): (Point, Point),
+) -> std::cmp::Ordering {
+    let angle = |(x, y): (i128, i128)| {
+        let cross = (dir.0 * y - dir.1 * x) as f64;
+        let dot = (dir.0 * x + dir.1 * y) as f64;
+        cross.atan2(dot)
+    };
+    angle(a1.sub(a0)).total_cmp(&angle(b1.sub(b0)))
+}
+
+fn remove_collinear(polygon: &mut Vec<Point>) {
+    let mut i = 0;
+    while polygon.len() >= 3 && i < polygon.len() {
+        let len = polygon.len();
+        let prev = polygon[(i + len - 1) % len];
+        let next = polygon[(i + 1) % len];
+        if orient(prev, polygon[i], next) == 0 {
+            polygon.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use super::Path;
+use crate::{internals::Tessellator, Offset, Solidity, Stroke};
+
+impl Path {
+    /// Returns a path covering the area drawn by stroking this path, with joins, caps and dashes applied.
+    ///
+    /// The result consists of polygons to fill with [`crate::FillRule::NonZero`],
+    /// their windings are kept by marking the reversed ones with [`Solidity::Hole`].
+    /// Inner sides of sharp or tight turns go back through the path to keep the covered area exact.
+    /// Curves, round joins and round caps are flattened with `tolerance` in path units,
+    /// divide it by the scale the outline is drawn at.
+    pub fn stroke_outline(&self, stroke: &Stroke, tolerance: f32) -> Self {
+        let mut path = Self::new();
+        if stroke.width <= 0.0 {
+            return path;
+        }
+
+        let mut tess = Tessellator::new();
+        tess.flatten_dashed(self.into_iter(), &stroke.dash, tolerance, 0.01);
+
+        for polygon in tess.expand_stroke_outline(*stroke, tolerance) {
+            let Some((first, rest)) = polygon.split_first() else {
+                continue;
+            };
+            path.move_to(*first);
+            for &p in rest {
+                path.line_to(p);
+            }
+            path.close();
+            if area(&polygon) < 0.0 {
+                path.solidity(Solidity::Hole);
+            }
+        }
+        path
+    }
+}
+
+/// Twice the signed area, positive for solid contours.
+fn area(polygon: &[Offset]) -> f32 {
+    let mut area = 0.0;
+    let mut prev = polygon[polygon.len() - 1];
+    for &p in polygon {
+        area += (p.x - prev.x) * (p.y + prev.y);
+        prev = p;
+    }
+    area
+}
@@ -0,0 +1,301 @@
+use super::Path;
+use crate::Offset;
+
+/// Kind of [`ParseError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Path data must start with a moveto command.
+    ExpectedMoveTo,
+    /// A character that does not start a command.
+    UnexpectedCharacter(char),
+    /// A number is missing or malformed.
+    ExpectedNumber,
+    /// An arc flag is not `0` or `1`.
+    ExpectedFlag,
+}
+
+/// Error of [`Path::from_svg_data`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset into the path data.
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ParseErrorKind::ExpectedMoveTo => write!(f, "expected moveto command")?,
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {c:?}")?,
+            ParseErrorKind::ExpectedNumber => write!(f, "expected number")?,
+            ParseErrorKind::ExpectedFlag => write!(f, "expected arc flag")?,
+        }
+        write!(f, " at position {}", self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Path {
+    /// Parses SVG path data, the `d` attribute of `<path>`.
+    ///
+    /// All commands are supported in both absolute and relative forms.
+    /// Arcs are converted to conics.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error with its byte offset in `data`.
+    pub fn from_svg_data(data: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser { data, pos: 0 };
+        let mut path = Self::new();
+
+        // Current point and start of the current sub-path.
+        let mut current = Offset::zero();
+        let mut start = Offset::zero();
+        // Sub-path was closed and a drawing command starts a new one at its start.
+        let mut reopen = false;
+        // Last control points for reflection by `S` and `T`.
+        let mut last_cubic: Option<Offset> = None;
+        let mut last_quad: Option<Offset> = None;
+
+        parser.skip_whitespace();
+        while let Some(c) = parser.peek() {
+            let position = parser.pos;
+            if !matches!(
+                c.to_ascii_uppercase(),
+                'M' | 'Z' | 'L' | 'H' | 'V' | 'C' | 'S' | 'Q' | 'T' | 'A'
+            ) {
+                return Err(ParseError {
+                    position,
+                    kind: ParseErrorKind::UnexpectedCharacter(c),
+                });
+            }
+            if path.is_empty() && !matches!(c, 'M' | 'm') {
+                return Err(ParseError {
+                    position,
+                    kind: ParseErrorKind::ExpectedMoveTo,
+                });
+            }
+            parser.pos += 1;
+
+            let relative = c.is_ascii_lowercase();
+            let origin = |current: Offset| if relative { current } else { Offset::zero() };
+
+            if matches!(c, 'Z' | 'z') {
+                path.close();
+                current = start;
+                reopen = true;
+                last_cubic = None;
+                last_quad = None;
+                parser.skip_whitespace();
+                continue;
+            }
+
+            let mut first = true;
+            loop {
+                let (cubic, quad) = match c.to_ascii_uppercase() {
+                    'M' => {
+                        let p = origin(current) + parser.point()?;
+                        if first {
+                            path.move_to(p);
+                            start = p;
+                        } else {
+                            path.line_to(p);
+                        }
+                        current = p;
+                        reopen = false;
+                        (None, None)
+                    }
+                    cmd => {
+                        if reopen {
+                            path.move_to(current);
+                            reopen = false;
+                        }
+
+                        match cmd {
+                            'L' => {
+                                current = origin(current) + parser.point()?;
+                                path.line_to(current);
+                                (None, None)
+                            }
+                            'H' => {
+                                current.x = origin(current).x + parser.number()?;
+                                path.line_to(current);
+                                (None, None)
+                            }
+                            'V' => {
+                                current.y = origin(current).y + parser.number()?;
+                                path.line_to(current);
+                                (None, None)
+                            }
+                            'C' => {
+                                let p1 = origin(current) + parser.point()?;
+                                let p2 = origin(current) + parser.point()?;
+                                let p3 = origin(current) + parser.point()?;
+                                path.cubic_to(p1, p2, p3);
+                                current = p3;
+                                (Some(p2), None)
+                            }
+                            'S' => {
+                                let p1 = last_cubic.map_or(current, |p| current * 2.0 - p);
+                                let p2 = origin(current) + parser.point()?;
+                                let p3 = origin(current) + parser.point()?;
+                                path.cubic_to(p1, p2, p3);
+                                current = p3;
+                                (Some(p2), None)
+                            }
+                            'Q' => {
+                                let p1 = origin(current) + parser.point()?;
+                                let p2 = origin(current) + parser.point()?;
+                                path.quad_to(p1, p2);
+                                current = p2;
+                                (None, Some(p1))
+                            }
+                            'T' => {
+                                let p1 = last_quad.map_or(current, |p| current * 2.0 - p);
+                                let p2 = origin(current) + parser.point()?;
+                                path.quad_to(p1, p2);
+                                current = p2;
+                                (None, Some(p1))
+                            }
+                            'A' => {
+                                let radii = Offset::new(parser.number()?, parser.number()?);
+                                let x_rotation = parser.number()?.to_radians();
+                                let large_arc = parser.flag()?;
+                                let sweep = parser.flag()?;
+                                let to = origin(current) + parser.point()?;
+                                path.svg_arc(current, radii, x_rotation, large_arc, sweep, to);
+                                current = to;
+                                (None, None)
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                };
+
+                last_cubic = cubic;
+                last_quad = quad;
+                first = false;
+
+                // Coordinates may repeat without the command letter.
+                if !parser.at_number() {
+                    break;
+                }
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+struct Parser<'a> {
+    data: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.data[self.pos..].chars().next()
+    }
+
+    fn peek_byte(&self) -> Option<u8> {
+        self.data.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: self.pos,
+            kind,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r' | b'\x0C') = self.peek_byte() {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips whitespace with at most one comma, which must be followed by a number.
+    fn skip_separator(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek_byte() == Some(b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+            if !self.at_number() {
+                return Err(self.error(ParseErrorKind::ExpectedNumber));
+            }
+        }
+        Ok(())
+    }
+
+    fn at_number(&self) -> bool {
+        matches!(self.peek_byte(), Some(b'0'..=b'9' | b'+' | b'-' | b'.'))
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek_byte() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<f32, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        if let Some(b'+' | b'-') = self.peek_byte() {
+            self.pos += 1;
+        }
+        let mut digits = self.skip_digits();
+        if self.peek_byte() == Some(b'.') {
+            self.pos += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            self.pos = start;
+            return Err(self.error(ParseErrorKind::ExpectedNumber));
+        }
+
+        // The exponent is only taken when it has digits.
+        if let Some(b'e' | b'E') = self.peek_byte() {
+            let mantissa = self.pos;
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek_byte() {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.pos = mantissa;
+            }
+        }
+
+        let value = self.data[start..self.pos]
+            .parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or(ParseError {
+                position: start,
+                kind: ParseErrorKind::ExpectedNumber,
+            })?;
+
+        self.skip_separator()?;
+        Ok(value)
+    }
+
+    fn point(&mut self) -> Result<Offset, ParseError> {
+        Ok(Offset::new(self.number()?, self.number()?))
+    }
+
+    /// Arc flags may be followed by the next value without a separator.
+    fn flag(&mut self) -> Result<bool, ParseError> {
+        self.skip_whitespace();
+        let flag = match self.peek_byte() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error(ParseErrorKind::ExpectedFlag)),
+        };
+        self.pos += 1;
+        self.skip_separator()?;
+        Ok(flag)
+    }
+}
@@ -1,6 +1,10 @@
 use crate::{
-    internals::{Batch, Draw, GpuBatch, Tessellator, Vertex},
-    FillRule, Images, IntoPaint, LineJoin, Path, Pipeline, Rect, Stroke, Transform,
+    clip::ClipDraw,
+    internals::{Batch, Draw, GpuBatch, Instance, Tessellator, Vertex},
+    layer::{BackdropFilter, Layer, LayerBlur, LayerComposite, LayerTargets},
+    pass::{Pass, PassEncoder},
+    BlendMode, Color, FillRule, GradientShape, ImageFilter, Images, IntoPaint, LineJoin, Offset,
+    Paint, Path, Pipeline, Rect, Scissor, Stroke, Transform,
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -13,7 +17,7 @@ pub struct DrawIndexed {
 
 impl DrawIndexed {
     #[inline]
-    fn new(start: u32, end: u32, base_vertex: i32, instance: u32) -> Self {
+    pub(crate) fn new(start: u32, end: u32, base_vertex: i32, instance: u32) -> Self {
         Self {
             start,
             end,
@@ -21,17 +25,6 @@ impl DrawIndexed {
             instance,
         }
     }
-
-    #[inline]
-    fn call<'a>(
-        &self,
-        rpass: &mut wgpu::RenderBundleEncoder<'a>,
-        pipeline: &'a wgpu::RenderPipeline,
-    ) {
-        let instances = self.instance..self.instance + 1;
-        rpass.set_pipeline(pipeline);
-        rpass.draw_indexed(self.start..self.end, self.base_vertex, instances);
-    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -47,103 +40,238 @@ pub enum DrawCall<Key> {
     ImageUnmultiplied(DrawIndexed),
     ImageFont(DrawIndexed),
 
+    PatternConvex(DrawIndexed),
+    PatternFringesNonZero(DrawIndexed),
+    PatternFringesEvenOdd(DrawIndexed),
+    PatternQuadNonZero(DrawIndexed),
+    PatternQuadEvenOdd(DrawIndexed),
+
+    ClipStencil(DrawIndexed),
+    ClipNonZero(DrawIndexed),
+    ClipEvenOdd(DrawIndexed),
+    ClipReset(DrawIndexed),
+
     BindImage(Key),
+    /// Binds gradient ramps in place of an image for the following pattern draws.
+    BindRamps,
     Stroke {
         start: u32,
         end: u32,
         base_vertex: i32,
         instance: u32,
     },
+    PatternStroke {
+        start: u32,
+        end: u32,
+        base_vertex: i32,
+        instance: u32,
+    },
+
+    /// Redirects the following draws into the layer target until the matching [`DrawCall::EndLayer`].
+    BeginLayer {
+        index: usize,
+        bounds: Rect,
+    },
+    EndLayer(LayerComposite),
+
+    /// Sets the blend mode of the following fill and stroke draws.
+    Blend(BlendMode),
+    /// Copies the current layer target into the given one, so the following
+    /// advanced blend draws can read what is under them.
+    Backdrop(usize),
+    /// Blurs the current layer before it is composited.
+    Blur(LayerBlur),
+    /// Blurs the current layer into the backdrop target.
+    Filter(BackdropFilter),
+    /// Replaces the current layer under the shape with the filtered backdrop.
+    FilterConvex(DrawIndexed),
+    FilterFringes(DrawIndexed),
+    FilterQuad(DrawIndexed),
+    /// Redirects all draws into a layer target composited onto the target at the end.
+    ///
+    /// Recorded first when draws outside of layers read the backdrop,
+    /// since the target itself can't be sampled.
+    /// The layer starts as the `backdrop` of the composite, which holds a copy of the target.
+    Root(LayerComposite),
 }
 
 #[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
-pub struct Picture(pub(crate) wgpu::RenderBundle);
+pub struct Picture {
+    /// Passes in the order they have to be rendered.
+    pub(crate) passes: Vec<Pass<wgpu::RenderBundle>>,
+}
 
 impl<'a> std::iter::IntoIterator for &'a Picture {
     type Item = &'a wgpu::RenderBundle;
-    type IntoIter = std::iter::Once<Self::Item>;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
 
+    /// Bundles drawn into the target itself, without passes of layers.
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        std::iter::once(&self.0)
+        let main = self.passes.iter().filter(|pass| pass.layer.is_none());
+        main.map(|pass| &pass.draws).collect::<Vec<_>>().into_iter()
     }
 }
 
 impl Picture {
-    pub fn new<Key: Eq + std::hash::Hash>(
+    /// Encodes draw calls into render bundles.
+    ///
+    /// Draws between [`DrawCall::BeginLayer`] and [`DrawCall::EndLayer`] are encoded
+    /// into separate passes for the layer target. Layers that are never ended are dropped.
+    /// The parent pass is split where a layer is composited, so the layer target
+    /// can be reused by the following layers, and where the backdrop is read.
+    pub fn new<Key: Clone + Eq + std::hash::Hash>(
         device: &wgpu::Device,
         viewport: &wgpu::BindGroup,
         offset: u32,
         pipeline: &Pipeline,
         batch: &GpuBatch,
         images: &Images<Key>,
+        layers: &LayerTargets<Key>,
         calls: &[DrawCall<Key>],
     ) -> Self {
-        let mut rpass = device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
-            label: Some("reui::Picture"),
-            color_formats: &[Some(wgpu::TextureFormat::Rgba8UnormSrgb)],
-            depth_stencil: Some(wgpu::RenderBundleDepthStencil {
-                format: wgpu::TextureFormat::Depth24PlusStencil8,
-                depth_read_only: true,
-                stencil_read_only: false,
-            }),
-            sample_count: 1,
-            multiview: None,
+        let ramps = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("reui::RampTexture"),
+            layout: &images.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(&images.default_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&batch.ramps.view),
+                },
+            ],
         });
 
-        rpass.set_bind_group(0, viewport, &[offset]);
-
-        rpass.set_index_buffer(batch.indices.slice(..), wgpu::IndexFormat::Uint32);
-        rpass.set_vertex_buffer(0, batch.vertices.slice(..));
-        rpass.set_vertex_buffer(1, batch.instances.slice(..));
-
-        for call in calls {
-            match call {
-                DrawCall::BindImage(image) => rpass.set_bind_group(1, &images[image].bind, &[]),
-
-                DrawCall::Convex(draw) => draw.call(&mut rpass, &pipeline.convex),
-                DrawCall::ConvexSimple(draw) => draw.call(&mut rpass, &pipeline.convex_simple),
-                DrawCall::Stencil(draw) => draw.call(&mut rpass, &pipeline.fill_stencil),
-                DrawCall::QuadNonZero(draw) => draw.call(&mut rpass, &pipeline.fill_quad_non_zero),
-                DrawCall::QuadEvenOdd(draw) => draw.call(&mut rpass, &pipeline.fill_quad_even_odd),
-                DrawCall::FringesNonZero(draw) => draw.call(&mut rpass, &pipeline.fringes_non_zero),
-                DrawCall::FringesEvenOdd(draw) => draw.call(&mut rpass, &pipeline.fringes_even_odd),
-                DrawCall::ImagePremultiplied(draw) => {
-                    draw.call(&mut rpass, &pipeline.premultiplied)
-                }
-                DrawCall::ImageUnmultiplied(draw) => draw.call(&mut rpass, &pipeline.unmultiplied),
-                DrawCall::ImageFont(draw) => draw.call(&mut rpass, &pipeline.font),
-
-                &DrawCall::Stroke {
-                    start,
-                    end,
-                    base_vertex,
-                    instance,
-                } => {
-                    rpass.set_pipeline(&pipeline.stroke_base);
-                    rpass.draw_indexed(start..end, base_vertex, instance..instance + 1);
-
-                    rpass.set_pipeline(&pipeline.fringes_non_zero);
-                    rpass.draw_indexed(start..end, base_vertex, instance + 1..instance + 2);
-
-                    rpass.set_pipeline(&pipeline.stroke_stencil);
-                    rpass.draw_indexed(start..end, base_vertex, 0..1);
-                }
+        let encoder = || {
+            let mut rpass =
+                device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+                    label: Some("reui::Picture"),
+                    color_formats: &[Some(pipeline.target.color)],
+                    depth_stencil: Some(wgpu::RenderBundleDepthStencil {
+                        format: pipeline.target.depth_stencil,
+                        depth_read_only: true,
+                        stencil_read_only: false,
+                    }),
+                    sample_count: pipeline.target.sample_count,
+                    multiview: None,
+                });
+
+            rpass.set_bind_group(0, viewport, &[offset]);
+            // Advanced blend pipelines expect an image even for solid paints.
+            rpass.set_bind_group(1, &ramps, &[]);
+
+            rpass.set_index_buffer(batch.indices.slice(..), wgpu::IndexFormat::Uint32);
+            rpass.set_vertex_buffer(0, batch.vertices.slice(..));
+            rpass.set_vertex_buffer(1, batch.instances.slice(..));
+            BundleEncoder {
+                rpass,
+                images,
+                layers,
+                ramps: &ramps,
             }
+        };
+
+        Self {
+            passes: crate::pass::passes(calls, pipeline, layers.size(), encoder),
         }
+    }
+}
 
-        Self(rpass.finish(&wgpu::RenderBundleDescriptor {
+/// Render bundle encoder of a pass with the resources its bindings come from.
+struct BundleEncoder<'a, Key> {
+    rpass: wgpu::RenderBundleEncoder<'a>,
+    images: &'a Images<Key>,
+    layers: &'a LayerTargets<Key>,
+    ramps: &'a wgpu::BindGroup,
+}
+
+impl<Key: Clone + Eq + std::hash::Hash> BundleEncoder<'_, Key> {
+    /// Binds the layer target as an image, returns `false` if there is no such target.
+    fn bind_group(&mut self, group: u32, index: usize) -> bool {
+        let image = self.layers.key(index).and_then(|key| self.images.get(key));
+        if let Some(image) = image {
+            self.rpass.set_bind_group(group, &image.bind, &[]);
+        }
+        image.is_some()
+    }
+}
+
+impl<'a, Key: Clone + Eq + std::hash::Hash> PassEncoder for BundleEncoder<'a, Key> {
+    type Pipeline = &'a wgpu::RenderPipeline;
+    type Image = &'a Key;
+    type Draws = wgpu::RenderBundle;
+
+    fn bind_image(&mut self, image: &'a Key) {
+        self.rpass.set_bind_group(1, &self.images[image].bind, &[]);
+    }
+
+    fn bind_ramps(&mut self) {
+        self.rpass.set_bind_group(1, self.ramps, &[]);
+    }
+
+    fn bind_layer(&mut self, index: usize) -> bool {
+        self.bind_group(1, index)
+    }
+
+    fn bind_backdrop(&mut self, index: usize) {
+        self.bind_group(2, index);
+    }
+
+    fn draw(&mut self, pipeline: &'a wgpu::RenderPipeline, call: DrawIndexed) {
+        let instances = call.instance..call.instance + 1;
+        self.rpass.set_pipeline(pipeline);
+        self.rpass
+            .draw_indexed(call.start..call.end, call.base_vertex, instances);
+    }
+
+    fn finish(self) -> wgpu::RenderBundle {
+        self.rpass.finish(&wgpu::RenderBundleDescriptor {
             label: Some("reui::Picture"),
-        }))
+        })
     }
 }
 
-#[derive(Default)]
+/// Largest standard deviation of layer blurs in target pixels.
+pub const MAX_BLUR_SIGMA: f32 = 64.0;
+
 #[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
 pub struct Recorder<Key> {
     pub(crate) calls: Vec<DrawCall<Key>>,
     pub(crate) batch: Batch,
     pub(crate) cache: Tessellator,
+    pub(crate) scissor: Scissor,
+    pub(crate) clips: Vec<ClipDraw>,
+    pub(crate) layers: Vec<Layer>,
+    pub(crate) layer_count: usize,
+    pub(crate) blend_mode: BlendMode,
+    pub(crate) root: Option<usize>,
+    pub(crate) backdrop: Option<usize>,
+    pub(crate) scratch: Option<usize>,
+    pub(crate) free_layers: Vec<usize>,
+    pub(crate) antialias: bool,
+}
+
+impl<Key> Default for Recorder<Key> {
+    fn default() -> Self {
+        Self {
+            calls: Vec::new(),
+            batch: Batch::default(),
+            cache: Tessellator::default(),
+            scissor: Scissor::none(),
+            clips: Vec::new(),
+            layers: Vec::new(),
+            layer_count: 0,
+            blend_mode: BlendMode::SrcOver,
+            root: None,
+            backdrop: None,
+            scratch: None,
+            free_layers: Vec::new(),
+            antialias: true,
+        }
+    }
 }
 
 impl<Key> Recorder<Key> {
@@ -151,25 +279,467 @@ impl<Key> Recorder<Key> {
         self.calls.clear();
         self.batch.clear();
         self.cache.clear();
+        self.scissor = Scissor::none();
+        self.clips.clear();
+        self.layers.clear();
+        self.layer_count = 0;
+        self.blend_mode = BlendMode::SrcOver;
+        self.root = None;
+        self.backdrop = None;
+        self.scratch = None;
+        self.free_layers.clear();
+    }
+
+    /// Geometry and instances of the recorded draws.
+    pub fn batch(&self) -> &Batch {
+        &self.batch
+    }
+
+    /// Recorded draw calls in the order they are encoded.
+    pub fn calls(&self) -> &[DrawCall<Key>] {
+        &self.calls
+    }
+
+    /// Enables the 1px fringes antialiasing fills and strokes.
+    ///
+    /// Disable them for multisampled targets, where fringes of abutting shapes show seams.
+    /// Kept by [`Recorder::clear`].
+    pub fn set_antialias(&mut self, antialias: bool) {
+        self.antialias = antialias;
+    }
+
+    /// Sets the scissor applied to all following draws.
+    pub fn set_scissor(&mut self, scissor: Scissor) {
+        self.scissor = scissor;
+    }
+
+    /// Returns the number of paths in the clip stack.
+    pub fn clip_depth(&self) -> usize {
+        self.clips.len()
+    }
+
+    /// Intersects the current clip with the path.
+    ///
+    /// Clip paths are rendered into the stencil buffer and are not antialiased.
+    pub fn clip_path(&mut self, path: &Path, transform: Transform, fill_rule: FillRule) {
+        let commands = path.transform_iter(transform);
+        self.cache.flatten(commands, 0.25, 0.01);
+
+        let stencil = match self
+            .cache
+            .expand_fill(&mut self.batch, 0.0, LineJoin::Miter, 2.4)
+        {
+            Draw::Convex {
+                base_vertex,
+                start,
+                end,
+            } => DrawIndexed::new(start, end, base_vertex, 0),
+            Draw::Concave {
+                base_vertex, fill, ..
+            } => DrawIndexed::new(fill.start, fill.end, base_vertex, 0),
+        };
+        let cover = self.cover_quad();
+
+        let clip = ClipDraw {
+            stencil,
+            cover,
+            fill_rule,
+        };
+        self.push_clip_draw(clip);
+        self.clips.push(clip);
+    }
+
+    /// Pops clip paths until only `depth` of them are left.
+    ///
+    /// The stencil clip is rebuilt from the remaining paths.
+    pub fn reset_clip(&mut self, depth: usize) {
+        if depth >= self.clips.len() {
+            return;
+        }
+
+        self.clips.truncate(depth);
+
+        let cover = self.cover_quad();
+        self.calls.push(DrawCall::ClipReset(cover));
+
+        for index in 0..self.clips.len() {
+            let clip = self.clips[index];
+            self.push_clip_draw(clip);
+        }
+    }
+
+    /// Returns the number of open layers.
+    pub fn layer_depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns the number of layer targets required by the recorded draws.
+    ///
+    /// Targets of composited layers are reused, so this grows with the nesting depth.
+    pub fn layer_count(&self) -> usize {
+        self.layer_count
+    }
+
+    /// Redirects the following draws into an offscreen layer.
+    ///
+    /// `bounds` are in target pixels, rounded out, and limit both drawing into
+    /// the layer and compositing it back with `opacity` and `blend_mode`.
+    /// The layer starts transparent and without clip paths, clips active at this point
+    /// are applied when the layer is composited.
+    pub fn save_layer(&mut self, bounds: Rect, opacity: f32, blend_mode: BlendMode) {
+        let bounds = Rect {
+            min: bounds.min.floor(),
+            max: bounds.max.ceil(),
+        };
+
+        let index = match self.free_layers.pop() {
+            Some(index) => index,
+            None => self.next_layer(),
+        };
+        self.layers.push(Layer {
+            index,
+            bounds,
+            opacity: opacity.clamp(0.0, 1.0),
+            blend_mode,
+            blur: 0.0,
+        });
+        self.calls.push(DrawCall::BeginLayer { index, bounds });
+    }
+
+    /// Blurs the innermost open layer with a Gaussian before it is composited.
+    ///
+    /// `sigma` is the standard deviation in target pixels, limited to [`MAX_BLUR_SIGMA`].
+    /// Does nothing outside of layers.
+    pub fn blur_layer(&mut self, sigma: f32) {
+        if let Some(layer) = self.layers.last_mut() {
+            layer.blur = sigma.clamp(0.0, MAX_BLUR_SIGMA);
+        }
+    }
+
+    /// Composites open layers into their parents until only `depth` of them are left.
+    pub fn restore_layer(&mut self, depth: usize) {
+        while self.layers.len() > depth {
+            let Some(layer) = self.layers.pop() else {
+                break;
+            };
+
+            let Rect { min, max } = layer.bounds;
+            let base_vertex = self.batch.base_vertex();
+            let indices = self.batch.push_strip(
+                0,
+                &[
+                    Vertex::new([max.x, max.y], [1.0, 1.0]),
+                    Vertex::new([max.x, min.y], [1.0, 0.0]),
+                    Vertex::new([min.x, max.y], [0.0, 1.0]),
+                    Vertex::new([min.x, min.y], [0.0, 0.0]),
+                ],
+            );
+            let quad = DrawIndexed::new(indices.start, indices.end, base_vertex, 0);
+
+            if layer.blur > 0.0 {
+                let scratch = self.scratch();
+                let white = Color::new(1.0, 1.0, 1.0, 1.0);
+                let pass = |extent| Instance {
+                    extent,
+                    ..Instance::image(white.into(), Scissor::none())
+                };
+                let horizontal = self.batch.instance(pass([layer.blur, 0.0]));
+                let vertical = self.batch.instance(pass([0.0, layer.blur]));
+                self.calls.push(DrawCall::Blur(LayerBlur {
+                    scratch,
+                    horizontal: DrawIndexed {
+                        instance: horizontal,
+                        ..quad
+                    },
+                    vertical: DrawIndexed {
+                        instance: vertical,
+                        ..quad
+                    },
+                }));
+            }
+
+            let color = Color::new(1.0, 1.0, 1.0, layer.opacity);
+            let instance = Instance {
+                blend: f32::from(layer.blend_mode as u8),
+                ..Instance::image(color.into(), self.scissor)
+            };
+            let instance = self.batch.instance(instance);
+            let draw = DrawIndexed { instance, ..quad };
+
+            let backdrop = layer.blend_mode.is_advanced().then(|| self.backdrop());
+            self.calls.push(DrawCall::EndLayer(LayerComposite {
+                index: layer.index,
+                blend_mode: layer.blend_mode,
+                backdrop,
+                unpremultiply: false,
+                draw,
+            }));
+
+            // Passes of the following layers are rendered after this composite.
+            self.free_layers.push(layer.index);
+        }
+    }
+
+    /// Fills the path blurred with a Gaussian, offset in local coordinates.
+    ///
+    /// `sigma` is the standard deviation in local units.
+    /// The path is rendered into a layer covering its blurred bounds.
+    pub fn shadow(
+        &mut self,
+        path: &Path,
+        color: Color,
+        transform: Transform,
+        offset: Offset,
+        sigma: f32,
+        blend_mode: BlendMode,
+    ) {
+        let transform = transform * Transform::translate(offset.x, offset.y);
+        let sigma = (sigma * transform.average_scale()).min(MAX_BLUR_SIGMA);
+        if sigma <= 0.0 {
+            self.fill(path, color, transform, FillRule::NonZero, blend_mode, true);
+            return;
+        }
+
+        let Rect { min, max } = path.control_bounds(transform);
+        let spread = Offset::new(sigma, sigma) * 3.0 + Offset::new(1.0, 1.0);
+        let bounds = Rect::new(min - spread, max + spread);
+        if bounds.is_empty() {
+            return;
+        }
+
+        let depth = self.layers.len();
+        let scissor = self.scissor;
+        self.save_layer(bounds, 1.0, blend_mode);
+        self.blur_layer(sigma);
+        self.scissor = Scissor::none();
+        self.fill(
+            path,
+            color,
+            transform,
+            FillRule::NonZero,
+            BlendMode::SrcOver,
+            true,
+        );
+        self.scissor = scissor;
+        self.restore_layer(depth);
+    }
+
+    fn next_layer(&mut self) -> usize {
+        let index = self.layer_count;
+        self.layer_count += 1;
+        index
+    }
+
+    /// Replaces what is drawn under the path with its filtered copy.
+    ///
+    /// Draws outside of layers are redirected into the root layer, since the target can't be read.
+    pub fn backdrop_filter(&mut self, path: &Path, transform: Transform, filter: ImageFilter) {
+        let ImageFilter::Blur(sigma) = filter;
+        let sigma = (sigma * transform.average_scale()).min(MAX_BLUR_SIGMA);
+        if sigma <= 0.0 || path.is_empty() {
+            return;
+        }
+
+        let Rect { min, max } = path.control_bounds(transform);
+        let bounds = Rect::new(min.floor(), max.ceil());
+        let spread = Offset::new(0.0, (sigma * 3.0).ceil());
+        let source = Rect::new(bounds.min - spread, bounds.max + spread);
+
+        let backdrop = self.backdrop();
+        let scratch = self.scratch();
+
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let quad = self.cover_quad();
+        let pass = |extent| Instance {
+            extent,
+            ..Instance::image(white.into(), Scissor::none())
+        };
+        let horizontal = self.batch.instance(pass([sigma, 0.0]));
+        let vertical = self.batch.instance(pass([0.0, sigma]));
+
+        let paint: Paint<Key> = white.into_paint(transform);
+        let fringe_width = if self.antialias { 1.0 } else { 0.0 };
+        let raw = self.paint_instance(&paint, fringe_width, fringe_width, -1.0);
+        let instance = self.batch.instance(raw);
+
+        self.calls.push(DrawCall::Filter(BackdropFilter {
+            backdrop,
+            scratch,
+            source,
+            bounds,
+            horizontal: DrawIndexed {
+                instance: horizontal,
+                ..quad
+            },
+            vertical: DrawIndexed {
+                instance: vertical,
+                ..quad
+            },
+        }));
+
+        let commands = path.transform_iter(transform);
+        self.cache.flatten(commands, 0.25, 0.01);
+
+        match self
+            .cache
+            .expand_fill(&mut self.batch, fringe_width, LineJoin::Miter, 2.4)
+        {
+            Draw::Convex {
+                base_vertex,
+                start,
+                end,
+            } => {
+                let draw = DrawIndexed::new(start, end, base_vertex, instance);
+                self.calls.push(DrawCall::FilterConvex(draw));
+            }
+            Draw::Concave {
+                base_vertex,
+                fill,
+                stroke,
+                quad,
+            } => {
+                let stencil = DrawIndexed::new(fill.start, fill.end, base_vertex, instance);
+                let stroke = DrawIndexed::new(stroke.start, stroke.end, base_vertex, instance);
+                let quad = DrawIndexed::new(quad.start, quad.end, base_vertex, instance);
+                self.calls.push(DrawCall::Stencil(stencil));
+                self.calls.push(DrawCall::FilterFringes(stroke));
+                self.calls.push(DrawCall::FilterQuad(quad));
+            }
+        }
+    }
+
+    /// Replaces the target with straight alpha colors instead of blending premultiplied ones,
+    /// for targets that are drawn as images later.
+    ///
+    /// All draws are redirected into the root layer, which is converted at the end.
+    pub fn unpremultiply(&mut self) {
+        self.root();
+        if let Some(DrawCall::Root(composite)) = self.calls.first_mut() {
+            composite.unpremultiply = true;
+        }
+    }
+
+    /// Returns the layer target that blurred layers are rendered through.
+    fn scratch(&mut self) -> usize {
+        match self.scratch {
+            Some(index) => index,
+            None => {
+                let index = self.next_layer();
+                self.scratch = Some(index);
+                index
+            }
+        }
+    }
+
+    /// Returns the layer target that advanced blend modes copy the backdrop into.
+    ///
+    /// Draws outside of layers are redirected into the root layer first,
+    /// since the target itself can't be read.
+    fn backdrop(&mut self) -> usize {
+        if self.layers.is_empty() {
+            self.root();
+        }
+        self.backdrop_target()
+    }
+
+    fn backdrop_target(&mut self) -> usize {
+        match self.backdrop {
+            Some(index) => index,
+            None => {
+                let index = self.next_layer();
+                self.backdrop = Some(index);
+                index
+            }
+        }
+    }
+
+    /// Redirects all draws into the root layer, composited onto the target at the end.
+    ///
+    /// The root layer starts with the contents of the target,
+    /// copied into the backdrop target before the first pass.
+    fn root(&mut self) -> usize {
+        if let Some(index) = self.root {
+            return index;
+        }
+
+        let index = self.next_layer();
+        let seed = self.backdrop_target();
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let instance = self
+            .batch
+            .instance(Instance::image(white.into(), Scissor::none()));
+        let draw = DrawIndexed {
+            instance,
+            ..self.cover_quad()
+        };
+
+        self.root = Some(index);
+        self.calls.insert(
+            0,
+            DrawCall::Root(LayerComposite {
+                index,
+                blend_mode: BlendMode::SrcOver,
+                backdrop: Some(seed),
+                unpremultiply: false,
+                draw,
+            }),
+        );
+        index
+    }
+
+    /// Switches the blend mode of the following fill and stroke draws.
+    fn blend(&mut self, blend_mode: BlendMode) {
+        if self.blend_mode != blend_mode {
+            self.blend_mode = blend_mode;
+            self.calls.push(DrawCall::Blend(blend_mode));
+        }
+
+        if blend_mode.is_advanced() {
+            let index = self.backdrop();
+            self.calls.push(DrawCall::Backdrop(index));
+        }
+    }
+
+    fn push_clip_draw(&mut self, clip: ClipDraw) {
+        self.calls.push(DrawCall::ClipStencil(clip.stencil));
+        self.calls.push(match clip.fill_rule {
+            FillRule::NonZero => DrawCall::ClipNonZero(clip.cover),
+            FillRule::EvenOdd => DrawCall::ClipEvenOdd(clip.cover),
+        });
+    }
+
+    /// Quad that covers any reasonable viewport.
+    fn cover_quad(&mut self) -> DrawIndexed {
+        let large = 1e5;
+        let base_vertex = self.batch.base_vertex();
+        let indices = self.batch.push_strip(
+            0,
+            &[
+                Vertex::new([large, large], [0.5, 1.0]),
+                Vertex::new([large, -large], [0.5, 1.0]),
+                Vertex::new([-large, large], [0.5, 1.0]),
+                Vertex::new([-large, -large], [0.5, 1.0]),
+            ],
+        );
+        DrawIndexed::new(indices.start, indices.end, base_vertex, 0)
     }
 
     pub fn stroke(
         &mut self,
         path: &Path,
-        paint: impl IntoPaint,
+        paint: impl IntoPaint<Key>,
         mut stroke: Stroke,
         transform: Transform,
+        blend_mode: BlendMode,
         antialias: bool,
     ) {
         let mut paint = paint.into_paint(transform);
+        self.blend(blend_mode);
 
-        let average_scale = {
-            let sx = (transform.sx * transform.sx + transform.shx * transform.shx).sqrt();
-            let sy = (transform.shy * transform.shy + transform.sy * transform.sy).sqrt();
-            (sx + sy) * 0.5
-        };
+        let average_scale = transform.average_scale();
 
         stroke.width = (stroke.width * average_scale).max(0.0);
+        stroke.dash = stroke.dash.scale(average_scale);
 
         let fringe_width = 1.0;
 
@@ -180,14 +750,22 @@ impl<Key> Recorder<Key> {
             let coverage = alpha * alpha;
             paint.inner_color.alpha *= coverage;
             paint.outer_color.alpha *= coverage;
+            for (_, color) in &mut paint.stops {
+                color.alpha *= coverage;
+            }
             stroke.width = fringe_width;
         }
 
-        let fringe_width = if antialias { fringe_width } else { 0.0 };
+        let fringe_width = if antialias && self.antialias {
+            fringe_width
+        } else {
+            0.0
+        };
 
         let commands = path.transform_iter(transform);
         let tess_tol = 0.25;
-        self.cache.flatten(commands, tess_tol, 0.01);
+        self.cache
+            .flatten_dashed(commands, &stroke.dash, tess_tol, 0.01);
 
         stroke.width *= 0.5;
 
@@ -197,35 +775,60 @@ impl<Key> Recorder<Key> {
             .expand_stroke(&mut self.batch, stroke, fringe_width, tess_tol);
 
         let stroke_thr = 1.0 - 0.5 / 255.0;
-        let first = paint.to_instance(stroke.width, fringe_width, stroke_thr);
-        let instance = self.batch.instance(first);
-
-        let second = paint.to_instance(stroke.width, fringe_width, -1.0);
-        let _ = self.batch.instance(second);
+        let first = self.paint_instance(&paint, stroke.width, fringe_width, stroke_thr);
+        let instance = self.batch.instance(Instance {
+            blend: f32::from(blend_mode as u8),
+            ..first
+        });
 
-        self.calls.push(DrawCall::Stroke {
-            start: indices.start,
-            end: indices.end,
-            base_vertex,
-            instance,
+        let second = self.paint_instance(&paint, stroke.width, fringe_width, -1.0);
+        let _ = self.batch.instance(Instance {
+            blend: f32::from(blend_mode as u8),
+            ..second
         });
+
+        let (start, end) = (indices.start, indices.end);
+        if self.bind_paint(paint, &first) {
+            self.calls.push(DrawCall::PatternStroke {
+                start,
+                end,
+                base_vertex,
+                instance,
+            });
+        } else {
+            self.calls.push(DrawCall::Stroke {
+                start,
+                end,
+                base_vertex,
+                instance,
+            });
+        }
     }
 
     pub fn fill(
         &mut self,
         path: &Path,
-        paint: impl IntoPaint,
+        paint: impl IntoPaint<Key>,
         transform: Transform,
         fill_rule: FillRule,
+        blend_mode: BlendMode,
         antialias: bool,
     ) {
         let paint = paint.into_paint(transform);
+        self.blend(blend_mode);
 
-        let fringe_width = if antialias { 1.0 } else { 0.0 };
+        let fringe_width = if antialias && self.antialias {
+            1.0
+        } else {
+            0.0
+        };
 
         // Setup uniforms for draw calls
-        let raw = paint.to_instance(fringe_width, fringe_width, -1.0);
-        let instance = self.batch.instance(raw);
+        let raw = self.paint_instance(&paint, fringe_width, fringe_width, -1.0);
+        let instance = self.batch.instance(Instance {
+            blend: f32::from(blend_mode as u8),
+            ..raw
+        });
 
         let commands = path.transform_iter(transform);
         self.cache.flatten(commands, 0.25, 0.01);
@@ -234,6 +837,9 @@ impl<Key> Recorder<Key> {
             .cache
             .expand_fill(&mut self.batch, fringe_width, LineJoin::Miter, 2.4);
 
+        let simple = paint.inner_color == paint.outer_color;
+        let pattern = self.bind_paint(paint, &raw);
+
         match draw {
             // Bounding box fill quad not needed for convex fill
             Draw::Convex {
@@ -242,7 +848,9 @@ impl<Key> Recorder<Key> {
                 end,
             } => {
                 let draw = DrawIndexed::new(start, end, base_vertex, instance);
-                if paint.inner_color == paint.outer_color {
+                if pattern {
+                    self.calls.push(DrawCall::PatternConvex(draw));
+                } else if simple {
                     self.calls.push(DrawCall::ConvexSimple(draw));
                 } else {
                     self.calls.push(DrawCall::Convex(draw));
@@ -260,18 +868,65 @@ impl<Key> Recorder<Key> {
                 let quad = DrawIndexed::new(quad.start, quad.end, base_vertex, instance);
 
                 self.calls.push(DrawCall::Stencil(stenicl));
-                self.calls.push(match fill_rule {
-                    FillRule::NonZero => DrawCall::FringesNonZero(stroke),
-                    FillRule::EvenOdd => DrawCall::FringesEvenOdd(stroke),
+                self.calls.push(match (fill_rule, pattern) {
+                    (FillRule::NonZero, false) => DrawCall::FringesNonZero(stroke),
+                    (FillRule::EvenOdd, false) => DrawCall::FringesEvenOdd(stroke),
+                    (FillRule::NonZero, true) => DrawCall::PatternFringesNonZero(stroke),
+                    (FillRule::EvenOdd, true) => DrawCall::PatternFringesEvenOdd(stroke),
                 });
-                self.calls.push(match fill_rule {
-                    FillRule::NonZero => DrawCall::QuadNonZero(quad),
-                    FillRule::EvenOdd => DrawCall::QuadEvenOdd(quad),
+                self.calls.push(match (fill_rule, pattern) {
+                    (FillRule::NonZero, false) => DrawCall::QuadNonZero(quad),
+                    (FillRule::EvenOdd, false) => DrawCall::QuadEvenOdd(quad),
+                    (FillRule::NonZero, true) => DrawCall::PatternQuadNonZero(quad),
+                    (FillRule::EvenOdd, true) => DrawCall::PatternQuadEvenOdd(quad),
                 });
             }
         }
     }
 
+    /// Converts the paint to an instance, gradients with stops are rasterized into a ramp.
+    ///
+    /// Gradients past `MAX_RAMP_ROWS` keep only their inner and outer colors.
+    fn paint_instance(
+        &mut self,
+        paint: &Paint<Key>,
+        width: f32,
+        fringe: f32,
+        stroke_thr: f32,
+    ) -> Instance {
+        let mut raw = paint.to_instance(width, fringe, stroke_thr, self.scissor);
+        let row = paint
+            .has_ramp()
+            .then(|| self.batch.ramp(&paint.color_stops()));
+        if let Some(row) = row.flatten() {
+            raw.inner_color = [255; 4];
+            raw.outer_color = [255; 4];
+            let shape = match paint.shape {
+                GradientShape::Box => 0.0,
+                GradientShape::Sweep { .. } => 1.0,
+                GradientShape::Conical { .. } => 2.0,
+            };
+            raw.ramp = [row as f32, f32::from(paint.spread as u8 + 1), shape];
+        }
+        raw
+    }
+
+    /// Binds the image or the gradient ramps of the paint.
+    ///
+    /// Returns `true` if the paint is drawn with pattern pipelines.
+    fn bind_paint(&mut self, paint: Paint<Key>, instance: &Instance) -> bool {
+        // Ramps are bound only if `paint_instance` found a row for the gradient.
+        if instance.ramp[1] > 0.0 {
+            self.calls.push(DrawCall::BindRamps);
+            true
+        } else if let Some(image) = paint.image {
+            self.calls.push(DrawCall::BindImage(image));
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn blit_premultiplied(&mut self, rect: Rect, transform: Transform, image: Key) {
         let Rect { min, max } = rect;
         let base_vertex = self.batch.base_vertex();
@@ -284,7 +939,8 @@ impl<Key> Recorder<Key> {
                 Vertex::new([min.x, min.y], [0.0, 0.0]).transform(transform),
             ],
         );
-        let draw = DrawIndexed::new(indices.start, indices.end, base_vertex, 0);
+        let instance = self.batch.instance(Instance::image([0; 4], self.scissor));
+        let draw = DrawIndexed::new(indices.start, indices.end, base_vertex, instance);
         self.calls.push(DrawCall::BindImage(image));
         self.calls.push(DrawCall::ImagePremultiplied(draw));
     }
@@ -301,7 +957,8 @@ impl<Key> Recorder<Key> {
                 Vertex::new([min.x, min.y], [0.0, 0.0]).transform(transform),
             ],
         );
-        let draw = DrawIndexed::new(indices.start, indices.end, base_vertex, 0);
+        let instance = self.batch.instance(Instance::image([0; 4], self.scissor));
+        let draw = DrawIndexed::new(indices.start, indices.end, base_vertex, instance);
         self.calls.push(DrawCall::BindImage(image));
         self.calls.push(DrawCall::ImageUnmultiplied(draw));
     }
@@ -318,7 +975,9 @@ impl<Key> Recorder<Key> {
                 Vertex::new([min.x, min.y], [0.0, 0.0]).transform(transform),
             ],
         );
-        let draw = DrawIndexed::new(indices.start, indices.end, base_vertex, 0);
+        let black = [0, 0, 0, 255];
+        let instance = self.batch.instance(Instance::image(black, self.scissor));
+        let draw = DrawIndexed::new(indices.start, indices.end, base_vertex, instance);
         self.calls.push(DrawCall::BindImage(image));
         self.calls.push(DrawCall::ImageFont(draw));
     }
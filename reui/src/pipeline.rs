@@ -1,4 +1,5 @@
-use crate::{Offset, Transform};
+use crate::{BlendMode, Offset, Scissor, Transform};
+use std::collections::HashMap;
 
 #[repr(C)]
 #[derive(Clone, Copy, Default, bytemuck::Zeroable, bytemuck::Pod)]
@@ -36,40 +37,95 @@ pub struct Instance {
 
     pub stroke_mul: f32, // scale
     pub stroke_thr: f32, // threshold
+
+    pub scissor_mat: Transform,
+    pub scissor_ext: [f32; 2],
+    pub scissor_scale: [f32; 2],
+
+    /// Gradient ramp row, spread mode (zero for image patterns) and gradient shape.
+    pub ramp: [f32; 3],
+
+    /// Advanced [`BlendMode`] applied in the shader.
+    pub blend: f32,
 }
 
 impl Instance {
-    pub fn image(color: [u8; 4]) -> Self {
+    pub fn image(color: [u8; 4], scissor: Scissor) -> Self {
+        let (scissor_mat, scissor_ext, scissor_scale) = scissor.to_uniform();
         Self {
             inner_color: color,
+            outer_color: color,
+            scissor_mat,
+            scissor_ext,
+            scissor_scale,
             ..Self::default()
         }
     }
 }
 
-#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
-pub struct Pipeline {
-    pub view_layout: wgpu::BindGroupLayout,
-
-    pub premultiplied: wgpu::RenderPipeline,
-    pub unmultiplied: wgpu::RenderPipeline,
-    pub font: wgpu::RenderPipeline,
+/// Stencil bit used to mark pixels outside of the current clip path.
+const CLIP: u32 = 0x80;
+/// Stencil bits used as a winding counter while filling paths.
+const FILL: u32 = 0x7F;
+
+macro_rules! stencil {
+    ($comp:ident, $fail:ident, $pass:ident) => {
+        wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::$comp,
+            fail_op: wgpu::StencilOperation::$fail,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op: wgpu::StencilOperation::$pass,
+        }
+    };
+}
 
-    pub convex: wgpu::RenderPipeline,
-    pub convex_simple: wgpu::RenderPipeline,
+const ALWAYS_ZERO: wgpu::StencilFaceState = stencil!(Always, Zero, Zero);
+const NE_ZERO: wgpu::StencilFaceState = stencil!(NotEqual, Zero, Zero);
+const EQ_KEEP: wgpu::StencilFaceState = stencil!(Equal, Keep, Keep);
+const EQ_INVERT: wgpu::StencilFaceState = stencil!(Equal, Keep, Invert);
+const INCR_CLAMP: wgpu::StencilFaceState = stencil!(Equal, Keep, IncrementClamp);
+const INCR_WRAP: wgpu::StencilFaceState = stencil!(Equal, Keep, IncrementWrap);
+const DECR_WRAP: wgpu::StencilFaceState = stencil!(Equal, Keep, DecrementWrap);
+
+/// Formats of the attachments pictures are rendered into.
+///
+/// Layer targets are created with the same formats,
+/// since layers are encoded with the same pipelines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TargetDescriptor {
+    pub color: wgpu::TextureFormat,
+    pub depth_stencil: wgpu::TextureFormat,
+    pub sample_count: u32,
+}
 
-    pub fill_stencil: wgpu::RenderPipeline,
-    pub fill_quad_non_zero: wgpu::RenderPipeline,
-    pub fill_quad_even_odd: wgpu::RenderPipeline,
+impl Default for TargetDescriptor {
+    fn default() -> Self {
+        Self {
+            color: wgpu::TextureFormat::Rgba8UnormSrgb,
+            depth_stencil: wgpu::TextureFormat::Depth24PlusStencil8,
+            sample_count: 1,
+        }
+    }
+}
 
-    pub fringes_non_zero: wgpu::RenderPipeline,
-    pub fringes_even_odd: wgpu::RenderPipeline,
+/// Pipelines for every target configuration, created on demand.
+///
+/// Every pipeline shares the layouts, so viewport bind groups work with any of them.
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+pub struct PipelineCache {
+    pub view_layout: wgpu::BindGroupLayout,
+    layouts: Layouts,
+    pipelines: HashMap<TargetDescriptor, Pipeline>,
+}
 
-    pub stroke_base: wgpu::RenderPipeline,
-    pub stroke_stencil: wgpu::RenderPipeline,
+struct Layouts {
+    module: wgpu::ShaderModule,
+    backdrop: wgpu::PipelineLayout,
+    image: wgpu::PipelineLayout,
+    paint: wgpu::PipelineLayout,
 }
 
-impl Pipeline {
+impl PipelineCache {
     pub fn new(device: &wgpu::Device, image_layout: &wgpu::BindGroupLayout) -> Self {
         let view_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("reui::view_layout"),
@@ -85,13 +141,20 @@ impl Pipeline {
             }],
         });
 
-        let image_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        // The backdrop is bound after the image, so advanced draws keep their paint.
+        let backdrop = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("reui pipeline layout"),
+            bind_group_layouts: &[&view_layout, image_layout, image_layout],
+            push_constant_ranges: &[],
+        });
+
+        let image = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("reui pipeline layout"),
             bind_group_layouts: &[&view_layout, image_layout],
             push_constant_ranges: &[],
         });
 
-        let paint_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let paint = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("reui pipeline layout"),
             bind_group_layouts: &[&view_layout],
             push_constant_ranges: &[],
@@ -102,109 +165,334 @@ impl Pipeline {
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });
 
-        macro_rules! stencil {
-            ($comp:ident, $fail:ident, $pass:ident) => {
-                wgpu::StencilFaceState {
-                    compare: wgpu::CompareFunction::$comp,
-                    fail_op: wgpu::StencilOperation::$fail,
-                    depth_fail_op: wgpu::StencilOperation::Keep,
-                    pass_op: wgpu::StencilOperation::$pass,
-                }
-            };
+        Self {
+            view_layout,
+            layouts: Layouts {
+                module,
+                backdrop,
+                image,
+                paint,
+            },
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Pipelines for the target, created if they don't exist yet.
+    pub fn get(&mut self, device: &wgpu::Device, target: TargetDescriptor) -> &Pipeline {
+        self.pipelines
+            .entry(target)
+            .or_insert_with(|| Pipeline::new(device, &self.layouts, target))
+    }
+
+    /// Pipelines for the target, if they were created with [`PipelineCache::get`].
+    pub fn pipeline(&self, target: TargetDescriptor) -> Option<&Pipeline> {
+        self.pipelines.get(&target)
+    }
+}
+
+/// Bind groups following the viewport in a pipeline layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Bindings {
+    Paint,
+    Image,
+    Backdrop,
+}
+
+/// Creates pipelines sharing shader entry points with fixed-function states.
+pub(crate) trait PipelineBuilder: Copy {
+    type Pipeline;
+
+    fn blend(self, blend: wgpu::BlendState) -> Self;
+
+    fn pipeline(
+        &self,
+        write_color: bool,
+        back_culling: bool,
+        masks: [u32; 2],
+        front: wgpu::StencilFaceState,
+        back: wgpu::StencilFaceState,
+    ) -> Self::Pipeline;
+}
+
+/// Render pipelines for a single target configuration.
+pub struct Pipeline<P = wgpu::RenderPipeline> {
+    pub target: TargetDescriptor,
+
+    pub premultiplied: P,
+    pub unmultiplied: P,
+    pub font: P,
+
+    pub fill_stencil: P,
+    pub stroke_stencil: P,
+
+    pub clip_non_zero: P,
+    pub clip_even_odd: P,
+    pub clip_clear: P,
+    pub clip_reset: P,
+
+    /// Fill and stroke pipelines indexed by fixed-function [`BlendMode`].
+    pub draws: [DrawPipelines<P>; BlendMode::FIXED.len()],
+    /// Fill and stroke pipelines blending with the backdrop in the shader.
+    pub draws_advanced: DrawPipelines<P>,
+
+    /// Layer composite pipelines indexed by fixed-function [`BlendMode`].
+    pub layers: [P; BlendMode::FIXED.len()],
+    /// Layer composite pipeline blending with the backdrop in the shader.
+    pub layer_advanced: P,
+    /// Layer composite replacing the target with straight alpha colors.
+    pub layer_unpremultiply: P,
+
+    /// Single pass of the separable Gaussian blur, replacing the target.
+    pub blur: P,
+    /// Backdrop filter shapes, each draw clears the shape and adds the filtered backdrop.
+    pub filter_convex: [P; 2],
+    pub filter_fringes: [P; 2],
+    pub filter_quad: [P; 2],
+}
+
+/// Visible fill and stroke pipelines sharing a blend mode.
+pub struct DrawPipelines<P = wgpu::RenderPipeline> {
+    pub convex: P,
+    pub convex_simple: P,
+
+    pub fill_quad_non_zero: P,
+    pub fill_quad_even_odd: P,
+
+    pub fringes_non_zero: P,
+    pub fringes_even_odd: P,
+
+    pub stroke_base: P,
+
+    pub pattern_convex: P,
+    pub pattern_quad_non_zero: P,
+    pub pattern_quad_even_odd: P,
+    pub pattern_fringes_non_zero: P,
+    pub pattern_fringes_even_odd: P,
+    pub pattern_stroke: P,
+}
+
+impl<P> DrawPipelines<P> {
+    fn new<B: PipelineBuilder<Pipeline = P>>(main: B, convex_simple: B, pattern: B) -> Self {
+        // Every visible draw is rejected where the `CLIP` bit is set.
+        Self {
+            convex: main.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+            convex_simple: convex_simple.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+
+            fill_quad_non_zero: main.pipeline(true, true, [FILL, FILL], NE_ZERO, NE_ZERO),
+            fill_quad_even_odd: main.pipeline(true, true, [0x01, FILL], NE_ZERO, NE_ZERO),
+
+            fringes_non_zero: main.pipeline(true, true, [0xFF, 0], EQ_KEEP, EQ_KEEP),
+            fringes_even_odd: main.pipeline(true, true, [CLIP | 0x01, 0], EQ_KEEP, EQ_KEEP),
+
+            stroke_base: main.pipeline(true, true, [0xFF, FILL], INCR_CLAMP, INCR_CLAMP),
+
+            pattern_convex: pattern.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+            pattern_quad_non_zero: pattern.pipeline(true, true, [FILL, FILL], NE_ZERO, NE_ZERO),
+            pattern_quad_even_odd: pattern.pipeline(true, true, [0x01, FILL], NE_ZERO, NE_ZERO),
+            pattern_fringes_non_zero: pattern.pipeline(true, true, [0xFF, 0], EQ_KEEP, EQ_KEEP),
+            pattern_fringes_even_odd: pattern.pipeline(
+                true,
+                true,
+                [CLIP | 0x01, 0],
+                EQ_KEEP,
+                EQ_KEEP,
+            ),
+            pattern_stroke: pattern.pipeline(true, true, [0xFF, FILL], INCR_CLAMP, INCR_CLAMP),
         }
+    }
+}
 
-        const ALWAYS_ZERO: wgpu::StencilFaceState = stencil!(Always, Zero, Zero);
-        const ALWAYS_KEEP: wgpu::StencilFaceState = stencil!(Always, Keep, Keep);
-        const NE_ZERO: wgpu::StencilFaceState = stencil!(NotEqual, Zero, Zero);
-        const EQ_KEEP: wgpu::StencilFaceState = stencil!(Equal, Keep, Keep);
-        const INCR_CLAMP: wgpu::StencilFaceState = stencil!(Equal, Keep, IncrementClamp);
-        const INCR_WRAP: wgpu::StencilFaceState = stencil!(Always, Keep, IncrementWrap);
-        const DECR_WRAP: wgpu::StencilFaceState = stencil!(Always, Keep, DecrementWrap);
+impl Pipeline {
+    fn new(device: &wgpu::Device, layouts: &Layouts, target: TargetDescriptor) -> Self {
+        Self::build(
+            target,
+            |vs_entry_point, fs_entry_point, bindings, instances| {
+                let layout = match bindings {
+                    Bindings::Paint => &layouts.paint,
+                    Bindings::Image => &layouts.image,
+                    Bindings::Backdrop => &layouts.backdrop,
+                };
+                Builder::new(
+                    vs_entry_point,
+                    fs_entry_point,
+                    device,
+                    layout,
+                    &layouts.module,
+                    target,
+                    instances,
+                )
+            },
+        )
+    }
+}
 
-        let premultiplied = Builder::new(
+impl<P> Pipeline<P> {
+    /// Creates every pipeline with `builder`, called with shader entry points,
+    /// bind groups following the viewport and whether instances are used.
+    pub(crate) fn build<B: PipelineBuilder<Pipeline = P>>(
+        target: TargetDescriptor,
+        builder: impl Fn(&'static str, &'static str, Bindings, bool) -> B,
+    ) -> Self {
+        let premultiplied = builder(
             "vertex_blit",
             "fragment_premultiplied",
-            device,
-            &image_layout,
-            &module,
-            false,
+            Bindings::Image,
+            true,
         );
 
-        let unmultiplied = Builder::new(
+        let unmultiplied = builder(
             "vertex_blit",
             "fragment_unmultiplied",
-            device,
-            &image_layout,
-            &module,
-            false,
+            Bindings::Image,
+            true,
         );
 
-        let font = Builder::new(
-            "vertex_blit",
-            "fragment_font",
-            device,
-            &image_layout,
-            &module,
-            false,
-        );
+        let font = builder("vertex_main", "fragment_font", Bindings::Image, true);
 
-        let main = Builder::new(
-            "vertex_main",
-            "fragment_main",
-            device,
-            &paint_layout,
-            &module,
+        let layer_unpremultiply = builder(
+            "vertex_blit",
+            "fragment_layer_unpremultiply",
+            Bindings::Image,
             true,
         );
 
-        let convex_simple = Builder::new(
+        let layer = builder("vertex_blit", "fragment_layer", Bindings::Image, true);
+
+        let main = builder("vertex_main", "fragment_main", Bindings::Paint, true);
+
+        let pattern = builder("vertex_main", "fragment_pattern", Bindings::Image, true);
+
+        let convex_simple = builder(
             "vertex_main",
             "fragment_convex_simple",
-            device,
-            &paint_layout,
-            &module,
+            Bindings::Paint,
             true,
         );
 
-        let stencil = Builder::new(
-            "vertex_stencil",
-            "fragment_stencil",
-            device,
-            &paint_layout,
-            &module,
-            false,
-        );
-
-        Self {
-            view_layout,
+        let advanced = |fs_entry_point| {
+            builder("vertex_main", fs_entry_point, Bindings::Backdrop, true)
+                .blend(wgpu::BlendState::REPLACE)
+        };
 
-            premultiplied: premultiplied.pipeline(true, true, 0xFF, ALWAYS_KEEP, ALWAYS_KEEP),
-            unmultiplied: unmultiplied.pipeline(true, true, 0xFF, ALWAYS_KEEP, ALWAYS_KEEP),
-            font: font.pipeline(true, true, 0xFF, ALWAYS_KEEP, ALWAYS_KEEP),
+        let clear = main.blend(BlendMode::DstOut.blend_state());
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let filter = builder("vertex_main", "fragment_backdrop", Bindings::Image, true).blend(
+            wgpu::BlendState {
+                color: additive,
+                alpha: additive,
+            },
+        );
 
-            convex: main.pipeline(true, true, 0xFF, ALWAYS_KEEP, ALWAYS_KEEP),
-            convex_simple: convex_simple.pipeline(true, true, 0xFF, ALWAYS_KEEP, ALWAYS_KEEP),
+        let stencil = builder("vertex_stencil", "fragment_stencil", Bindings::Paint, false);
 
-            fill_stencil: stencil.pipeline(false, false, 0xFF, INCR_WRAP, DECR_WRAP),
-            fill_quad_non_zero: main.pipeline(true, true, 0xFF, NE_ZERO, NE_ZERO),
-            fill_quad_even_odd: main.pipeline(true, true, 0x01, NE_ZERO, NE_ZERO),
+        Self {
+            target,
+
+            // Every visible draw is rejected where the `CLIP` bit is set.
+            premultiplied: premultiplied.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+            unmultiplied: unmultiplied.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+            font: font.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+
+            fill_stencil: stencil.pipeline(false, false, [CLIP, FILL], INCR_WRAP, DECR_WRAP),
+            stroke_stencil: stencil.pipeline(false, true, [0xFF, FILL], ALWAYS_ZERO, ALWAYS_ZERO),
+
+            // Marks pixels outside of the winding stored in the `FILL` bits.
+            clip_non_zero: stencil.pipeline(false, false, [0xFF, CLIP], EQ_INVERT, EQ_INVERT),
+            clip_even_odd: stencil.pipeline(
+                false,
+                false,
+                [CLIP | 0x01, CLIP],
+                EQ_INVERT,
+                EQ_INVERT,
+            ),
+            clip_clear: stencil.pipeline(false, false, [0xFF, FILL], ALWAYS_ZERO, ALWAYS_ZERO),
+            clip_reset: stencil.pipeline(false, false, [0xFF, CLIP], ALWAYS_ZERO, ALWAYS_ZERO),
+
+            draws: BlendMode::FIXED.map(|mode| {
+                let blend = mode.blend_state();
+                DrawPipelines::new(
+                    main.blend(blend),
+                    convex_simple.blend(blend),
+                    pattern.blend(blend),
+                )
+            }),
+            draws_advanced: DrawPipelines::new(
+                advanced("fragment_main_blend"),
+                advanced("fragment_convex_simple_blend"),
+                advanced("fragment_pattern_blend"),
+            ),
+
+            layers: BlendMode::FIXED.map(|mode| {
+                let layer = layer.blend(mode.blend_state());
+                layer.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP)
+            }),
+            layer_advanced: builder(
+                "vertex_blit",
+                "fragment_layer_blend",
+                Bindings::Backdrop,
+                true,
+            )
+            .blend(wgpu::BlendState::REPLACE)
+            .pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+
+            layer_unpremultiply: layer_unpremultiply
+                .blend(wgpu::BlendState::REPLACE)
+                .pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+
+            // Blurs the whole layer regardless of clips inside of it.
+            blur: builder("vertex_blit", "fragment_blur", Bindings::Image, true)
+                .blend(wgpu::BlendState::REPLACE)
+                .pipeline(true, true, [0, 0], EQ_KEEP, EQ_KEEP),
+
+            filter_convex: [
+                clear.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+                filter.pipeline(true, true, [CLIP, 0], EQ_KEEP, EQ_KEEP),
+            ],
+            filter_fringes: [
+                clear.pipeline(true, true, [0xFF, 0], EQ_KEEP, EQ_KEEP),
+                filter.pipeline(true, true, [0xFF, 0], EQ_KEEP, EQ_KEEP),
+            ],
+            // The winding is kept for the second draw.
+            filter_quad: [
+                clear.pipeline(true, true, [FILL, 0], NE_ZERO, NE_ZERO),
+                filter.pipeline(true, true, [FILL, FILL], NE_ZERO, NE_ZERO),
+            ],
+        }
+    }
 
-            stroke_base: main.pipeline(true, true, 0xFF, INCR_CLAMP, INCR_CLAMP),
-            stroke_stencil: stencil.pipeline(false, true, 0xFF, ALWAYS_ZERO, ALWAYS_ZERO),
+    /// Fill and stroke pipelines for the blend mode.
+    pub fn draws(&self, blend_mode: BlendMode) -> &DrawPipelines<P> {
+        if blend_mode.is_advanced() {
+            &self.draws_advanced
+        } else {
+            &self.draws[blend_mode as usize]
+        }
+    }
 
-            fringes_non_zero: main.pipeline(true, true, 0xFF, EQ_KEEP, EQ_KEEP),
-            fringes_even_odd: main.pipeline(true, true, 0x01, EQ_KEEP, EQ_KEEP),
+    /// Layer composite pipeline for the blend mode.
+    pub fn layer(&self, blend_mode: BlendMode) -> &P {
+        if blend_mode.is_advanced() {
+            &self.layer_advanced
+        } else {
+            &self.layers[blend_mode as usize]
         }
     }
 }
 
+#[derive(Clone, Copy)]
 struct Builder<'a> {
     vs_entry_point: &'a str,
     fs_entry_point: &'a str,
     device: &'a wgpu::Device,
     layout: &'a wgpu::PipelineLayout,
     module: &'a wgpu::ShaderModule,
+    target: TargetDescriptor,
     instances: bool,
+    blend: wgpu::BlendState,
 }
 
 impl<'a> Builder<'a> {
@@ -214,7 +502,7 @@ impl<'a> Builder<'a> {
         device: &'a wgpu::Device,
         layout: &'a wgpu::PipelineLayout,
         module: &'a wgpu::ShaderModule,
-
+        target: TargetDescriptor,
         instances: bool,
     ) -> Self {
         Self {
@@ -223,26 +511,36 @@ impl<'a> Builder<'a> {
             device,
             layout,
             module,
+            target,
             instances,
+            blend: wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
         }
     }
+}
+
+impl PipelineBuilder for Builder<'_> {
+    type Pipeline = wgpu::RenderPipeline;
+
+    fn blend(self, blend: wgpu::BlendState) -> Self {
+        Self { blend, ..self }
+    }
 
     fn pipeline(
         &self,
         write_color: bool,
         back_culling: bool,
-        stencil_mask: u32,
+        [read_mask, write_mask]: [u32; 2],
         front: wgpu::StencilFaceState,
         back: wgpu::StencilFaceState,
     ) -> wgpu::RenderPipeline {
-        let target = wgpu::ColorTargetState {
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        let color = wgpu::ColorTargetState {
+            format: self.target.color,
             write_mask: if write_color {
                 wgpu::ColorWrites::all()
             } else {
                 wgpu::ColorWrites::empty()
             },
-            blend: write_color.then_some(wgpu::BlendState::ALPHA_BLENDING),
+            blend: write_color.then_some(self.blend),
         };
 
         let cull_mode = back_culling.then_some(wgpu::Face::Back);
@@ -253,7 +551,9 @@ impl<'a> Builder<'a> {
             module,
             vs_entry_point,
             fs_entry_point,
+            target,
             instances,
+            ..
         } = self;
 
         let vertex_buffer = wgpu::VertexBufferLayout {
@@ -271,6 +571,11 @@ impl<'a> Builder<'a> {
                 5 => Unorm8x4,
                 6 => Float32x4,
                 7 => Float32x2,
+                8 => Float32x4,
+                9 => Float32x2,
+                10 => Float32x4,
+                11 => Float32x3,
+                12 => Float32,
             ],
         };
 
@@ -287,7 +592,7 @@ impl<'a> Builder<'a> {
             fragment: Some(wgpu::FragmentState {
                 module,
                 entry_point: fs_entry_point,
-                targets: &[Some(target)],
+                targets: &[Some(color)],
             }),
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleList,
@@ -297,18 +602,21 @@ impl<'a> Builder<'a> {
                 ..wgpu::PrimitiveState::default()
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth24PlusStencil8,
+                format: target.depth_stencil,
                 depth_write_enabled: false,
                 depth_compare: wgpu::CompareFunction::Always,
                 stencil: wgpu::StencilState {
                     front,
                     back,
-                    read_mask: stencil_mask,
-                    write_mask: stencil_mask,
+                    read_mask,
+                    write_mask,
                 },
                 bias: wgpu::DepthBiasState::default(),
             }),
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: target.sample_count,
+                ..wgpu::MultisampleState::default()
+            },
             multiview: None,
         })
     }
@@ -1,14 +1,17 @@
 use crate::{
     internals::{DrawCall, GpuBatch},
-    Picture, Pipeline,
+    Picture, PipelineCache, TargetDescriptor,
 };
 use bevy::{
     prelude::*,
     render::{
+        camera::ExtractedCamera,
         render_graph::{RenderGraphApp, ViewNodeRunner},
         renderer::{RenderDevice, RenderQueue},
+        view::{Msaa, ViewTarget},
         Extract, ExtractSchedule, Render, RenderApp, RenderSet,
     },
+    utils::HashMap,
 };
 
 mod node;
@@ -19,6 +22,11 @@ pub use self::viewport::{UniformOffset, ViewDepthStencilTexture};
 
 pub type Recorder = crate::Recorder<Handle<Image>>;
 pub type Images = crate::Images<Handle<Image>>;
+pub type LayerTargets = crate::LayerTargets<Handle<Image>>;
+
+/// Layer targets of every view, kept between frames.
+#[derive(Resource, Default)]
+pub struct ViewLayerTargets(pub HashMap<Entity, LayerTargets>);
 
 pub const REUI_PASS_DRIVER: &str = "reui_pass_driver";
 
@@ -74,8 +82,9 @@ impl bevy::app::Plugin for ReuiPlugin {
 
         render_app
             .init_resource::<Images>()
+            .init_resource::<ViewLayerTargets>()
             .init_resource::<GpuBatch>()
-            .init_resource::<Pipeline>()
+            .init_resource::<PipelineCache>()
             .init_resource::<viewport::Uniforms>();
     }
 }
@@ -112,7 +121,7 @@ impl FromWorld for Images {
     }
 }
 
-impl FromWorld for Pipeline {
+impl FromWorld for PipelineCache {
     fn from_world(world: &mut World) -> Self {
         let device = world.resource::<RenderDevice>();
         let images = world.resource::<Images>();
@@ -130,6 +139,7 @@ impl FromWorld for GpuBatch {
 #[derive(Component)]
 struct ExtractedRecorder {
     calls: Vec<DrawCall<Handle<Image>>>,
+    layer_count: usize,
 }
 
 fn extract_recorder(
@@ -144,7 +154,8 @@ fn extract_recorder(
         batch.queue(&render_queue, device, &recorder.batch);
 
         let calls = recorder.calls.clone();
-        let component = ExtractedRecorder { calls };
+        let layer_count = recorder.layer_count();
+        let component = ExtractedRecorder { calls, layer_count };
         commands.get_or_spawn(entity).insert(component);
     }
 }
@@ -152,11 +163,19 @@ fn extract_recorder(
 fn queue_pictures(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
-    pipeline: Res<Pipeline>,
+    mut pipelines: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
     batch: Res<GpuBatch>,
     uniforms: Res<viewport::Uniforms>,
-    images: Res<Images>,
-    query: Query<(Entity, &ExtractedRecorder, &UniformOffset)>,
+    mut images: ResMut<Images>,
+    mut layers: ResMut<ViewLayerTargets>,
+    query: Query<(
+        Entity,
+        &ExtractedRecorder,
+        &UniformOffset,
+        &ExtractedCamera,
+        &ViewTarget,
+    )>,
 ) {
     let device = render_device.wgpu_device();
 
@@ -164,18 +183,41 @@ fn queue_pictures(
         let binding = 0;
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("reui::Viewport"),
-            layout: &pipeline.view_layout,
+            layout: &pipelines.view_layout,
             entries: &[wgpu::BindGroupEntry { resource, binding }],
         });
 
-        for (entity, cmd, offset) in query.iter() {
+        for (entity, cmd, offset, camera, view_target) in query.iter() {
+            let size = camera.physical_target_size.unwrap_or_default();
+            // Pictures are rendered into the sampled main texture of the view.
+            let target = TargetDescriptor {
+                color: view_target.main_texture_format(),
+                sample_count: msaa.samples(),
+                ..TargetDescriptor::default()
+            };
+            let pipeline = pipelines.get(device, target);
+            let targets = layers.0.entry(entity).or_default();
+
+            // Layer keys are unique for every view and layer index.
+            let view = u128::from(entity.to_bits()) << 32;
+            targets.prepare(
+                device,
+                &mut images,
+                target,
+                cmd.layer_count,
+                size.x,
+                size.y,
+                |index| Handle::weak_from_u128(view | index as u128),
+            );
+
             let picture = Picture::new(
                 device,
                 &bind_group,
                 offset.offset,
-                &pipeline,
+                pipeline,
                 &batch,
                 &images,
+                targets,
                 &cmd.calls,
             );
             commands.get_or_spawn(entity).insert(picture);
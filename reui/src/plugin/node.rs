@@ -1,4 +1,7 @@
-use crate::{picture::Picture, plugin::ViewDepthStencilTexture};
+use crate::{
+    picture::Picture,
+    plugin::{LayerTargets, ViewDepthStencilTexture, ViewLayerTargets},
+};
 use bevy::{
     ecs::query::QueryItem,
     prelude::*,
@@ -21,19 +24,34 @@ impl ViewNode for ReuiNode {
 
     fn run(
         &self,
-        _graph: &mut RenderGraphContext<'_>,
+        graph: &mut RenderGraphContext<'_>,
         render_context: &mut RenderContext,
         (picture, target, depth): QueryItem<Self::ViewQuery>,
-        _world: &World,
+        world: &World,
     ) -> Result<(), NodeRunError> {
         #[cfg(feature = "trace")]
         let _span = info_span!("reui_pass").entered();
 
+        let empty = LayerTargets::new();
+        let layers = world.resource::<ViewLayerTargets>();
+        let layers = layers.0.get(&graph.view_entity()).unwrap_or(&empty);
+
+        // Advanced blend modes outside of layers read a copy of the main texture.
+        let texture: &wgpu::Texture = target.main_texture();
+        let main: &wgpu::TextureView = target.main_texture_view();
+        let (color_view, resolve_view) = match target.sampled_main_texture_view() {
+            Some(sampled) => (&**sampled, Some(main)),
+            None => (main, None),
+        };
+
         crate::render_pictures(
             render_context.command_encoder(),
-            target.main_texture_view(),
+            color_view,
+            resolve_view,
+            Some(texture),
             &depth.view,
-            picture,
+            layers,
+            [picture],
             None,
             true,
         );
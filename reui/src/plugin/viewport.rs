@@ -5,7 +5,7 @@ use bevy::{
         render_resource::*,
         renderer::{RenderDevice, RenderQueue},
         texture::TextureCache,
-        view::ExtractedView,
+        view::{ExtractedView, Msaa},
     },
     utils::HashMap,
 };
@@ -56,6 +56,7 @@ pub fn prepare_textures(
     mut commands: Commands,
     mut texture_cache: ResMut<TextureCache>,
     render_device: Res<RenderDevice>,
+    msaa: Res<Msaa>,
     views: Query<(Entity, &ExtractedCamera)>,
 ) {
     let mut textures = HashMap::default();
@@ -77,9 +78,9 @@ pub fn prepare_textures(
                             height: physical_target_size.y,
                         },
                         mip_level_count: 1,
-                        sample_count: 1,
+                        sample_count: msaa.samples(),
                         dimension: TextureDimension::D2,
-                        format: TextureFormat::Depth24PlusStencil8,
+                        format: crate::TargetDescriptor::default().depth_stencil,
                         usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                         view_formats: &[],
                     },
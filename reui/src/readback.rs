@@ -0,0 +1,99 @@
+use std::sync::mpsc;
+
+/// Unmultiplied sRGB RGBA8 pixels read back from a texture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pixels {
+    pub width: u32,
+    pub height: u32,
+    /// Rows of `width * 4` bytes, without padding.
+    pub data: Vec<u8>,
+}
+
+impl Pixels {
+    /// Returns the color of the pixel, if it is inside.
+    pub fn get(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = (y * self.width + x) as usize * 4;
+        let pixel = &self.data[index..index + 4];
+        Some([pixel[0], pixel[1], pixel[2], pixel[3]])
+    }
+
+    /// Encodes the pixels as an sRGB PNG.
+    pub fn encode_png(&self, writer: impl std::io::Write) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)
+    }
+
+    /// Writes the pixels into a PNG file.
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.encode_png(std::io::BufWriter::new(file))?;
+        Ok(())
+    }
+}
+
+/// Copies an `Rgba8Unorm` or `Rgba8UnormSrgb` texture into CPU memory.
+///
+/// The copy is submitted on its own and waited for,
+/// so commands writing the texture have to be submitted before.
+/// The texture needs [`wgpu::TextureUsages::COPY_SRC`].
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Pixels, wgpu::BufferAsyncError> {
+    let (width, height) = (texture.width(), texture.height());
+
+    // Rows of the copy are aligned, padding is stripped after mapping.
+    let row = width as usize * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+    let padded_row = row.div_ceil(align) * align;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("reui::Readback"),
+        size: (padded_row * height as usize) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("reui::Readback"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row as u32),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let (sender, receiver) = mpsc::channel();
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, move |result| drop(sender.send(result)));
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+    let mut data = Vec::with_capacity(row * height as usize);
+    for padded in slice.get_mapped_range().chunks_exact(padded_row) {
+        data.extend_from_slice(&padded[..row]);
+    }
+    buffer.unmap();
+
+    Ok(Pixels {
+        width,
+        height,
+        data,
+    })
+}
@@ -1,4 +1,7 @@
-use crate::{internals::GpuBatch, Canvas, Images, Picture, Pipeline, Recorder};
+use crate::{
+    internals::GpuBatch, Canvas, FontStore, GlyphCache, Images, LayerTargets, Picture,
+    PipelineCache, Pixels, Recorder, TargetDescriptor,
+};
 use wgpu::util::DeviceExt as _;
 
 pub type Image = u32;
@@ -6,23 +9,45 @@ pub type Image = u32;
 pub struct Renderer {
     pub recorder: Recorder<Image>,
     pub batch: GpuBatch,
-    pub pipeline: Pipeline,
+    pub pipelines: PipelineCache,
+    /// Formats of the views passed to [`Renderer::flush`].
+    ///
+    /// With `sample_count` above 1 the renderer draws into its own multisampled target
+    /// and resolves it into the view, fringes can be disabled with [`Recorder::set_antialias`].
+    pub target: TargetDescriptor,
     pub images: Images<Image>,
+    pub fonts: FontStore,
+    pub glyphs: GlyphCache<Image>,
+    pub layers: LayerTargets<Image>,
 
     pub(crate) view_buffer: wgpu::Buffer,
     pub(crate) view_binding: wgpu::BindGroup,
     pub(crate) depth_stencil: wgpu::TextureView,
+    pub(crate) multisampled: Option<wgpu::TextureView>,
+    pub(crate) depth_target: TargetDescriptor,
     pub(crate) width: u32,
     pub(crate) height: u32,
 
     image_index: Image,
+    /// Layer targets of [`Renderer::render_image`], kept apart from the ones sized for the view.
+    image_layers: LayerTargets<Image>,
 }
 
 impl Renderer {
     pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        Self::with_target(device, width, height, TargetDescriptor::default())
+    }
+
+    /// Creates a renderer for views with the given formats.
+    pub fn with_target(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        target: TargetDescriptor,
+    ) -> Self {
         let images = Images::new(device);
         let batch = GpuBatch::new(device);
-        let pipeline = Pipeline::new(device, &images.layout);
+        let pipelines = PipelineCache::new(device, &images.layout);
         let recorder = Recorder::default();
 
         let contents = crate::combine_viewport(width, height);
@@ -34,28 +59,39 @@ impl Renderer {
 
         let view_binding = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("reui::Viewport"),
-            layout: &pipeline.view_layout,
+            layout: &pipelines.view_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
                 resource: view_buffer.as_entire_binding(),
             }],
         });
 
-        let depth_stencil = create_depth_texture(device, width, height);
+        let depth_stencil = create_depth_texture(device, target, width, height);
+        let multisampled = create_multisampled_texture(device, target, width, height);
+
+        // The first image keys are reserved for the glyph atlases.
+        let glyphs = GlyphCache::new(0, 1);
 
         Self {
             batch,
-            pipeline,
+            pipelines,
+            target,
             recorder,
             images,
+            fonts: FontStore::new(),
+            glyphs,
+            layers: LayerTargets::new(),
 
             view_buffer,
             view_binding,
             depth_stencil,
+            multisampled,
+            depth_target: target,
             width,
             height,
 
-            image_index: 0,
+            image_index: 2,
+            image_layers: LayerTargets::new(),
         }
     }
 
@@ -83,11 +119,13 @@ impl Renderer {
         queue: &wgpu::Queue,
         width: u32,
         height: u32,
-    ) -> Canvas<Image> {
+    ) -> Canvas<'_, Image> {
         self.recorder.clear();
 
-        if self.width != width || self.height != height {
-            self.depth_stencil = create_depth_texture(device, width, height);
+        if self.depth_target != self.target || self.width != width || self.height != height {
+            self.depth_stencil = create_depth_texture(device, self.target, width, height);
+            self.multisampled = create_multisampled_texture(device, self.target, width, height);
+            self.depth_target = self.target;
         }
 
         let viewport = crate::combine_viewport(width, height);
@@ -96,42 +134,322 @@ impl Renderer {
         self.width = width;
         self.height = height;
 
-        Canvas::new(&mut self.recorder, &self.images)
+        Canvas::with_text(
+            &mut self.recorder,
+            &self.images,
+            &self.fonts,
+            &mut self.glyphs,
+        )
     }
 
+    /// Renders the recorded canvas into the view, clearing it with `clear_color`.
+    ///
+    /// Advanced blend modes outside of layers can't read the contents of the view,
+    /// they are blended over transparent if it isn't cleared, see [`Renderer::flush_texture`].
     pub fn flush(
         &mut self,
         encoder: &mut wgpu::CommandEncoder,
         staging_belt: &mut wgpu::util::StagingBelt,
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         view: &wgpu::TextureView,
         clear_color: Option<wgpu::Color>,
     ) {
-        self.batch
-            .staging(encoder, staging_belt, device, &self.recorder.batch);
+        self.render(
+            encoder,
+            staging_belt,
+            device,
+            queue,
+            view,
+            None,
+            clear_color,
+        );
+    }
 
-        let bundle = Picture::new(
+    /// Renders the recorded canvas into the texture like [`Renderer::flush`].
+    ///
+    /// If it isn't cleared, advanced blend modes outside of layers read a copy of the texture,
+    /// so it needs [`wgpu::TextureUsages::COPY_SRC`].
+    pub fn flush_texture(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        clear_color: Option<wgpu::Color>,
+    ) {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.render(
+            encoder,
+            staging_belt,
             device,
-            &self.view_binding,
-            0,
-            &self.pipeline,
-            &self.batch,
-            &self.images,
-            &self.recorder.calls,
+            queue,
+            &view,
+            Some(texture),
+            clear_color,
         );
+    }
 
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view: &wgpu::TextureView,
+        texture: Option<&wgpu::Texture>,
+        clear_color: Option<wgpu::Color>,
+    ) {
+        self.prepare(
+            encoder,
+            staging_belt,
+            device,
+            queue,
+            self.target,
+            self.width,
+            self.height,
+        );
+        let picture = self.picture(device, &self.view_binding, self.target);
+        let (color_view, resolve_view) = match &self.multisampled {
+            Some(multisampled) => (multisampled, Some(view)),
+            None => (view, None),
+        };
         crate::render_pictures(
             encoder,
-            view,
+            color_view,
+            resolve_view,
+            texture,
             &self.depth_stencil,
-            &bundle,
+            &self.layers,
+            [&picture],
             clear_color,
             true,
         )
     }
+
+    /// Records the canvas drawn by `draw` and renders it into a new image, returning its key.
+    ///
+    /// The image has a transparent background and is drawn with [`Canvas::image`]
+    /// or an image pattern like any uploaded one, so static content can be cached.
+    /// The image has its own viewport, depth-stencil and layer targets, but shares the recorder,
+    /// so call this outside of [`Renderer::start`] and [`Renderer::flush`].
+    pub fn render_image(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        draw: impl FnOnce(&mut Canvas<'_, Image>),
+    ) -> Image {
+        let size = wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("reui::RenderImage"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.target.color,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let target = self.target;
+        let depth_stencil = create_depth_texture(device, target, size.width, size.height);
+        let multisampled = create_multisampled_texture(device, target, size.width, size.height);
+
+        // Queue writes land before the whole submission, so the target viewport can't be reused.
+        let contents = crate::combine_viewport(size.width, size.height);
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("reui::Viewport"),
+            contents: bytemuck::bytes_of(&contents),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let viewport = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("reui::Viewport"),
+            layout: &self.pipelines.view_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: view_buffer.as_entire_binding(),
+            }],
+        });
+
+        self.recorder.clear();
+        draw(&mut Canvas::with_text(
+            &mut self.recorder,
+            &self.images,
+            &self.fonts,
+            &mut self.glyphs,
+        ));
+        // Images are drawn with straight alpha.
+        self.recorder.unpremultiply();
+
+        std::mem::swap(&mut self.layers, &mut self.image_layers);
+        self.prepare(
+            encoder,
+            staging_belt,
+            device,
+            queue,
+            target,
+            size.width,
+            size.height,
+        );
+        let picture = self.picture(device, &viewport, target);
+        let (color_view, resolve_view) = match &multisampled {
+            Some(multisampled) => (multisampled, Some(&view)),
+            None => (&view, None),
+        };
+        crate::render_pictures(
+            encoder,
+            color_view,
+            resolve_view,
+            Some(&texture),
+            &depth_stencil,
+            &self.layers,
+            [&picture],
+            Some(wgpu::Color::TRANSPARENT),
+            true,
+        );
+        std::mem::swap(&mut self.layers, &mut self.image_layers);
+        self.recorder.clear();
+
+        let image_key = self.image_index;
+        drop(self.images.insert(device, image_key, &view, size, None));
+        self.image_index += 1;
+        image_key
+    }
+
+    /// Renders the canvas drawn by `draw` without a window and reads it back.
+    ///
+    /// The canvas is cleared with `clear_color` and drawn into an owned `Rgba8UnormSrgb`
+    /// texture with the depth-stencil format and sample count of [`Renderer::target`].
+    /// Colors are unmultiplied like images from [`Renderer::render_image`].
+    /// Commands are submitted and waited for, so this works on a software adapter as well.
+    pub fn render_pixels(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        clear_color: wgpu::Color,
+        draw: impl FnOnce(&mut Canvas<'_, Image>),
+    ) -> Result<Pixels, wgpu::BufferAsyncError> {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("reui::Headless"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let target = self.target;
+        self.target = TargetDescriptor {
+            color: wgpu::TextureFormat::Rgba8UnormSrgb,
+            ..target
+        };
+
+        draw(&mut self.start(device, queue, width.max(1), height.max(1)));
+        self.recorder.unpremultiply();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("reui::Headless"),
+        });
+        let mut staging_belt = wgpu::util::StagingBelt::new(0x20_0000);
+        self.flush_texture(
+            &mut encoder,
+            &mut staging_belt,
+            device,
+            queue,
+            &texture,
+            Some(clear_color),
+        );
+        staging_belt.finish();
+        queue.submit(Some(encoder.finish()));
+        staging_belt.recall();
+
+        self.target = target;
+        crate::read_texture(device, queue, &texture)
+    }
+
+    /// Uploads the recorded batch and makes sure pipelines and layer targets fit the recorded draws.
+    fn prepare(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: TargetDescriptor,
+        width: u32,
+        height: u32,
+    ) {
+        self.glyphs.upload(device, queue, &mut self.images);
+        self.pipelines.get(device, target);
+
+        self.batch
+            .staging(encoder, staging_belt, device, &self.recorder.batch);
+
+        // Layer targets take image keys as they are created.
+        // They keep their size if nothing is drawn into them, images may be smaller than the target.
+        if self.recorder.layer_count() == 0 {
+            return;
+        }
+        let image_index = &mut self.image_index;
+        self.layers.prepare(
+            device,
+            &mut self.images,
+            target,
+            self.recorder.layer_count(),
+            width,
+            height,
+            |_| {
+                let key = *image_index;
+                *image_index += 1;
+                key
+            },
+        );
+    }
+
+    fn picture(
+        &self,
+        device: &wgpu::Device,
+        viewport: &wgpu::BindGroup,
+        target: TargetDescriptor,
+    ) -> Picture {
+        let pipeline = self.pipelines.pipeline(target);
+        Picture::new(
+            device,
+            viewport,
+            0,
+            pipeline.expect("pipelines are created in prepare"),
+            &self.batch,
+            &self.images,
+            &self.layers,
+            &self.recorder.calls,
+        )
+    }
 }
 
-fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+fn create_depth_texture(
+    device: &wgpu::Device,
+    target: TargetDescriptor,
+    width: u32,
+    height: u32,
+) -> wgpu::TextureView {
     device
         .create_texture(&wgpu::TextureDescriptor {
             label: Some("reui::DepthStencil"),
@@ -141,11 +459,39 @@ fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu:
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count: target.sample_count,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24PlusStencil8,
+            format: target.depth_stencil,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         })
         .create_view(&wgpu::TextureViewDescriptor::default())
 }
+
+/// Color target drawn into instead of the view when the target is multisampled.
+fn create_multisampled_texture(
+    device: &wgpu::Device,
+    target: TargetDescriptor,
+    width: u32,
+    height: u32,
+) -> Option<wgpu::TextureView> {
+    if target.sample_count <= 1 {
+        return None;
+    }
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("reui::Multisampled"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: target.sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: target.color,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+}
@@ -12,6 +12,11 @@ struct VertexInput {
     @location(5) outer_color: vec4<f32>,
     @location(6) erf: vec4<f32>,
     @location(7) stroke: vec2<f32>,
+    @location(8) scissor_transform: vec4<f32>,
+    @location(9) scissor_translate: vec2<f32>,
+    @location(10) scissor: vec4<f32>,
+    @location(11) ramp: vec3<f32>,
+    @location(12) blend: f32,
 }
 
 struct VertexOutput {
@@ -23,6 +28,10 @@ struct VertexOutput {
     @location(3) outer_color: vec4<f32>,
     @location(4) erf: vec4<f32>,
     @location(5) stroke: vec2<f32>,
+    @location(6) scissor_position: vec2<f32>,
+    @location(7) scissor: vec4<f32>,
+    @location(8) ramp: vec3<f32>,
+    @location(9) blend: f32,
 }
 
 struct FragmentInput {
@@ -32,17 +41,34 @@ struct FragmentInput {
     @location(3) outer_color: vec4<f32>,
     @location(4) erf: vec4<f32>,
     @location(5) stroke: vec2<f32>,
+    @location(6) scissor_position: vec2<f32>,
+    @location(7) scissor: vec4<f32>,
+    @location(8) ramp: vec3<f32>,
+    @location(9) blend: f32,
 }
 
 @group(0) @binding(0) var<uniform> viewport: Viewport;
 @group(1) @binding(0) var s_color: sampler;
 @group(1) @binding(1) var t_color: texture_2d<f32>;
+@group(2) @binding(1) var t_backdrop: texture_2d<f32>;
 
 fn sdroundrect(pt: vec2<f32>, ext: vec2<f32>, rad: f32) -> f32 {
     let d = abs(pt) - ext + vec2<f32>(rad, rad);
     return min(max(d.x, d.y), 0.0) + length(max(d, vec2<f32>(0.0))) - rad;
 }
 
+fn scissor_position(pt: vec2<f32>, transform: vec4<f32>, translate: vec2<f32>) -> vec2<f32> {
+    let x = pt.x * transform.x + pt.y * transform.z;
+    let y = pt.x * transform.y + pt.y * transform.w;
+    return translate + vec2<f32>(x, y);
+}
+
+// Scissor coverage - `scissor.xy` is the half extent, `scissor.zw` is the pixel scale.
+fn scissor_mask(pt: vec2<f32>, scissor: vec4<f32>) -> f32 {
+    let sc = vec2<f32>(0.5) - (abs(pt) - scissor.xy) * scissor.zw;
+    return clamp(sc.x, 0.0, 1.0) * clamp(sc.y, 0.0, 1.0);
+}
+
 @vertex
 fn vertex_main(in: VertexInput) -> VertexOutput {
     let position = in.position * viewport.inv_size * 2.0;
@@ -58,21 +84,61 @@ fn vertex_main(in: VertexInput) -> VertexOutput {
     out.outer_color = in.outer_color;
     out.erf = in.erf;
     out.stroke = in.stroke;
+    out.scissor_position = scissor_position(in.position, in.scissor_transform, in.scissor_translate);
+    out.scissor = in.scissor;
+    out.ramp = in.ramp;
+    out.blend = in.blend;
 
     return out;
 }
 
-@fragment
-fn fragment_main(in: FragmentInput) -> @location(0) vec4<f32> {
+// Advanced blend of the premultiplied source with the backdrop under it.
+// 7 is multiply, 8 is overlay, 9 is darken, 10 is lighten.
+fn blend(src: vec4<f32>, position: vec4<f32>, mode: f32) -> vec4<f32> {
+    let dst = textureLoad(t_backdrop, vec2<i32>(position.xy), 0);
+    let s = src.rgb;
+    let d = dst.rgb;
+    let sa = src.a;
+    let da = dst.a;
+
+    // Blend functions scaled by both alphas, so they work on premultiplied colors.
+    let multiply = s * d;
+    let overlay = select(sa * da - 2.0 * (da - d) * (sa - s), 2.0 * s * d, 2.0 * d <= vec3<f32>(da));
+    let darken = min(s * da, d * sa);
+    let lighten = max(s * da, d * sa);
+    let mixed = select(select(select(multiply, overlay, mode == 8.0), darken, mode == 9.0), lighten, mode == 10.0);
+
+    let color = s * (1.0 - da) + d * (1.0 - sa) + mixed;
+    return vec4<f32>(color, sa + da * (1.0 - sa));
+}
+
+// Stroke - from [0..1] to clipped pyramid, where the slope is 1px.
+// Fragments under `stroke.y` are discarded by the entry points, since GLSL rejects
+// `discard` in functions shared with vertex shaders.
+fn stroke_coverage(in: FragmentInput) -> f32 {
     let uv = in.texcoord;
     let scale = in.stroke.x;
-    let limit = in.stroke.y;
+    return min(1.0, (1.0 - abs(uv.x * 2.0 - 1.0)) * scale) * uv.y;
+}
 
-    // Stroke - from [0..1] to clipped pyramid, where the slope is 1px.
-    let stroke_alpha = min(1.0, (1.0 - abs(uv.x * 2.0 - 1.0)) * scale) * uv.y;
-    if (stroke_alpha < limit) {
+@fragment
+fn fragment_main(in: FragmentInput) -> @location(0) vec4<f32> {
+    if (stroke_coverage(in) < in.stroke.y) {
         discard;
     }
+    return main_color(in);
+}
+
+@fragment
+fn fragment_main_blend(@builtin(position) position: vec4<f32>, in: FragmentInput) -> @location(0) vec4<f32> {
+    if (stroke_coverage(in) < in.stroke.y) {
+        discard;
+    }
+    return blend(main_color(in), position, in.blend);
+}
+
+fn main_color(in: FragmentInput) -> vec4<f32> {
+    let stroke_alpha = stroke_coverage(in);
 
     let pt = in.position;
     let extent = in.erf.xy;
@@ -84,16 +150,128 @@ fn fragment_main(in: FragmentInput) -> @location(0) vec4<f32> {
     let color = mix(in.inner_color, in.outer_color, clamp(d, 0.0, 1.0));
 
     // Combine alpha
-    return vec4<f32>(color.rgb, color.a * stroke_alpha);
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    let alpha = color.a * stroke_alpha * scissor;
+    return vec4<f32>(color.rgb * alpha, alpha);
+}
+
+// Filtered backdrop added where the preceding destination-out fill cleared the shape,
+// so together they mix the backdrop with the filtered one by coverage.
+@fragment
+fn fragment_backdrop(@builtin(position) position: vec4<f32>, in: FragmentInput) -> @location(0) vec4<f32> {
+    let color = textureLoad(t_color, vec2<i32>(position.xy), 0);
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    return color * in.inner_color.a * stroke_coverage(in) * scissor;
+}
+
+// Gradient position extended by the spread mode - 1 is pad, 2 is repeat, 3 is reflect.
+fn spread(t: f32, mode: f32) -> f32 {
+    let repeat = fract(t);
+    let reflect = 1.0 - abs(fract(t * 0.5) * 2.0 - 1.0);
+    return select(select(clamp(t, 0.0, 1.0), repeat, mode == 2.0), reflect, mode == 3.0);
+}
+
+// Clockwise angle divided by the span - `erf.x` is the span.
+fn sweep(pt: vec2<f32>, span: f32) -> f32 {
+    let tau = 6.283185307179586;
+    let angle = atan2(pt.y, pt.x);
+    return select(angle, angle + tau, angle < 0.0) / span;
+}
+
+// Two-point conical gradient - `erf.x` is the distance between centers,
+// `erf.y` and `erf.z` are the start and the end radius.
+// Returns the largest `t` with a non-negative radius and the validity of it.
+fn conical(pt: vec2<f32>, erf: vec4<f32>) -> vec2<f32> {
+    let d = erf.x;
+    let r0 = erf.y;
+    let dr = erf.z - erf.y;
+
+    // Solves `|pt - t * (d, 0)| = r0 + t * dr` for `t`.
+    let a = d * d - dr * dr;
+    let b = pt.x * d + r0 * dr;
+    let c = dot(pt, pt) - r0 * r0;
+
+    let disc = b * b - a * c;
+    let root = sqrt(max(disc, 0.0));
+    let linear = abs(a) < 1e-4;
+    let t0 = select((b + root) / a, c / (2.0 * b), linear);
+    let t1 = select((b - root) / a, t0, linear);
+
+    let hi = max(t0, t1);
+    let lo = min(t0, t1);
+    let hi_valid = r0 + hi * dr >= 0.0;
+    let lo_valid = r0 + lo * dr >= 0.0;
+    let t = select(lo, hi, hi_valid);
+    let valid = (disc >= 0.0 || linear) && (hi_valid || lo_valid);
+    return vec2<f32>(t, select(0.0, 1.0, valid));
+}
+
+// Image pattern or gradient ramp - `ramp.x` is the ramp row, `ramp.y` is the spread mode
+// or zero for image patterns, where `erf.xy` is the image size.
+// `ramp.z` is the gradient shape - 0 is box, 1 is sweep, 2 is conical.
+@fragment
+fn fragment_pattern(in: FragmentInput) -> @location(0) vec4<f32> {
+    // Sampled before `discard` to keep derivatives in uniform control flow.
+    let color = pattern_color(in);
+    if (stroke_coverage(in) < in.stroke.y) {
+        discard;
+    }
+    return color;
+}
+
+@fragment
+fn fragment_pattern_blend(@builtin(position) position: vec4<f32>, in: FragmentInput) -> @location(0) vec4<f32> {
+    let color = pattern_color(in);
+    if (stroke_coverage(in) < in.stroke.y) {
+        discard;
+    }
+    return blend(color, position, in.blend);
+}
+
+fn pattern_color(in: FragmentInput) -> vec4<f32> {
+    let pt = in.position;
+    let extent = in.erf.xy;
+    let radius = in.erf.z;
+    let feather = in.erf.w;
+
+    let box = sdroundrect(pt, extent, radius) * feather + 0.5;
+    let angle = sweep(pt, in.erf.x);
+    let cone = conical(pt, in.erf);
+    let position = select(select(box, angle, in.ramp.z == 1.0), cone.x, in.ramp.z == 2.0);
+    let valid = select(1.0, cone.y, in.ramp.z == 2.0);
+
+    // Ramp texels are sampled at their centers to keep the stops exact.
+    let t = spread(position, in.ramp.y);
+    let size = vec2<f32>(textureDimensions(t_color));
+    let ramp_uv = vec2<f32>(t * (size.x - 1.0) + 0.5, in.ramp.x + 0.5) / size;
+    let texcoord = select(pt / extent, ramp_uv, in.ramp.y > 0.0);
+
+    let color = textureSample(t_color, s_color, texcoord) * in.inner_color * valid;
+    let stroke_alpha = stroke_coverage(in);
+
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    let alpha = color.a * stroke_alpha * scissor;
+    return vec4<f32>(color.rgb * alpha, alpha);
 }
 
 @fragment
 fn fragment_convex_simple(in: FragmentInput) -> @location(0) vec4<f32> {
+    return convex_simple_color(in);
+}
+
+@fragment
+fn fragment_convex_simple_blend(@builtin(position) position: vec4<f32>, in: FragmentInput) -> @location(0) vec4<f32> {
+    return blend(convex_simple_color(in), position, in.blend);
+}
+
+fn convex_simple_color(in: FragmentInput) -> vec4<f32> {
     let uv = in.texcoord;
     let scale = in.stroke.x;
-    let alpha = min(1.0, (1.0 - abs(uv.x * 2.0 - 1.0)) * scale) * uv.y;
+    let coverage = min(1.0, (1.0 - abs(uv.x * 2.0 - 1.0)) * scale) * uv.y;
     let color = in.inner_color;
-    return vec4<f32>(color.rgb, color.a * alpha);
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    let alpha = color.a * coverage * scissor;
+    return vec4<f32>(color.rgb * alpha, alpha);
 }
 
 @vertex
@@ -108,30 +286,111 @@ fn fragment_stencil() {}
 struct BlitOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) texcoord: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) scissor_position: vec2<f32>,
+    @location(3) scissor: vec4<f32>,
+    @location(4) blend: f32,
+    @location(5) step: vec2<f32>,
+}
+
+struct BlitInput {
+    @location(0) texcoord: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) scissor_position: vec2<f32>,
+    @location(3) scissor: vec4<f32>,
+    @location(4) blend: f32,
+    @location(5) step: vec2<f32>,
 }
 
 @vertex
-fn vertex_blit(
-    @location(0) position: vec2<f32>,
-    @location(1) texcoord: vec2<f32>,
-) -> BlitOutput {
-    let frag_position = position * viewport.inv_size * 2.0;
-    return BlitOutput(vec4<f32>(frag_position.x - 1.0, 1.0 - frag_position.y, 0.0, 1.0), texcoord);
+fn vertex_blit(in: VertexInput) -> BlitOutput {
+    let frag_position = in.position * viewport.inv_size * 2.0;
+
+    var out: BlitOutput;
+    out.position = vec4<f32>(frag_position.x - 1.0, 1.0 - frag_position.y, 0.0, 1.0);
+    out.texcoord = in.texcoord;
+    out.color = in.inner_color;
+    out.scissor_position = scissor_position(in.position, in.scissor_transform, in.scissor_translate);
+    out.scissor = in.scissor;
+    out.blend = in.blend;
+    out.step = in.erf.xy;
+    return out;
+}
+
+@fragment
+fn fragment_premultiplied(in: BlitInput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_color, s_color, in.texcoord);
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    let alpha = color.a * scissor;
+    return vec4<f32>(color.rgb * alpha, alpha);
 }
 
 @fragment
-fn fragment_premultiplied(@location(0) texcoord: vec2<f32>) -> @location(0) vec4<f32> {
-    return textureSample(t_color, s_color, texcoord);
+fn fragment_unmultiplied(in: BlitInput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_color, s_color, in.texcoord);
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    let alpha = color.a * scissor;
+    return vec4<f32>(color.rgb * color.a * alpha, alpha);
 }
 
+// Layer composite - the layer has the size of the target and is premultiplied.
 @fragment
-fn fragment_unmultiplied(@location(0) texcoord: vec2<f32>) -> @location(0) vec4<f32> {
-    let color = textureSample(t_color, s_color, texcoord);
-    return vec4<f32>(color.rgb * color.a, color.a);
+fn fragment_layer(@builtin(position) position: vec4<f32>, in: BlitInput) -> @location(0) vec4<f32> {
+    return layer_color(position, in);
 }
 
 @fragment
-fn fragment_font(@location(0) texcoord: vec2<f32>) -> @location(0) vec4<f32> {
-    let alpha = textureSample(t_color, s_color, texcoord).r;
-    return vec4<f32>(0.0, 0.0, 0.0, alpha);
-}
\ No newline at end of file
+fn fragment_layer_blend(@builtin(position) position: vec4<f32>, in: BlitInput) -> @location(0) vec4<f32> {
+    return blend(layer_color(position, in), position, in.blend);
+}
+
+// One pass of the separable Gaussian blur, `step` is the standard deviation along the axis.
+@fragment
+fn fragment_blur(@builtin(position) position: vec4<f32>, in: BlitInput) -> @location(0) vec4<f32> {
+    let sigma = max(in.step.x, in.step.y);
+    let axis = vec2<i32>(sign(in.step));
+    let radius = i32(ceil(sigma * 3.0));
+    let last = vec2<i32>(textureDimensions(t_color)) - 1;
+    let center = vec2<i32>(position.xy);
+
+    var color = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var i = -radius; i <= radius; i++) {
+        let x = f32(i) / sigma;
+        let weight = exp(-0.5 * x * x);
+        let texel = clamp(center + axis * i, vec2<i32>(0), last);
+        color += textureLoad(t_color, texel, 0) * weight;
+        total += weight;
+    }
+    return color / total;
+}
+
+// Root layer written into a target that is drawn as a straight alpha image later.
+@fragment
+fn fragment_layer_unpremultiply(@builtin(position) position: vec4<f32>, in: BlitInput) -> @location(0) vec4<f32> {
+    let color = layer_color(position, in);
+    return vec4<f32>(color.rgb / max(color.a, 0.00001), color.a);
+}
+
+fn layer_color(position: vec4<f32>, in: BlitInput) -> vec4<f32> {
+    let color = textureLoad(t_color, vec2<i32>(position.xy), 0);
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    return color * in.color.a * scissor;
+}
+
+// Glyph coverage from the red channel of the atlas filled with the paint.
+@fragment
+fn fragment_font(in: FragmentInput) -> @location(0) vec4<f32> {
+    let pt = in.position;
+    let extent = in.erf.xy;
+    let radius = in.erf.z;
+    let feather = in.erf.w;
+
+    let d = sdroundrect(pt, extent, radius) * feather + 0.5;
+    let color = mix(in.inner_color, in.outer_color, clamp(d, 0.0, 1.0));
+
+    let coverage = textureSample(t_color, s_color, in.texcoord).r;
+    let scissor = scissor_mask(in.scissor_position, in.scissor);
+    let alpha = color.a * coverage * scissor;
+    return vec4<f32>(color.rgb * alpha, alpha);
+}
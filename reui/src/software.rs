@@ -0,0 +1,1203 @@
+use crate::{
+    image::ImageSize,
+    internals::{Batch, DrawCall, Instance},
+    pass::{PassEncoder, PassLoad},
+    picture::DrawIndexed,
+    pipeline::PipelineBuilder,
+    BlendMode, Canvas, FontStore, GlyphCache, Image, Pipeline, Pixels, Recorder, Rect,
+    TargetDescriptor,
+};
+use std::{collections::HashMap, hash::Hash};
+
+/// Renderer drawing canvases on the CPU, without a device.
+///
+/// Recorded draws are rasterized with [`rasterize`], which emulates the pipelines
+/// and shaders of [`crate::Renderer`], so the output is deterministic on any machine.
+pub struct SoftwareRenderer {
+    pub recorder: Recorder<Image>,
+    pub images: SoftwareImages<Image>,
+    pub fonts: FontStore,
+    pub glyphs: GlyphCache<Image>,
+
+    image_index: Image,
+}
+
+impl Default for SoftwareRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SoftwareRenderer {
+    pub fn new() -> Self {
+        Self {
+            recorder: Recorder::default(),
+            images: SoftwareImages::new(),
+            fonts: FontStore::new(),
+            // The first image keys are reserved for the glyph atlases.
+            glyphs: GlyphCache::new(0, 1),
+            image_index: 2,
+        }
+    }
+
+    /// Adds unmultiplied sRGB RGBA8 pixels as an image, returning its key.
+    pub fn upload_image(&mut self, width: u32, height: u32, data: &[u8]) -> Image {
+        let image_key = self.image_index;
+        self.images.upload(image_key, width, height, data);
+        self.image_index += 1;
+        image_key
+    }
+
+    /// Renders the canvas drawn by `draw` like [`crate::Renderer::render_pixels`].
+    ///
+    /// The canvas is cleared with `clear_color` and the colors are unmultiplied.
+    pub fn render_pixels(
+        &mut self,
+        width: u32,
+        height: u32,
+        clear_color: wgpu::Color,
+        draw: impl FnOnce(&mut Canvas<'_, Image>),
+    ) -> Pixels {
+        self.recorder.clear();
+        draw(&mut Canvas::with_text(
+            &mut self.recorder,
+            &self.images,
+            &self.fonts,
+            &mut self.glyphs,
+        ));
+        self.recorder.unpremultiply();
+        self.glyphs.upload_software(&mut self.images);
+
+        rasterize(
+            self.recorder.batch(),
+            self.recorder.calls(),
+            &self.images,
+            width.max(1),
+            height.max(1),
+            clear_color,
+        )
+    }
+
+    /// Renders the canvas drawn by `draw` into `pixels` with the same size.
+    ///
+    /// The pixels are cleared with `clear_color`, or drawn over if it is `None`,
+    /// like a target passed to [`crate::Renderer::flush_texture`]. Colors stay premultiplied.
+    pub fn render_into(
+        &mut self,
+        pixels: &mut Pixels,
+        clear_color: Option<wgpu::Color>,
+        draw: impl FnOnce(&mut Canvas<'_, Image>),
+    ) {
+        self.recorder.clear();
+        draw(&mut Canvas::with_text(
+            &mut self.recorder,
+            &self.images,
+            &self.fonts,
+            &mut self.glyphs,
+        ));
+        self.glyphs.upload_software(&mut self.images);
+
+        rasterize_into(
+            self.recorder.batch(),
+            self.recorder.calls(),
+            &self.images,
+            pixels,
+            clear_color,
+        );
+    }
+}
+
+/// Images sampled by [`rasterize`], the software counterpart of [`crate::Images`].
+///
+/// Texels are decoded once on insertion and sampled bilinearly with clamped edges,
+/// like the default sampler.
+pub struct SoftwareImages<Key> {
+    images: HashMap<Key, Texture>,
+}
+
+impl<Key: Eq + Hash> Default for SoftwareImages<Key> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key: Eq + Hash> ImageSize<Key> for SoftwareImages<Key> {
+    fn image_size(&self, image: &Key) -> Option<(u32, u32)> {
+        let texture = self.images.get(image)?;
+        Some((texture.width, texture.height))
+    }
+}
+
+impl<Key: Eq + Hash> SoftwareImages<Key> {
+    pub fn new() -> Self {
+        Self {
+            images: HashMap::new(),
+        }
+    }
+
+    /// Adds unmultiplied sRGB RGBA8 pixels, like [`crate::Images::upload`].
+    pub fn upload(&mut self, key: Key, width: u32, height: u32, data: &[u8]) {
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        self.insert(key, width, height, format, data);
+    }
+
+    /// Adds pixels in one of the formats used by images, ramps and glyph atlases.
+    ///
+    /// # Panics
+    ///
+    /// Panics for formats other than `Rgba8UnormSrgb`, `Rgba8Unorm` and `R8Unorm`.
+    pub fn insert(
+        &mut self,
+        key: Key,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        data: &[u8],
+    ) {
+        let texture = Texture::new(width, height, format, data);
+        let _ = self.images.insert(key, texture);
+    }
+
+    pub fn remove(&mut self, key: &Key) -> bool {
+        self.images.remove(key).is_some()
+    }
+}
+
+/// Rasterizes recorded draws into an `Rgba8UnormSrgb` target on the CPU.
+///
+/// Draws are split into the passes of [`crate::Picture::new`] and executed like
+/// [`crate::render_pictures`], with the stencil and blend states of [`Pipeline`]
+/// and the fragment shaders ported to Rust. Pixels are sampled at their centers,
+/// so edges are only antialiased by fringes. Returns the target like [`crate::read_texture`].
+pub fn rasterize<Key: Clone + Eq + Hash>(
+    batch: &Batch,
+    calls: &[DrawCall<Key>],
+    images: &SoftwareImages<Key>,
+    width: u32,
+    height: u32,
+    clear_color: wgpu::Color,
+) -> Pixels {
+    let mut pixels = Pixels {
+        width,
+        height,
+        data: vec![0; width as usize * height as usize * 4],
+    };
+    rasterize_into(batch, calls, images, &mut pixels, Some(clear_color));
+    pixels
+}
+
+/// Rasterizes recorded draws into existing pixels like [`rasterize`].
+///
+/// The pixels are cleared with `clear_color`, or loaded if it is `None`
+/// and drawn over like the texture passed to [`crate::render_pictures`].
+pub fn rasterize_into<Key: Clone + Eq + Hash>(
+    batch: &Batch,
+    calls: &[DrawCall<Key>],
+    images: &SoftwareImages<Key>,
+    pixels: &mut Pixels,
+    clear_color: Option<wgpu::Color>,
+) {
+    let (width, height) = (pixels.width, pixels.height);
+    let pipeline = Pipeline::build(TargetDescriptor::default(), |_, fragment, _, _| Program {
+        fragment,
+        blend: wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+    });
+    let passes = crate::pass::passes(calls, &pipeline, (width, height), SoftwareEncoder::new);
+
+    let ramps = Texture::new(
+        crate::batch::RAMP_WIDTH,
+        batch.ramps().len() as u32 / crate::batch::RAMP_WIDTH,
+        wgpu::TextureFormat::Rgba8Unorm,
+        bytemuck::cast_slice(batch.ramps()),
+    );
+
+    let mut main = Target::new(width, height);
+    for (color, pixel) in main.color.iter_mut().zip(pixels.data.chunks_exact(4)) {
+        color.copy_from_slice(pixel);
+    }
+    let mut context = Context {
+        batch,
+        images,
+        ramps: &ramps,
+        main,
+        layers: Vec::new(),
+    };
+
+    let mut main_clear = clear_color.map(|color| [color.r, color.g, color.b, color.a]);
+    // The root layer takes the clear color of the target, so it isn't blended twice.
+    let seeded = passes.iter().any(|pass| pass.seed.is_some());
+    let root_clear = match main_clear {
+        Some([r, g, b, a]) if seeded => {
+            main_clear = Some([0.0; 4]);
+            Some([r * a, g * a, b * a, a])
+        }
+        _ => None,
+    };
+
+    for pass in &passes {
+        let Some((index, bounds)) = pass.layer else {
+            if let (PassLoad::Clear, Some(color)) = (pass.load, main_clear) {
+                context.main.clear(color.map(|c| c as f32));
+            }
+            // Following passes are drawn over the first one.
+            main_clear = None;
+
+            let mut target = std::mem::take(&mut context.main);
+            let scissor = [0, 0, width, height];
+            context.execute(&mut target, scissor, &pass.draws);
+            context.main = target;
+            continue;
+        };
+
+        let Rect { min, max } = bounds;
+        let x0 = min.x.clamp(0.0, width as f32) as u32;
+        let y0 = min.y.clamp(0.0, height as f32) as u32;
+        let x1 = max.x.clamp(0.0, width as f32) as u32;
+        let y1 = max.y.clamp(0.0, height as f32) as u32;
+
+        if let PassLoad::Backdrop(backdrop) = pass.load {
+            let source = std::mem::take(context.layer(index));
+            context.layer(backdrop).copy(&source, [x0, y0, x1, y1]);
+            *context.layer(index) = source;
+        }
+
+        if let Some(seed) = pass.seed {
+            let mut target = std::mem::take(context.layer(seed));
+            match root_clear {
+                Some(color) => target.clear(color.map(|c| c as f32)),
+                None => target.copy(&context.main, [0, 0, width, height]),
+            }
+            *context.layer(seed) = target;
+        }
+
+        let mut target = std::mem::take(context.layer(index));
+        if let PassLoad::Clear = pass.load {
+            target.clear([0.0; 4]);
+        }
+        if x0 < x1 && y0 < y1 {
+            context.execute(&mut target, [x0, y0, x1, y1], &pass.draws);
+        }
+        *context.layer(index) = target;
+    }
+
+    pixels.data = context.main.color.into_iter().flatten().collect();
+}
+
+/// Builds [`SoftwarePipeline`]s from the shader entry points of [`Pipeline`].
+#[derive(Clone, Copy)]
+struct Program {
+    fragment: &'static str,
+    blend: wgpu::BlendState,
+}
+
+impl PipelineBuilder for Program {
+    type Pipeline = SoftwarePipeline;
+
+    fn blend(self, blend: wgpu::BlendState) -> Self {
+        Self { blend, ..self }
+    }
+
+    fn pipeline(
+        &self,
+        write_color: bool,
+        back_culling: bool,
+        [read_mask, write_mask]: [u32; 2],
+        front: wgpu::StencilFaceState,
+        back: wgpu::StencilFaceState,
+    ) -> SoftwarePipeline {
+        SoftwarePipeline {
+            fragment: Fragment::new(self.fragment),
+            blend: write_color.then_some(self.blend),
+            back_culling,
+            read_mask: read_mask as u8,
+            write_mask: write_mask as u8,
+            front,
+            back,
+        }
+    }
+}
+
+/// Fixed-function states of a render pipeline with its fragment shader.
+pub(crate) struct SoftwarePipeline {
+    fragment: Fragment,
+    /// `None` if colors are not written.
+    blend: Option<wgpu::BlendState>,
+    back_culling: bool,
+    read_mask: u8,
+    write_mask: u8,
+    front: wgpu::StencilFaceState,
+    back: wgpu::StencilFaceState,
+}
+
+/// Fragment shader entry points of `shader.wgsl`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Fragment {
+    Main,
+    MainBlend,
+    Backdrop,
+    Pattern,
+    PatternBlend,
+    ConvexSimple,
+    ConvexSimpleBlend,
+    Stencil,
+    Premultiplied,
+    Unmultiplied,
+    Layer,
+    LayerBlend,
+    Blur,
+    LayerUnpremultiply,
+    Font,
+}
+
+impl Fragment {
+    fn new(entry_point: &str) -> Self {
+        match entry_point {
+            "fragment_main" => Self::Main,
+            "fragment_main_blend" => Self::MainBlend,
+            "fragment_backdrop" => Self::Backdrop,
+            "fragment_pattern" => Self::Pattern,
+            "fragment_pattern_blend" => Self::PatternBlend,
+            "fragment_convex_simple" => Self::ConvexSimple,
+            "fragment_convex_simple_blend" => Self::ConvexSimpleBlend,
+            "fragment_stencil" => Self::Stencil,
+            "fragment_premultiplied" => Self::Premultiplied,
+            "fragment_unmultiplied" => Self::Unmultiplied,
+            "fragment_layer" => Self::Layer,
+            "fragment_layer_blend" => Self::LayerBlend,
+            "fragment_blur" => Self::Blur,
+            "fragment_layer_unpremultiply" => Self::LayerUnpremultiply,
+            "fragment_font" => Self::Font,
+            _ => unreachable!("no software fragment shader for `{entry_point}`"),
+        }
+    }
+}
+
+/// Texture bound as the image of a draw.
+enum Binding<'a, Key> {
+    Ramps,
+    Image(&'a Key),
+    Layer(usize),
+}
+
+impl<Key> Clone for Binding<'_, Key> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Key> Copy for Binding<'_, Key> {}
+
+/// Draw with the bindings of the pass it was recorded into.
+struct Draw<'a, Key> {
+    pipeline: &'a SoftwarePipeline,
+    call: DrawIndexed,
+    image: Binding<'a, Key>,
+    backdrop: Option<usize>,
+}
+
+/// Draws of a pass with the bindings they were recorded with.
+struct SoftwareEncoder<'a, Key> {
+    draws: Vec<Draw<'a, Key>>,
+    image: Binding<'a, Key>,
+    backdrop: Option<usize>,
+}
+
+impl<Key> SoftwareEncoder<'_, Key> {
+    fn new() -> Self {
+        Self {
+            draws: Vec::new(),
+            image: Binding::Ramps,
+            backdrop: None,
+        }
+    }
+}
+
+impl<'a, Key> PassEncoder for SoftwareEncoder<'a, Key> {
+    type Pipeline = &'a SoftwarePipeline;
+    type Image = &'a Key;
+    type Draws = Vec<Draw<'a, Key>>;
+
+    fn bind_image(&mut self, image: &'a Key) {
+        self.image = Binding::Image(image);
+    }
+
+    fn bind_ramps(&mut self) {
+        self.image = Binding::Ramps;
+    }
+
+    fn bind_layer(&mut self, index: usize) -> bool {
+        // Missing layer targets are read as transparent.
+        self.image = Binding::Layer(index);
+        true
+    }
+
+    fn bind_backdrop(&mut self, index: usize) {
+        self.backdrop = Some(index);
+    }
+
+    fn draw(&mut self, pipeline: &'a SoftwarePipeline, call: DrawIndexed) {
+        self.draws.push(Draw {
+            pipeline,
+            call,
+            image: self.image,
+            backdrop: self.backdrop,
+        });
+    }
+
+    fn finish(self) -> Vec<Draw<'a, Key>> {
+        self.draws
+    }
+}
+
+/// Color attachment in `Rgba8UnormSrgb` with its stencil.
+#[derive(Default)]
+struct Target {
+    width: u32,
+    height: u32,
+    color: Vec<[u8; 4]>,
+    stencil: Vec<u8>,
+}
+
+impl Target {
+    fn new(width: u32, height: u32) -> Self {
+        let len = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            color: vec![[0; 4]; len],
+            stencil: vec![0; len],
+        }
+    }
+
+    fn clear(&mut self, color: [f32; 4]) {
+        self.color.fill(encode_srgb(color));
+        self.stencil.fill(0);
+    }
+
+    /// Copies colors of the region, stencil values are kept.
+    fn copy(&mut self, source: &Self, [x0, y0, x1, y1]: [u32; 4]) {
+        if (self.width, self.height) != (source.width, source.height) {
+            *self = Self::new(source.width, source.height);
+        }
+        let width = self.width as usize;
+        for y in y0 as usize..y1 as usize {
+            let row = y * width + x0 as usize..y * width + x1 as usize;
+            self.color[row.clone()].copy_from_slice(&source.color[row]);
+        }
+    }
+
+    fn load(&self, x: i32, y: i32) -> [f32; 4] {
+        if self.color.is_empty() {
+            return [0.0; 4];
+        }
+        let x = x.clamp(0, self.width as i32 - 1) as usize;
+        let y = y.clamp(0, self.height as i32 - 1) as usize;
+        decode_srgb(self.color[y * self.width as usize + x])
+    }
+}
+
+/// Image texels in linear colors, alpha is not premultiplied.
+struct Texture {
+    width: u32,
+    height: u32,
+    texels: Vec<[f32; 4]>,
+}
+
+impl Texture {
+    fn new(width: u32, height: u32, format: wgpu::TextureFormat, data: &[u8]) -> Self {
+        let unorm = |value: u8| f32::from(value) / 255.0;
+        let mut texels: Vec<[f32; 4]> = match format {
+            wgpu::TextureFormat::Rgba8UnormSrgb => data
+                .chunks_exact(4)
+                .map(|texel| decode_srgb([texel[0], texel[1], texel[2], texel[3]]))
+                .collect(),
+            wgpu::TextureFormat::Rgba8Unorm => data
+                .chunks_exact(4)
+                .map(|texel| [texel[0], texel[1], texel[2], texel[3]].map(unorm))
+                .collect(),
+            wgpu::TextureFormat::R8Unorm => data
+                .iter()
+                .map(|&texel| [unorm(texel), 0.0, 0.0, 1.0])
+                .collect(),
+            _ => panic!("unsupported software texture format {format:?}"),
+        };
+        texels.resize(width as usize * height as usize, [0.0; 4]);
+
+        Self {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    fn load(&self, x: i32, y: i32) -> [f32; 4] {
+        if self.texels.is_empty() {
+            return [0.0; 4];
+        }
+        let x = x.clamp(0, self.width as i32 - 1) as usize;
+        let y = y.clamp(0, self.height as i32 - 1) as usize;
+        self.texels[y * self.width as usize + x]
+    }
+}
+
+/// Texture read by a fragment shader.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Texture(&'a Texture),
+    Target(&'a Target),
+    Missing,
+}
+
+impl Source<'_> {
+    fn size(self) -> [f32; 2] {
+        match self {
+            Self::Texture(texture) => [texture.width as f32, texture.height as f32],
+            Self::Target(target) => [target.width as f32, target.height as f32],
+            Self::Missing => [0.0, 0.0],
+        }
+    }
+
+    /// Texel at the coordinates clamped to the texture, like `textureLoad`.
+    fn load(self, x: i32, y: i32) -> [f32; 4] {
+        match self {
+            Self::Texture(texture) => texture.load(x, y),
+            Self::Target(target) => target.load(x, y),
+            Self::Missing => [0.0; 4],
+        }
+    }
+
+    /// Bilinear sample with clamped edges, like `textureSample` with the default sampler.
+    fn sample(self, [u, v]: [f32; 2]) -> [f32; 4] {
+        let [width, height] = self.size();
+        let x = u * width - 0.5;
+        let y = v * height - 0.5;
+        if !x.is_finite() || !y.is_finite() {
+            return [0.0; 4];
+        }
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let top = mix(self.load(x0, y0), self.load(x0 + 1, y0), fx);
+        let bottom = mix(self.load(x0, y0 + 1), self.load(x0 + 1, y0 + 1), fx);
+        mix(top, bottom, fy)
+    }
+}
+
+/// Resources shared by the passes of [`rasterize`].
+struct Context<'a, Key> {
+    batch: &'a Batch,
+    images: &'a SoftwareImages<Key>,
+    ramps: &'a Texture,
+    main: Target,
+    layers: Vec<Target>,
+}
+
+impl<'a, Key: Eq + Hash> Context<'a, Key> {
+    fn layer(&mut self, index: usize) -> &mut Target {
+        if index >= self.layers.len() {
+            self.layers.resize_with(index + 1, Target::default);
+        }
+        if self.layers[index].color.is_empty() {
+            self.layers[index] = Target::new(self.main.width, self.main.height);
+        }
+        &mut self.layers[index]
+    }
+
+    fn source(&self, binding: Binding<Key>) -> Source<'_> {
+        let texture = match binding {
+            Binding::Ramps => Some(self.ramps),
+            Binding::Image(key) => self.images.images.get(key),
+            Binding::Layer(index) => {
+                return self
+                    .layers
+                    .get(index)
+                    .map_or(Source::Missing, Source::Target)
+            }
+        };
+        texture.map_or(Source::Missing, Source::Texture)
+    }
+
+    /// Draws into the target, which is taken out of the context while it is drawn.
+    fn execute(&self, target: &mut Target, scissor: [u32; 4], draws: &[Draw<Key>]) {
+        let vertices = self.batch.vertices();
+        let indices = self.batch.indices();
+        let instances = self.batch.instances();
+
+        for draw in draws {
+            let DrawIndexed {
+                start,
+                end,
+                base_vertex,
+                instance,
+            } = draw.call;
+            let Some(instance) = instances.get(instance as usize) else {
+                continue;
+            };
+            let Some(indices) = indices.get(start as usize..end as usize) else {
+                continue;
+            };
+
+            let image = self.source(draw.image);
+            let backdrop = draw
+                .backdrop
+                .map_or(Source::Missing, |index| self.source(Binding::Layer(index)));
+
+            for triangle in indices.chunks_exact(3) {
+                let vertex = |index: u32| {
+                    let index = usize::try_from(i64::from(index) + i64::from(base_vertex));
+                    index.ok().and_then(|index| vertices.get(index))
+                };
+                if let [Some(a), Some(b), Some(c)] =
+                    [triangle[0], triangle[1], triangle[2]].map(vertex)
+                {
+                    let triangle =
+                        [a, b, c].map(|v| (v.pos, v.uv.map(|uv| f32::from(uv) / 65535.0)));
+                    let shader = Shader {
+                        instance,
+                        image,
+                        backdrop,
+                    };
+                    rasterize_triangle(target, scissor, draw.pipeline, &shader, triangle);
+                }
+            }
+        }
+    }
+}
+
+/// Subpixel precision of vertex positions, like the fixed-point snapping of GPUs.
+const SUBPIXEL_BITS: u32 = 8;
+
+/// Rasterizes a triangle at pixel centers with the top-left fill rule,
+/// so abutting triangles don't share pixels.
+fn rasterize_triangle(
+    target: &mut Target,
+    [sx0, sy0, sx1, sy1]: [u32; 4],
+    pipeline: &SoftwarePipeline,
+    shader: &Shader,
+    triangle: [([f32; 2], [f32; 2]); 3],
+) {
+    let one = (1 << SUBPIXEL_BITS) as f32;
+    // Far away vertices are clamped to keep edge functions in range.
+    let limit = (1 << 20) as f32;
+    let snap = |[x, y]: [f32; 2]| {
+        let fixed = |v: f32| (v.clamp(-limit, limit) * one).round() as i64;
+        [fixed(x), fixed(y)]
+    };
+
+    let [(p0, uv0), (p1, uv1), (p2, uv2)] = triangle;
+    let [mut a, mut b, c] = [snap(p0), snap(p1), snap(p2)];
+    let (mut uva, mut uvb, uvc) = (uv0, uv1, uv2);
+
+    let edge = |a: [i64; 2], b: [i64; 2], p: [i64; 2]| {
+        (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+    };
+
+    let mut area = edge(a, b, c);
+    if area == 0 {
+        return;
+    }
+
+    // Counter-clockwise in clip space is clockwise with y pointing down.
+    let front = area < 0;
+    if !front && pipeline.back_culling {
+        return;
+    }
+    if area < 0 {
+        std::mem::swap(&mut a, &mut b);
+        std::mem::swap(&mut uva, &mut uvb);
+        area = -area;
+    }
+    let face = if front {
+        &pipeline.front
+    } else {
+        &pipeline.back
+    };
+
+    // Pixels exactly on top and left edges are inside.
+    let bias = |a: [i64; 2], b: [i64; 2]| {
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        i64::from(!(dy < 0 || (dy == 0 && dx > 0)))
+    };
+    let (bias0, bias1, bias2) = (bias(b, c), bias(c, a), bias(a, b));
+
+    let pixel = |v: i64| v >> SUBPIXEL_BITS;
+    let min_x = pixel(a[0].min(b[0]).min(c[0])).max(i64::from(sx0));
+    let min_y = pixel(a[1].min(b[1]).min(c[1])).max(i64::from(sy0));
+    let max_x = (pixel(a[0].max(b[0]).max(c[0])) + 1).min(i64::from(sx1.min(target.width)));
+    let max_y = (pixel(a[1].max(b[1]).max(c[1])) + 1).min(i64::from(sy1.min(target.height)));
+
+    let half = 1 << (SUBPIXEL_BITS - 1);
+    let inv_area = (area as f64).recip();
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = [(x << SUBPIXEL_BITS) + half, (y << SUBPIXEL_BITS) + half];
+            let w0 = edge(b, c, p);
+            let w1 = edge(c, a, p);
+            let w2 = edge(a, b, p);
+            if w0 < bias0 || w1 < bias1 || w2 < bias2 {
+                continue;
+            }
+
+            let index = y as usize * target.width as usize + x as usize;
+            let stencil = target.stencil[index];
+            let passed = stencil_test(face.compare, pipeline.read_mask, stencil);
+            let op = if passed { face.pass_op } else { face.fail_op };
+            if !passed && op == wgpu::StencilOperation::Keep {
+                continue;
+            }
+
+            let [w0, w1, w2] = [w0, w1, w2].map(|w| (w as f64 * inv_area) as f32);
+            let texcoord = [
+                uva[0] * w0 + uvb[0] * w1 + uvc[0] * w2,
+                uva[1] * w0 + uvb[1] * w1 + uvc[1] * w2,
+            ];
+            let frag = [x as f32 + 0.5, y as f32 + 0.5];
+
+            // Discarded fragments don't update the stencil.
+            let Some(color) = shader.shade(pipeline.fragment, frag, texcoord) else {
+                continue;
+            };
+
+            let value = stencil_op(op, stencil);
+            let mask = pipeline.write_mask;
+            target.stencil[index] = (stencil & !mask) | (value & mask);
+
+            if let (true, Some(blend)) = (passed, pipeline.blend) {
+                let dst = decode_srgb(target.color[index]);
+                let src = color.map(|c| c.clamp(0.0, 1.0));
+                target.color[index] = encode_srgb(blend_color(&blend, src, dst));
+            }
+        }
+    }
+}
+
+/// Compares the masked stencil value with the reference, which is never set and stays zero.
+fn stencil_test(compare: wgpu::CompareFunction, read_mask: u8, value: u8) -> bool {
+    use wgpu::CompareFunction as F;
+    let reference = 0;
+    let value = value & read_mask;
+    match compare {
+        F::Never => false,
+        F::Less => reference < value,
+        F::Equal => reference == value,
+        F::LessEqual => reference <= value,
+        F::Greater => reference > value,
+        F::NotEqual => reference != value,
+        F::GreaterEqual => reference >= value,
+        F::Always => true,
+    }
+}
+
+fn stencil_op(op: wgpu::StencilOperation, value: u8) -> u8 {
+    use wgpu::StencilOperation as Op;
+    match op {
+        Op::Keep => value,
+        Op::Zero | Op::Replace => 0,
+        Op::Invert => !value,
+        Op::IncrementClamp => value.saturating_add(1),
+        Op::DecrementClamp => value.saturating_sub(1),
+        Op::IncrementWrap => value.wrapping_add(1),
+        Op::DecrementWrap => value.wrapping_sub(1),
+    }
+}
+
+/// Blends linear colors with a fixed-function blend state, the blend constant is zero.
+fn blend_color(state: &wgpu::BlendState, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+    let component = |blend: &wgpu::BlendComponent, channel: usize| {
+        let factor = |factor: wgpu::BlendFactor| {
+            use wgpu::BlendFactor as F;
+            match factor {
+                F::Zero | F::Constant => 0.0,
+                F::One | F::OneMinusConstant => 1.0,
+                F::Src => src[channel],
+                F::OneMinusSrc => 1.0 - src[channel],
+                F::SrcAlpha => src[3],
+                F::OneMinusSrcAlpha => 1.0 - src[3],
+                F::Dst => dst[channel],
+                F::OneMinusDst => 1.0 - dst[channel],
+                F::DstAlpha => dst[3],
+                F::OneMinusDstAlpha => 1.0 - dst[3],
+                F::SrcAlphaSaturated if channel == 3 => 1.0,
+                F::SrcAlphaSaturated => src[3].min(1.0 - dst[3]),
+            }
+        };
+
+        let s = src[channel] * factor(blend.src_factor);
+        let d = dst[channel] * factor(blend.dst_factor);
+        let value = match blend.operation {
+            wgpu::BlendOperation::Add => s + d,
+            wgpu::BlendOperation::Subtract => s - d,
+            wgpu::BlendOperation::ReverseSubtract => d - s,
+            wgpu::BlendOperation::Min => src[channel].min(dst[channel]),
+            wgpu::BlendOperation::Max => src[channel].max(dst[channel]),
+        };
+        value.clamp(0.0, 1.0)
+    };
+
+    [
+        component(&state.color, 0),
+        component(&state.color, 1),
+        component(&state.color, 2),
+        component(&state.alpha, 3),
+    ]
+}
+
+/// Fragment shaders of `shader.wgsl` for a single draw.
+struct Shader<'a> {
+    instance: &'a Instance,
+    /// Texture bound as `t_color`.
+    image: Source<'a>,
+    /// Texture bound as `t_backdrop`.
+    backdrop: Source<'a>,
+}
+
+impl Shader<'_> {
+    /// Runs the fragment shader at the pixel center `frag`, returns `None` if it discards.
+    ///
+    /// Varyings other than texture coordinates are affine in the position,
+    /// so they are evaluated at the pixel center instead of being interpolated.
+    fn shade(&self, fragment: Fragment, frag: [f32; 2], texcoord: [f32; 2]) -> Option<[f32; 4]> {
+        let instance = self.instance;
+        let input = Input {
+            position: instance.paint_mat.apply(frag),
+            texcoord,
+            scissor_position: instance.scissor_mat.apply(frag),
+            instance,
+        };
+        let discard = || input.stroke_coverage() < instance.stroke_thr;
+
+        let color = match fragment {
+            Fragment::Main => {
+                if discard() {
+                    return None;
+                }
+                input.main_color()
+            }
+            Fragment::MainBlend => {
+                if discard() {
+                    return None;
+                }
+                self.blend(input.main_color(), frag)
+            }
+            Fragment::Backdrop => {
+                let color = self.load(self.image, frag);
+                let alpha = input.inner_color()[3] * input.stroke_coverage() * input.scissor();
+                color.map(|c| c * alpha)
+            }
+            Fragment::Pattern => {
+                let color = self.pattern_color(&input);
+                if discard() {
+                    return None;
+                }
+                color
+            }
+            Fragment::PatternBlend => {
+                let color = self.pattern_color(&input);
+                if discard() {
+                    return None;
+                }
+                self.blend(color, frag)
+            }
+            Fragment::ConvexSimple => input.convex_simple_color(),
+            Fragment::ConvexSimpleBlend => self.blend(input.convex_simple_color(), frag),
+            Fragment::Stencil => [0.0; 4],
+            Fragment::Premultiplied => {
+                let color = self.image.sample(texcoord);
+                let alpha = color[3] * input.scissor();
+                premultiply(color, alpha)
+            }
+            Fragment::Unmultiplied => {
+                let color = self.image.sample(texcoord);
+                let alpha = color[3] * input.scissor();
+                let [r, g, b, _] = color.map(|c| c * color[3]);
+                premultiply([r, g, b, 1.0], alpha)
+            }
+            Fragment::Layer => self.layer_color(&input, frag),
+            Fragment::LayerBlend => self.blend(self.layer_color(&input, frag), frag),
+            Fragment::Blur => self.blur(frag),
+            Fragment::LayerUnpremultiply => {
+                let color = self.layer_color(&input, frag);
+                let alpha = color[3].max(0.00001);
+                [
+                    color[0] / alpha,
+                    color[1] / alpha,
+                    color[2] / alpha,
+                    color[3],
+                ]
+            }
+            Fragment::Font => {
+                let color = input.paint_color();
+                let coverage = self.image.sample(texcoord)[0];
+                premultiply(color, color[3] * coverage * input.scissor())
+            }
+        };
+        Some(color)
+    }
+
+    fn load(&self, source: Source, [x, y]: [f32; 2]) -> [f32; 4] {
+        source.load(x as i32, y as i32)
+    }
+
+    /// Advanced blend of the premultiplied source with the backdrop under it.
+    fn blend(&self, src: [f32; 4], frag: [f32; 2]) -> [f32; 4] {
+        let dst = self.load(self.backdrop, frag);
+        let (sa, da) = (src[3], dst[3]);
+        let mode = self.instance.blend;
+
+        let channel = |s: f32, d: f32| {
+            // Blend functions scaled by both alphas, so they work on premultiplied colors.
+            let mixed = if mode == f32::from(BlendMode::Lighten as u8) {
+                (s * da).max(d * sa)
+            } else if mode == f32::from(BlendMode::Darken as u8) {
+                (s * da).min(d * sa)
+            } else if mode == f32::from(BlendMode::Overlay as u8) {
+                if 2.0 * d <= da {
+                    2.0 * s * d
+                } else {
+                    sa * da - 2.0 * (da - d) * (sa - s)
+                }
+            } else {
+                s * d
+            };
+            s * (1.0 - da) + d * (1.0 - sa) + mixed
+        };
+
+        [
+            channel(src[0], dst[0]),
+            channel(src[1], dst[1]),
+            channel(src[2], dst[2]),
+            sa + da * (1.0 - sa),
+        ]
+    }
+
+    fn pattern_color(&self, input: &Input) -> [f32; 4] {
+        let instance = self.instance;
+        let pt = input.position;
+        let [shape, spread_mode] = [instance.ramp[2], instance.ramp[1]];
+
+        let position = if shape == 1.0 {
+            sweep(pt, instance.extent[0])
+        } else if shape == 2.0 {
+            conical(pt, instance).0
+        } else {
+            sdroundrect(pt, instance.extent, instance.radius) * instance.inv_feather + 0.5
+        };
+        let valid = if shape == 2.0 {
+            conical(pt, instance).1
+        } else {
+            1.0
+        };
+
+        // Ramp texels are sampled at their centers to keep the stops exact.
+        let texcoord = if spread_mode > 0.0 {
+            let t = spread(position, spread_mode);
+            let [width, height] = self.image.size();
+            [
+                (t * (width - 1.0) + 0.5) / width,
+                (instance.ramp[0] + 0.5) / height,
+            ]
+        } else {
+            [pt[0] / instance.extent[0], pt[1] / instance.extent[1]]
+        };
+
+        let inner = input.inner_color();
+        let sampled = self.image.sample(texcoord);
+        let color: [f32; 4] = std::array::from_fn(|i| sampled[i] * inner[i] * valid);
+        premultiply(color, color[3] * input.stroke_coverage() * input.scissor())
+    }
+
+    /// Layer composite - the layer has the size of the target and is premultiplied.
+    fn layer_color(&self, input: &Input, frag: [f32; 2]) -> [f32; 4] {
+        let color = self.load(self.image, frag);
+        let alpha = input.inner_color()[3] * input.scissor();
+        color.map(|c| c * alpha)
+    }
+
+    /// One pass of the separable Gaussian blur, the extent is the standard deviation along the axis.
+    fn blur(&self, [x, y]: [f32; 2]) -> [f32; 4] {
+        let [step_x, step_y] = self.instance.extent;
+        let sigma = step_x.max(step_y);
+        let axis = [sign(step_x), sign(step_y)];
+        let radius = (sigma * 3.0).ceil() as i32;
+        let [width, height] = self.image.size();
+        let last = [width as i32 - 1, height as i32 - 1];
+        let center = [x as i32, y as i32];
+
+        let mut color = [0.0; 4];
+        let mut total = 0.0;
+        for i in -radius..=radius {
+            let x = i as f32 / sigma;
+            let weight = (-0.5 * x * x).exp();
+            let texel_x = (center[0] + axis[0] * i).clamp(0, last[0].max(0));
+            let texel_y = (center[1] + axis[1] * i).clamp(0, last[1].max(0));
+            let texel = self.image.load(texel_x, texel_y);
+            for (color, texel) in color.iter_mut().zip(texel) {
+                *color += texel * weight;
+            }
+            total += weight;
+        }
+        color.map(|c| c / total)
+    }
+}
+
+/// Varyings of `FragmentInput` and `BlitInput`.
+struct Input<'a> {
+    position: [f32; 2],
+    texcoord: [f32; 2],
+    scissor_position: [f32; 2],
+    instance: &'a Instance,
+}
+
+impl Input<'_> {
+    fn inner_color(&self) -> [f32; 4] {
+        self.instance.inner_color.map(|c| f32::from(c) / 255.0)
+    }
+
+    fn outer_color(&self) -> [f32; 4] {
+        self.instance.outer_color.map(|c| f32::from(c) / 255.0)
+    }
+
+    /// Stroke - from [0..1] to clipped pyramid, where the slope is 1px.
+    fn stroke_coverage(&self) -> f32 {
+        let [u, v] = self.texcoord;
+        let scale = self.instance.stroke_mul;
+        f32::min(1.0, (1.0 - (u * 2.0 - 1.0).abs()) * scale) * v
+    }
+
+    /// Scissor coverage of the pixel.
+    fn scissor(&self) -> f32 {
+        let [x, y] = self.scissor_position;
+        let [ext_x, ext_y] = self.instance.scissor_ext;
+        let [scale_x, scale_y] = self.instance.scissor_scale;
+        let sc_x = 0.5 - (x.abs() - ext_x) * scale_x;
+        let sc_y = 0.5 - (y.abs() - ext_y) * scale_y;
+        sc_x.clamp(0.0, 1.0) * sc_y.clamp(0.0, 1.0)
+    }
+
+    /// Box gradient of the paint.
+    fn paint_color(&self) -> [f32; 4] {
+        let instance = self.instance;
+        let d = sdroundrect(self.position, instance.extent, instance.radius);
+        let t = (d * instance.inv_feather + 0.5).clamp(0.0, 1.0);
+        mix(self.inner_color(), self.outer_color(), t)
+    }
+
+    fn main_color(&self) -> [f32; 4] {
+        let color = self.paint_color();
+        premultiply(color, color[3] * self.stroke_coverage() * self.scissor())
+    }
+
+    fn convex_simple_color(&self) -> [f32; 4] {
+        let color = self.inner_color();
+        premultiply(color, color[3] * self.stroke_coverage() * self.scissor())
+    }
+}
+
+fn sdroundrect([x, y]: [f32; 2], [ext_x, ext_y]: [f32; 2], rad: f32) -> f32 {
+    let dx = x.abs() - ext_x + rad;
+    let dy = y.abs() - ext_y + rad;
+    f32::min(dx.max(dy), 0.0) + dx.max(0.0).hypot(dy.max(0.0)) - rad
+}
+
+/// Gradient position extended by the spread mode - 1 is pad, 2 is repeat, 3 is reflect.
+fn spread(t: f32, mode: f32) -> f32 {
+    let fract = |v: f32| v - v.floor();
+    if mode == 3.0 {
+        1.0 - (fract(t * 0.5) * 2.0 - 1.0).abs()
+    } else if mode == 2.0 {
+        fract(t)
+    } else {
+        t.clamp(0.0, 1.0)
+    }
+}
+
+/// Clockwise angle divided by the span.
+fn sweep([x, y]: [f32; 2], span: f32) -> f32 {
+    let angle = y.atan2(x);
+    let angle = if angle < 0.0 {
+        angle + std::f32::consts::TAU
+    } else {
+        angle
+    };
+    angle / span
+}
+
+/// Two-point conical gradient position and its validity.
+fn conical([x, y]: [f32; 2], instance: &Instance) -> (f32, f32) {
+    let d = instance.extent[0];
+    let r0 = instance.extent[1];
+    let dr = instance.radius - r0;
+
+    // Solves `|pt - t * (d, 0)| = r0 + t * dr` for `t`.
+    let a = d * d - dr * dr;
+    let b = x * d + r0 * dr;
+    let c = x * x + y * y - r0 * r0;
+
+    let disc = b * b - a * c;
+    let root = disc.max(0.0).sqrt();
+    let linear = a.abs() < 1e-4;
+    let t0 = if linear {
+        c / (2.0 * b)
+    } else {
+        (b + root) / a
+    };
+    let t1 = if linear { t0 } else { (b - root) / a };
+
+    let hi = t0.max(t1);
+    let lo = t0.min(t1);
+    let hi_valid = r0 + hi * dr >= 0.0;
+    let lo_valid = r0 + lo * dr >= 0.0;
+    let t = if hi_valid { hi } else { lo };
+    let valid = (disc >= 0.0 || linear) && (hi_valid || lo_valid);
+    (t, if valid { 1.0 } else { 0.0 })
+}
+
+fn sign(v: f32) -> i32 {
+    if v > 0.0 {
+        1
+    } else if v < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+fn premultiply([r, g, b, _]: [f32; 4], alpha: f32) -> [f32; 4] {
+    [r * alpha, g * alpha, b * alpha, alpha]
+}
+
+fn decode_srgb([r, g, b, a]: [u8; 4]) -> [f32; 4] {
+    let decode = |c: u8| {
+        let c = f32::from(c) / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [decode(r), decode(g), decode(b), f32::from(a) / 255.0]
+}
+
+fn encode_srgb([r, g, b, a]: [f32; 4]) -> [u8; 4] {
+    let encode = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        let c = if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(2.4f32.recip()) - 0.055
+        };
+        (c * 255.0 + 0.5) as u8
+    };
+    [
+        encode(r),
+        encode(g),
+        encode(b),
+        (a.clamp(0.0, 1.0) * 255.0 + 0.5) as u8,
+    ]
+}
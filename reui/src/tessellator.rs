@@ -1,6 +1,6 @@
 use crate::{
     internals::{Batch, Vertex},
-    Command, FillRule, LineCap, LineJoin, Offset, Rect, Solidity, Stroke,
+    Command, Dash, FillRule, LineCap, LineJoin, Offset, Rect, Solidity, Stroke,
 };
 use std::{cmp::Ordering, f32::consts::PI, f32::consts::TAU, ops::Range};
 
@@ -15,18 +15,13 @@ const DEFAULT_BOUNDS: Rect = Rect {
     max: Offset::new(-1e6, -1e6),
 };
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum Convexity {
+    #[default]
     Concave,
     Convex,
 }
 
-impl Default for Convexity {
-    fn default() -> Self {
-        Self::Concave
-    }
-}
-
 bitflags::bitflags!(
     #[derive(Default, Clone, Copy)]
     pub struct PointFlags: u32 {
@@ -186,56 +181,13 @@ impl Tessellator {
         self.bounds = DEFAULT_BOUNDS;
     }
 
-    pub fn contains(&self, p2: Offset, fill_rule: FillRule) -> bool {
-        // Early out if point is outside the bounding rectangle
-        if !self.bounds.contains(p2) {
-            return false;
-        }
-
-        fn extract_pos(pair: (&Point, &Point)) -> (Offset, Offset) {
-            (pair.0.pos, pair.1.pos)
-        }
-
-        fn is_left(p0: Offset, p1: Offset, p2: Offset) -> f32 {
-            (p1.x - p0.x) * (p2.y - p0.y) - (p1.y - p0.y) * (p2.x - p0.x)
-        }
-
-        match fill_rule {
-            FillRule::EvenOdd => self.contours.iter().any(|contour| {
-                let mut crossing = false;
-                for (p0, p1) in contour.point_pairs(&self.points).map(extract_pos) {
-                    if (p1.y > p2.y) != (p0.y > p2.y)
-                        && (p2.x < (p0.x - p1.x) * (p2.y - p1.y) / (p0.y - p1.y) + p1.x)
-                    {
-                        crossing = !crossing;
-                    }
-                }
-                crossing
-            }),
-            FillRule::NonZero => self.contours.iter().any(|contour| {
-                let mut winding_number: i32 = 0;
-                for (p0, p1) in contour.point_pairs(&self.points).map(extract_pos) {
-                    if p0.y <= p2.y {
-                        if p1.y > p2.y && is_left(p0, p1, p2) > 0.0 {
-                            winding_number = winding_number.wrapping_add(1);
-                        }
-                    } else if p1.y <= p2.y && is_left(p0, p1, p2) < 0.0 {
-                        winding_number = winding_number.wrapping_sub(1);
-                    }
-                }
-                winding_number != 0
-            }),
-        }
-    }
-
-    /// # Panics
-    pub fn flatten(
+    /// Converts path commands to contours of flattened points, as they are in the path.
+    pub(crate) fn flatten_commands(
         &mut self,
         commands: impl Iterator<Item = Command>,
         tess_tol: f32,
         dist_tol: f32,
     ) {
-        // clear all
         self.clear();
 
         // Convert path commands to a set of contours
@@ -263,6 +215,11 @@ impl Tessellator {
                         self.tesselate_bezier([p0, p1, p2, p3], tess_tol, dist_tol);
                     }
                 }
+                Command::ConicTo(p1, p2, weight) => {
+                    if let Some(last) = self.points.last() {
+                        self.tesselate_conic([last.pos, p1, p2], weight, tess_tol, dist_tol, 0);
+                    }
+                }
                 Command::CubicTo(p1, p2, p3) => {
                     if let Some(last) = self.points.last() {
                         self.tesselate_bezier([last.pos, p1, p2, p3], tess_tol, dist_tol);
@@ -285,6 +242,84 @@ impl Tessellator {
                 }
             }
         }
+    }
+
+    /// Flattened contours with whether they are closed.
+    ///
+    /// Windings are enforced by the [`Solidity`] of contours.
+    pub(crate) fn contours(
+        &self,
+    ) -> impl Iterator<Item = (impl Iterator<Item = Offset> + '_, bool)> + '_ {
+        self.contours.iter().map(|contour| {
+            let points = self.points[contour.range()].iter().map(|p| p.pos);
+            (points, contour.closed)
+        })
+    }
+
+    pub fn contains(&self, p2: Offset, fill_rule: FillRule) -> bool {
+        // Early out if point is outside the bounding rectangle
+        if !self.bounds.contains(p2) {
+            return false;
+        }
+
+        fn extract_pos(pair: (&Point, &Point)) -> (Offset, Offset) {
+            (pair.0.pos, pair.1.pos)
+        }
+
+        fn is_left(p0: Offset, p1: Offset, p2: Offset) -> f32 {
+            (p1.x - p0.x) * (p2.y - p0.y) - (p1.y - p0.y) * (p2.x - p0.x)
+        }
+
+        match fill_rule {
+            FillRule::EvenOdd => self.contours.iter().any(|contour| {
+                let mut crossing = false;
+                for (p0, p1) in contour.point_pairs(&self.points).map(extract_pos) {
+                    if (p1.y > p2.y) != (p0.y > p2.y)
+                        && (p2.x < (p0.x - p1.x) * (p2.y - p1.y) / (p0.y - p1.y) + p1.x)
+                    {
+                        crossing = !crossing;
+                    }
+                }
+                crossing
+            }),
+            FillRule::NonZero => self.contours.iter().any(|contour| {
+                let mut winding_number: i32 = 0;
+                for (p0, p1) in contour.point_pairs(&self.points).map(extract_pos) {
+                    if p0.y <= p2.y {
+                        if p1.y > p2.y && is_left(p0, p1, p2) > 0.0 {
+                            winding_number = winding_number.wrapping_add(1);
+                        }
+                    } else if p1.y <= p2.y && is_left(p0, p1, p2) < 0.0 {
+                        winding_number = winding_number.wrapping_sub(1);
+                    }
+                }
+                winding_number != 0
+            }),
+        }
+    }
+
+    pub fn flatten(
+        &mut self,
+        commands: impl Iterator<Item = Command>,
+        tess_tol: f32,
+        dist_tol: f32,
+    ) {
+        self.flatten_dashed(commands, &Dash::default(), tess_tol, dist_tol);
+    }
+
+    /// Flattens the path and splits contours into open dashes, one contour per dash.
+    pub fn flatten_dashed(
+        &mut self,
+        commands: impl Iterator<Item = Command>,
+        dash: &Dash,
+        tess_tol: f32,
+        dist_tol: f32,
+    ) {
+        self.flatten_commands(commands, tess_tol, dist_tol);
+
+        if !dash.is_solid() {
+            self.split_dashes(dash, dist_tol);
+        }
 
         let all_points = &mut self.points;
         let bounds = &mut self.bounds;
@@ -538,6 +573,189 @@ impl Tessellator {
         base_index..batch.base_index()
     }
 
+    /// Expands flattened contours to closed polygons covering the stroke with the non-zero rule.
+    ///
+    /// Open contours become a single polygon around both sides and caps,
+    /// closed contours become the polygon of the right side and the reversed polygon of the left one.
+    /// The polygons add up to the union of the segments, joins and caps,
+    /// so every covered point has a positive winding.
+    pub(crate) fn expand_stroke_outline(
+        &mut self,
+        mut stroke: Stroke,
+        tess_tol: f32,
+    ) -> Vec<Vec<Offset>> {
+        stroke.width *= 0.5;
+        let ncap = curve_divisions(stroke.width, PI, tess_tol);
+        self.calculate_joins(stroke.width, stroke.join, stroke.miter);
+
+        let mut polygons = Vec::new();
+        for contour in &self.contours {
+            let points = &self.points[contour.range()];
+            let join = |polygon: &mut Vec<Offset>, i: usize, side: f32| {
+                let p0 = &points[(i + points.len() - 1) % points.len()];
+                outline_join(polygon, p0, &points[i], stroke, side, ncap);
+            };
+
+            let mut left = Vec::new();
+            let mut right = Vec::new();
+
+            if contour.closed {
+                for i in 0..points.len() {
+                    join(&mut left, i, 1.0);
+                    join(&mut right, i, -1.0);
+                }
+                left.reverse();
+                polygons.extend([right, left]);
+            } else {
+                let (first, last) = (&points[0], &points[points.len() - 1]);
+                let end_dir = points[points.len() - 2].dir;
+
+                for i in 1..points.len() - 1 {
+                    join(&mut left, i, 1.0);
+                    join(&mut right, i, -1.0);
+                }
+                outline_cap(
+                    &mut right,
+                    stroke.end,
+                    last.pos,
+                    end_dir,
+                    stroke.width,
+                    ncap,
+                );
+                right.extend(left.into_iter().rev());
+                outline_cap(
+                    &mut right,
+                    stroke.start,
+                    first.pos,
+                    -first.dir,
+                    stroke.width,
+                    ncap,
+                );
+                polygons.push(right);
+            }
+        }
+
+        polygons
+    }
+
+    fn split_dashes(&mut self, dash: &Dash, dist_tol: f32) {
+        // Too many dashes to be visible, keep the stroke solid instead.
+        const MAX_DASHES: f32 = 100_000.0;
+        let length: f32 = (self.contours.iter())
+            .flat_map(|contour| self.points[contour.range()].windows(2))
+            .map(|pair| (pair[1].pos - pair[0].pos).magnitude())
+            .sum();
+        if length / dash.length() > MAX_DASHES {
+            return;
+        }
+
+        let intervals = dash.intervals();
+        let points = std::mem::take(&mut self.points);
+        let contours = std::mem::take(&mut self.contours);
+
+        for contour in &contours {
+            let points = &points[contour.range()];
+            let closed = match (points.first(), points.last()) {
+                (Some(p0), Some(p1)) => contour.closed || approx_pt_eq(p0.pos, p1.pos, dist_tol),
+                _ => continue,
+            };
+
+            // Every contour restarts the pattern at its phase.
+            let mut index = 0;
+            let mut remaining = intervals[0];
+            let mut phase = dash.phase.rem_euclid(dash.length());
+            while phase >= remaining {
+                phase -= remaining;
+                index = (index + 1) % intervals.len();
+                remaining = intervals[index];
+            }
+            remaining -= phase;
+
+            let mut on = index % 2 == 0;
+            let starts_on = on;
+            let first = self.contours.len();
+            if on {
+                self.start_dash(points[0].pos);
+            }
+
+            let count = if closed {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+            for i in 0..count {
+                let (a, b) = (&points[i], &points[(i + 1) % points.len()]);
+                let delta = b.pos - a.pos;
+                let len = delta.magnitude();
+
+                let mut t = 0.0;
+                while len - t > remaining {
+                    t += remaining;
+                    let pos = a.pos + delta * (t / len);
+                    if on {
+                        self.end_dash(pos, delta / len, dist_tol);
+                    } else {
+                        self.start_dash(pos);
+                    }
+                    on = !on;
+                    index = (index + 1) % intervals.len();
+                    remaining = intervals[index];
+                }
+                remaining -= len - t;
+
+                if on {
+                    self.push_dash_point(b.pos, b.flags, dist_tol);
+                }
+            }
+
+            // Join the dash crossing the start of a closed contour with the first one.
+            if closed && on && starts_on && self.contours.len() - 1 != first {
+                let range = self.contours[first].range();
+                for i in range.start + 1..range.end {
+                    let point = self.points[i].clone();
+                    self.push_dash_point(point.pos, point.flags, dist_tol);
+                }
+                self.contours[first].end = self.contours[first].start;
+            }
+        }
+    }
+
+    fn start_dash(&mut self, pos: Offset) {
+        let start = self.points.len() as u32;
+        self.points.push(Point::new(pos, PointFlags::CORNER));
+        self.contours.push(Contour {
+            start,
+            end: start + 1,
+            ..Contour::default()
+        });
+    }
+
+    fn end_dash(&mut self, pos: Offset, dir: Offset, dist_tol: f32) {
+        self.push_dash_point(pos, PointFlags::CORNER, dist_tol);
+
+        // Keep zero length dashes as short segments so they still get caps.
+        if let Some(contour) = self.contours.last_mut() {
+            if contour.len() == 1 {
+                let pos = pos + dir * (dist_tol * 2.0);
+                self.points.push(Point::new(pos, PointFlags::CORNER));
+                contour.end += 1;
+            }
+        }
+    }
+
+    fn push_dash_point(&mut self, pos: Offset, flags: PointFlags, dist_tol: f32) {
+        if let Some(contour) = self.contours.last_mut() {
+            if let Some(last) = self.points.last_mut() {
+                if contour.len() > 0 && approx_pt_eq(last.pos, pos, dist_tol) {
+                    last.flags |= flags;
+                    return;
+                }
+            }
+            self.points.push(Point::new(pos, flags));
+            contour.end += 1;
+        }
+    }
+
     fn add_point(&mut self, point: Offset, dist_tol: f32, flags: PointFlags) {
         if let Some(contour) = self.contours.last_mut() {
             // If last point equals this new point just OR the flags and ignore the new point
@@ -743,6 +961,39 @@ impl Tessellator {
             debug_assert!(times <= AFD_ONE);
         }
     }
+
+    /// Flattens a conic by recursive halving until every piece is flat.
+    fn tesselate_conic(
+        &mut self,
+        [p0, p1, p2]: [Offset; 3],
+        weight: f32,
+        tess_tol: f32,
+        dist_tol: f32,
+        level: u32,
+    ) {
+        // The farthest point from the chord is at t = 0.5, at `w / (1 + w)` of the control point distance.
+        // Pieces are flat with the same tolerance as the forward differencing of cubics.
+        let chord = p2 - p0;
+        let height = chord.cross(p1 - p0) * weight / (1.0 + weight);
+        let flat = height * height * 16.0 <= tess_tol * chord.magnitude_sq();
+        let tiny = (p1 - p0).magnitude_sq() + (p2 - p1).magnitude_sq() <= dist_tol * dist_tol;
+
+        if level >= 10 || tiny || flat && chord.magnitude_sq() > 0.0 {
+            self.add_point(p2, dist_tol, PointFlags::CORNER);
+            return;
+        }
+
+        // Split at t = 0.5, both halves share the new weight.
+        let scale = (1.0 + weight).recip();
+        let wp1 = p1 * weight;
+        let mid = (p0 + wp1 * 2.0 + p2) * (scale * 0.5);
+        let half = (0.5 + weight * 0.5).sqrt();
+
+        let a = [p0, (p0 + wp1) * scale, mid];
+        let b = [mid, (wp1 + p2) * scale, p2];
+        self.tesselate_conic(a, half, tess_tol, dist_tol, level + 1);
+        self.tesselate_conic(b, half, tess_tol, dist_tol, level + 1);
+    }
 }
 
 #[inline]
@@ -751,6 +1002,78 @@ fn curve_divisions(r: f32, arc: f32, tol: f32) -> usize {
     ((arc / da).ceil() as usize).max(2)
 }
 
+/// Appends the offset of the joint at `p1` on the left side for positive `side`, right for negative.
+fn outline_join(
+    polygon: &mut Vec<Offset>,
+    p0: &Point,
+    p1: &Point,
+    stroke: Stroke,
+    side: f32,
+    ncap: usize,
+) {
+    let w = stroke.width * side;
+    let dl0 = Offset::new(p0.dir.y, -p0.dir.x) * w;
+    let dl1 = Offset::new(p1.dir.y, -p1.dir.x) * w;
+
+    // Left turns have the inner side on the left.
+    if p1.flags.contains(PointFlags::LEFT) == (side > 0.0) {
+        // The miter cuts off the overlap of both segments, which is only safe while it stays
+        // within the halves of them, `width * tan(turn / 2) < len / 2`.
+        // Otherwise the outline goes through the pivot, like the segments ending there.
+        let sin = p0.dir.cross(p1.dir).abs();
+        let cos = p0.dir.x * p1.dir.x + p0.dir.y * p1.dir.y;
+        if 2.0 * stroke.width * sin < p0.len.min(p1.len) * (1.0 + cos) {
+            polygon.push(p1.pos + p1.ext * w);
+        } else {
+            polygon.extend([p1.pos + dl0, p1.pos, p1.pos + dl1]);
+        }
+    } else if !p1.flags.contains(PointFlags::BEVEL) {
+        polygon.push(p1.pos + p1.ext * w);
+    } else if matches!(stroke.join, LineJoin::Round) {
+        // Outer side turns counter-clockwise on the left and clockwise on the right.
+        let a0 = f32::atan2(dl0.y, dl0.x);
+        let mut a1 = f32::atan2(dl1.y, dl1.x);
+        if side > 0.0 && a1 < a0 {
+            a1 += TAU;
+        } else if side < 0.0 && a1 > a0 {
+            a1 -= TAU;
+        }
+
+        let n = (((a1 - a0).abs() / PI * ncap as f32).ceil() as usize).clamp(2, ncap);
+        for i in 0..n {
+            let a = a0 + (a1 - a0) * (i as f32 / (n - 1) as f32);
+            let (sn, cs) = a.sin_cos();
+            polygon.push(p1.pos + Offset::new(cs, sn) * stroke.width);
+        }
+    } else {
+        polygon.extend([p1.pos + dl0, p1.pos + dl1]);
+    }
+}
+
+/// Appends the cap at `pos` going from the right side to the left one around `dir`.
+fn outline_cap(
+    polygon: &mut Vec<Offset>,
+    cap: LineCap,
+    pos: Offset,
+    dir: Offset,
+    w: f32,
+    ncap: usize,
+) {
+    let dl = Offset::new(dir.y, -dir.x) * w;
+    let dir = dir * w;
+    match cap {
+        LineCap::Butt => polygon.extend([pos - dl, pos + dl]),
+        LineCap::Square => polygon.extend([pos - dl + dir, pos + dl + dir]),
+        LineCap::Round => {
+            for i in 0..ncap {
+                let angle = i as f32 / (ncap as f32 - 1.0) * PI;
+                let (sn, cs) = angle.sin_cos();
+                polygon.push(pos - dl * cs + dir * sn);
+            }
+        }
+    }
+}
+
 #[inline]
 fn choose_bevel(p0: &Point, p1: &Point, w: f32) -> [Offset; 2] {
     if p1.flags.contains(PointFlags::INNERBEVEL) {
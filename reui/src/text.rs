@@ -0,0 +1,286 @@
+mod atlas;
+mod caret;
+mod font;
+mod paragraph;
+
+pub use self::{
+    atlas::{GlyphCache, GlyphEntry, GlyphKey},
+    font::{Font, FontId, FontMetrics, FontStore},
+    paragraph::{
+        Cluster, LineMetrics, Paragraph, ParagraphStyle, PositionedGlyph, TextAlign, TextDirection,
+    },
+};
+
+use crate::{
+    internals::{Batch, DrawCall, Instance, Vertex},
+    picture::DrawIndexed,
+    GradientShape, IntoPaint, Offset, Paint, Recorder, Rect, Transform,
+};
+
+/// Size of a single line of text.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextMetrics {
+    pub advance: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    /// Ink bounds relative to the start of the baseline.
+    pub bounds: Rect,
+}
+
+/// Measures a single line of text as drawn by [`Recorder::fill_text`].
+pub fn measure_text(fonts: &FontStore, text: &str, font: FontId, size: f32) -> TextMetrics {
+    let paragraph = single_line(fonts, text, font, size);
+    let Some(line) = paragraph.lines.first() else {
+        return TextMetrics::default();
+    };
+
+    let baseline = Offset::new(0.0, line.baseline);
+    TextMetrics {
+        advance: paragraph.width,
+        ascent: line.ascent,
+        descent: line.descent,
+        bounds: paragraph.ink_bounds(fonts).translate(-baseline),
+    }
+}
+
+fn single_line(fonts: &FontStore, text: &str, font: FontId, size: f32) -> Paragraph {
+    let style = ParagraphStyle::new(font, size).align(TextAlign::Left);
+    Paragraph::new(fonts, text, &style)
+}
+
+impl Paragraph {
+    /// Returns the union of glyph bounding boxes relative to the paragraph origin.
+    pub fn ink_bounds(&self, fonts: &FontStore) -> Rect {
+        let mut bounds: Option<Rect> = None;
+        for glyph in &self.glyphs {
+            let Some(font) = fonts.get(glyph.font) else {
+                continue;
+            };
+            let Some(bbox) = font
+                .face()
+                .glyph_bounding_box(ttf_parser::GlyphId(glyph.glyph))
+            else {
+                continue;
+            };
+
+            let scale = font.scale(self.size);
+            let rect = Rect::from_ltrb(
+                f32::from(bbox.x_min) * scale,
+                -f32::from(bbox.y_max) * scale,
+                f32::from(bbox.x_max) * scale,
+                -f32::from(bbox.y_min) * scale,
+            )
+            .translate(glyph.position);
+
+            bounds = Some(bounds.map_or(rect, |bounds| Rect::union(bounds, rect)));
+        }
+        bounds.unwrap_or_default()
+    }
+}
+
+impl<Key: Clone + Eq + std::hash::Hash> Recorder<Key> {
+    /// Draws a single line of text with the baseline starting at `position`.
+    ///
+    /// Returns the advance of the text.
+    pub fn fill_text(
+        &mut self,
+        fonts: &FontStore,
+        glyphs: &mut GlyphCache<Key>,
+        text: &str,
+        position: Offset,
+        font: FontId,
+        size: f32,
+        paint: impl IntoPaint<Key>,
+        transform: Transform,
+    ) -> f32 {
+        let paragraph = single_line(fonts, text, font, size);
+        let baseline = paragraph.lines.first().map_or(0.0, |line| line.baseline);
+        let origin = position - Offset::new(0.0, baseline);
+        self.fill_paragraph(fonts, glyphs, &paragraph, origin, paint, transform);
+        paragraph.width
+    }
+
+    /// Draws a paragraph with the top left corner at `position`.
+    pub fn fill_paragraph(
+        &mut self,
+        fonts: &FontStore,
+        glyphs: &mut GlyphCache<Key>,
+        paragraph: &Paragraph,
+        position: Offset,
+        paint: impl IntoPaint<Key>,
+        transform: Transform,
+    ) {
+        let iter = paragraph.glyphs.iter().map(|glyph| {
+            let position = position + glyph.position;
+            (glyph.font, glyph.glyph, position)
+        });
+        self.fill_glyphs(fonts, glyphs, iter, paragraph.size, paint, transform);
+    }
+
+    /// Draws glyphs with origins at the given baseline positions.
+    ///
+    /// Glyphs are sampled from the atlas, so image patterns only contribute their alpha
+    /// and gradients use their `inner_color` and `outer_color` without ramps.
+    pub fn fill_glyphs(
+        &mut self,
+        fonts: &FontStore,
+        glyphs: &mut GlyphCache<Key>,
+        iter: impl IntoIterator<Item = (FontId, u16, Offset)>,
+        size: f32,
+        paint: impl IntoPaint<Key>,
+        transform: Transform,
+    ) {
+        let mut paint = paint.into_paint(transform);
+        if paint.shape != GradientShape::Box {
+            paint.outer_color = paint.inner_color;
+            paint.shape = GradientShape::Box;
+        }
+        let raw = paint.to_instance(1.0, 1.0, -1.0, self.scissor);
+        let instance = self.batch.instance(raw);
+
+        // Axis-aligned text is rasterized in device space and snapped to pixels,
+        // otherwise glyphs are rasterized at the average scale and transformed.
+        let axis_aligned = transform.shx == 0.0
+            && transform.shy == 0.0
+            && transform.sx > 0.0
+            && (transform.sx - transform.sy).abs() < 1e-4;
+
+        let device_scale = if axis_aligned {
+            transform.sx
+        } else {
+            let sx = (transform.sx * transform.sx + transform.shx * transform.shx).sqrt();
+            let sy = (transform.shy * transform.shy + transform.sy * transform.sy).sqrt();
+            (sx + sy) * 0.5
+        };
+        let pixel_size = size * device_scale;
+
+        let mut run = GlyphRun::new(&self.batch, instance);
+
+        for (font, glyph, pen) in iter {
+            let Some(font_data) = fonts.get(font) else {
+                continue;
+            };
+
+            // Glyph origin in device space snapped to whole pixels and the subpixel remainder.
+            let (origin, subpixel) = if axis_aligned {
+                let origin: Offset = transform.apply(pen);
+                let x = origin.x.floor();
+                (Offset::new(x, origin.y.round()), origin.x - x)
+            } else {
+                (pen, 0.0)
+            };
+
+            let quad = |entry: GlyphEntry| {
+                if axis_aligned {
+                    glyph_quad(
+                        entry.rect.translate(origin),
+                        entry.uv,
+                        Transform::identity(),
+                    )
+                } else {
+                    let inv = device_scale.recip();
+                    let Rect { min, max } = entry.rect;
+                    let rect = Rect::from_ltrb(min.x * inv, min.y * inv, max.x * inv, max.y * inv);
+                    glyph_quad(rect.translate(pen), entry.uv, transform)
+                }
+            };
+
+            // Bitmap glyphs are blitted from the color atlas as is.
+            if font_data.has_color_bitmaps() {
+                let key = GlyphKey::new(font, glyph, pixel_size, 0.0);
+                if let Some(entry) = glyphs.color_glyph(font_data, key) {
+                    self.end_glyph_run(&run, glyphs.key(), DrawCall::ImageFont);
+
+                    let white = self.batch.instance(Instance::image([255; 4], self.scissor));
+                    let mut image = GlyphRun::new(&self.batch, white);
+                    image.push(&mut self.batch, &quad(entry));
+                    self.end_glyph_run(&image, glyphs.color_key(), DrawCall::ImageUnmultiplied);
+
+                    run = GlyphRun::new(&self.batch, instance);
+                    continue;
+                }
+            }
+
+            // Layered glyphs are drawn as a stack of outlines with palette colors.
+            let layers = font_data.color_layers(glyph);
+            if !layers.is_empty() {
+                self.end_glyph_run(&run, glyphs.key(), DrawCall::ImageFont);
+
+                for (layer, color) in layers {
+                    let key = GlyphKey::new(font, layer, pixel_size, subpixel);
+                    let Some(entry) = glyphs.glyph(font_data, key) else {
+                        continue;
+                    };
+
+                    let instance = color.map_or(instance, |color| {
+                        let paint: Paint<Key> = color.into_paint(transform);
+                        let raw = paint.to_instance(1.0, 1.0, -1.0, self.scissor);
+                        self.batch.instance(raw)
+                    });
+
+                    let mut layer = GlyphRun::new(&self.batch, instance);
+                    layer.push(&mut self.batch, &quad(entry));
+                    self.end_glyph_run(&layer, glyphs.key(), DrawCall::ImageFont);
+                }
+
+                run = GlyphRun::new(&self.batch, instance);
+                continue;
+            }
+
+            let key = GlyphKey::new(font, glyph, pixel_size, subpixel);
+            if let Some(entry) = glyphs.glyph(font_data, key) {
+                run.push(&mut self.batch, &quad(entry));
+            }
+        }
+
+        self.end_glyph_run(&run, glyphs.key(), DrawCall::ImageFont);
+    }
+
+    fn end_glyph_run(
+        &mut self,
+        run: &GlyphRun,
+        image: &Key,
+        call: impl FnOnce(DrawIndexed) -> DrawCall<Key>,
+    ) {
+        let end = self.batch.base_index();
+        if run.start != end {
+            let draw = DrawIndexed::new(run.start, end, run.base_vertex, run.instance);
+            self.calls.push(DrawCall::BindImage(image.clone()));
+            self.calls.push(call(draw));
+        }
+    }
+}
+
+/// Glyph quads sharing a single instance and atlas.
+struct GlyphRun {
+    base_vertex: i32,
+    start: u32,
+    offset: u32,
+    instance: u32,
+}
+
+impl GlyphRun {
+    fn new(batch: &Batch, instance: u32) -> Self {
+        Self {
+            base_vertex: batch.base_vertex(),
+            start: batch.base_index(),
+            offset: 0,
+            instance,
+        }
+    }
+
+    fn push(&mut self, batch: &mut Batch, vertices: &[Vertex; 4]) {
+        let _ = batch.push_strip(self.offset, vertices);
+        self.offset += 4;
+    }
+}
+
+fn glyph_quad(rect: Rect, uv: Rect, transform: Transform) -> [Vertex; 4] {
+    let Rect { min, max } = rect;
+    [
+        Vertex::new([max.x, max.y], [uv.max.x, uv.max.y]).transform(transform),
+        Vertex::new([max.x, min.y], [uv.max.x, uv.min.y]).transform(transform),
+        Vertex::new([min.x, max.y], [uv.min.x, uv.max.y]).transform(transform),
+        Vertex::new([min.x, min.y], [uv.min.x, uv.min.y]).transform(transform),
+    ]
+}
@@ -0,0 +1,452 @@
+use super::{Font, FontId};
+use crate::{Images, Rect, SoftwareImages};
+use ab_glyph_rasterizer::{point, Point, Rasterizer};
+use std::collections::HashMap;
+
+/// Glyph rasterized at a specific pixel size and subpixel offset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: FontId,
+    pub glyph: u16,
+    /// Pixel size in quarters of pixel.
+    pub size: u32,
+    /// Horizontal subpixel offset in quarters of pixel.
+    pub subpixel: u8,
+}
+
+impl GlyphKey {
+    /// Number of quantization steps per pixel used for size and subpixel offset.
+    pub const STEPS: f32 = 4.0;
+
+    pub fn new(font: FontId, glyph: u16, size: f32, subpixel: f32) -> Self {
+        Self {
+            font,
+            glyph,
+            size: (size * Self::STEPS).round() as u32,
+            subpixel: ((subpixel * Self::STEPS) as u8).min(Self::STEPS as u8 - 1),
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> f32 {
+        self.size as f32 / Self::STEPS
+    }
+
+    #[inline]
+    pub fn subpixel(&self) -> f32 {
+        f32::from(self.subpixel) / Self::STEPS
+    }
+}
+
+/// Location of a rasterized glyph in the atlas.
+#[derive(Clone, Copy, Debug)]
+pub struct GlyphEntry {
+    /// Glyph bounds in pixels relative to the pen position.
+    pub rect: Rect,
+    /// Normalized texture coordinates in the atlas.
+    pub uv: Rect,
+}
+
+/// Glyph atlases registered in [`Images`].
+///
+/// Coverage of outline glyphs is stored in a single channel atlas,
+/// bitmap color glyphs are stored in a separate RGBA atlas.
+///
+/// Atlases have a fixed size during a frame.
+/// Glyphs that do not fit are skipped and the atlas is cleared and enlarged
+/// at the start of the next frame.
+pub struct GlyphCache<Key> {
+    mask: Atlas<Key>,
+    color: Atlas<Key>,
+}
+
+impl<Key: Clone + Eq + std::hash::Hash> GlyphCache<Key> {
+    pub fn new(mask_key: Key, color_key: Key) -> Self {
+        Self {
+            mask: Atlas::new(mask_key, wgpu::TextureFormat::R8Unorm, 512, 4096),
+            color: Atlas::new(color_key, wgpu::TextureFormat::Rgba8UnormSrgb, 256, 4096),
+        }
+    }
+
+    /// Key of the coverage atlas texture in [`Images`].
+    pub fn key(&self) -> &Key {
+        &self.mask.key
+    }
+
+    /// Key of the color atlas texture in [`Images`].
+    pub fn color_key(&self) -> &Key {
+        &self.color.key
+    }
+
+    /// Size of the coverage atlas texture in pixels.
+    pub fn size(&self) -> u32 {
+        self.mask.size
+    }
+
+    /// Drops all cached glyphs.
+    pub fn clear(&mut self) {
+        self.mask.clear();
+        self.color.clear();
+    }
+
+    /// Returns the cached glyph coverage, rasterizing it if necessary.
+    ///
+    /// Returns `None` for empty glyphs and glyphs that do not fit into the atlas.
+    pub fn glyph(&mut self, font: &Font, key: GlyphKey) -> Option<GlyphEntry> {
+        self.mask
+            .get_or_insert(key, |atlas| atlas.rasterize(font, key))
+    }
+
+    /// Returns the cached bitmap image of a color glyph, decoding it if necessary.
+    ///
+    /// Returns `None` if the font has no PNG image for the glyph.
+    pub fn color_glyph(&mut self, font: &Font, key: GlyphKey) -> Option<GlyphEntry> {
+        self.color
+            .get_or_insert(key, |atlas| atlas.decode(font, key))
+    }
+
+    /// Uploads rasterized glyphs to the atlas textures.
+    ///
+    /// Must be called after recording and before [`crate::Picture::new`].
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, images: &mut Images<Key>) {
+        self.mask.upload(device, queue, images);
+        self.color.upload(device, queue, images);
+    }
+
+    /// Copies the atlases into images of the software renderer, like [`GlyphCache::upload`].
+    pub fn upload_software(&mut self, images: &mut SoftwareImages<Key>) {
+        self.mask.upload_software(images);
+        self.color.upload_software(images);
+    }
+}
+
+struct Atlas<Key> {
+    key: Key,
+    format: wgpu::TextureFormat,
+    allocator: etagere::AtlasAllocator,
+    glyphs: HashMap<GlyphKey, Option<GlyphEntry>>,
+    pixels: Vec<u8>,
+    size: u32,
+    max_size: u32,
+
+    texture: Option<wgpu::Texture>,
+    /// Region modified since the last upload as `[x0, y0, x1, y1]`.
+    dirty: Option<[u32; 4]>,
+    overflow: bool,
+    grow: bool,
+}
+
+impl<Key: Clone + Eq + std::hash::Hash> Atlas<Key> {
+    const PADDING: i32 = 1;
+
+    fn new(key: Key, format: wgpu::TextureFormat, size: u32, max_size: u32) -> Self {
+        let mut atlas = Self {
+            key,
+            format,
+            allocator: etagere::AtlasAllocator::new(etagere::size2(1, 1)),
+            glyphs: HashMap::default(),
+            pixels: Vec::new(),
+            size,
+            max_size: max_size.max(size),
+
+            texture: None,
+            dirty: None,
+            overflow: false,
+            grow: false,
+        };
+        atlas.resize(size);
+        atlas
+    }
+
+    fn bytes_per_pixel(&self) -> u32 {
+        self.format.block_size(None).unwrap_or(1)
+    }
+
+    fn clear(&mut self) {
+        self.allocator.clear();
+        self.glyphs.clear();
+        self.pixels.fill(0);
+        self.dirty = Some([0, 0, self.size, self.size]);
+    }
+
+    fn resize(&mut self, size: u32) {
+        self.size = size;
+        self.allocator = etagere::AtlasAllocator::new(etagere::size2(size as i32, size as i32));
+        self.glyphs.clear();
+        self.pixels = vec![0; (size * size * self.bytes_per_pixel()) as usize];
+        self.texture = None;
+        self.dirty = None;
+    }
+
+    fn get_or_insert(
+        &mut self,
+        key: GlyphKey,
+        insert: impl FnOnce(&mut Self) -> Option<GlyphEntry>,
+    ) -> Option<GlyphEntry> {
+        if self.grow {
+            self.grow = false;
+            self.resize((self.size * 2).min(self.max_size));
+        }
+
+        if let Some(&entry) = self.glyphs.get(&key) {
+            return entry;
+        }
+
+        let entry = insert(self);
+        if entry.is_some() || !self.overflow {
+            let _ = self.glyphs.insert(key, entry);
+        }
+        entry
+    }
+
+    /// Allocates a padded region and returns the position of its interior.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<[u32; 2]> {
+        let padding = Self::PADDING;
+        let size = etagere::size2(width as i32 + padding * 2, height as i32 + padding * 2);
+        let Some(allocation) = self.allocator.allocate(size) else {
+            self.overflow = true;
+            return None;
+        };
+
+        let x = (allocation.rectangle.min.x + padding) as u32;
+        let y = (allocation.rectangle.min.y + padding) as u32;
+
+        let [x1, y1] = [x + width, y + height];
+        self.dirty = Some(match self.dirty {
+            Some([dx0, dy0, dx1, dy1]) => [dx0.min(x), dy0.min(y), dx1.max(x1), dy1.max(y1)],
+            None => [x, y, x1, y1],
+        });
+
+        Some([x, y])
+    }
+
+    fn uv(&self, [x, y]: [u32; 2], width: u32, height: u32) -> Rect {
+        let inv = (self.size as f32).recip();
+        Rect::from_ltrb(
+            x as f32 * inv,
+            y as f32 * inv,
+            (x + width) as f32 * inv,
+            (y + height) as f32 * inv,
+        )
+    }
+
+    fn rasterize(&mut self, font: &Font, key: GlyphKey) -> Option<GlyphEntry> {
+        let face = font.face();
+        let glyph = ttf_parser::GlyphId(key.glyph);
+        let bbox = face.glyph_bounding_box(glyph)?;
+
+        let scale = font.scale(key.size());
+        let subpixel = key.subpixel();
+
+        // Glyph bounds in pixels, y axis points down.
+        let x0 = (f32::from(bbox.x_min) * scale + subpixel).floor();
+        let y0 = (-f32::from(bbox.y_max) * scale).floor();
+        let x1 = (f32::from(bbox.x_max) * scale + subpixel).ceil();
+        let y1 = (-f32::from(bbox.y_min) * scale).ceil();
+
+        let width = (x1 - x0) as i32;
+        let height = (y1 - y0) as i32;
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+        let (width, height) = (width as u32, height as u32);
+        let origin @ [ax, ay] = self.allocate(width, height)?;
+
+        let mut builder = OutlineBuilder {
+            rasterizer: Rasterizer::new(width as usize, height as usize),
+            scale,
+            offset: [subpixel - x0, -y0],
+            start: point(0.0, 0.0),
+            last: point(0.0, 0.0),
+        };
+        let _ = face.outline_glyph(glyph, &mut builder);
+
+        let stride = self.size as usize;
+        let pixels = &mut self.pixels;
+        builder.rasterizer.for_each_pixel_2d(|x, y, alpha| {
+            let index = (ay + y) as usize * stride + (ax + x) as usize;
+            pixels[index] = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+
+        Some(GlyphEntry {
+            rect: Rect::from_ltrb(x0, y0, x1, y1),
+            uv: self.uv(origin, width, height),
+        })
+    }
+
+    fn decode(&mut self, font: &Font, key: GlyphKey) -> Option<GlyphEntry> {
+        let face = font.face();
+        let glyph = ttf_parser::GlyphId(key.glyph);
+        let ppem = key.size().ceil().clamp(1.0, f32::from(u16::MAX)) as u16;
+        let image = face.glyph_raster_image(glyph, ppem)?;
+        if image.format != ttf_parser::RasterImageFormat::PNG {
+            return None;
+        }
+
+        let (width, height, rgba) = decode_png(image.data)?;
+        let origin @ [ax, ay] = self.allocate(width, height)?;
+
+        let stride = (self.size * 4) as usize;
+        let row = (width * 4) as usize;
+        for (y, src) in rgba.chunks_exact(row).enumerate() {
+            let start = (ay as usize + y) * stride + ax as usize * 4;
+            self.pixels[start..start + row].copy_from_slice(src);
+        }
+
+        // Image offset points to the bottom left corner with y axis pointing up.
+        let scale = key.size() / f32::from(image.pixels_per_em);
+        let x = f32::from(image.x) * scale;
+        let y = -f32::from(image.y) * scale;
+        Some(GlyphEntry {
+            rect: Rect::from_ltrb(x, y - height as f32 * scale, x + width as f32 * scale, y),
+            uv: self.uv(origin, width, height),
+        })
+    }
+
+    /// Enlarges the atlas at the start of the next frame if glyphs did not fit.
+    fn finish_frame(&mut self) {
+        if self.overflow {
+            self.overflow = false;
+            self.grow = true;
+        }
+    }
+
+    fn upload_software(&mut self, images: &mut SoftwareImages<Key>) {
+        self.finish_frame();
+        let key = self.key.clone();
+        images.insert(key, self.size, self.size, self.format, &self.pixels);
+    }
+
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, images: &mut Images<Key>) {
+        self.finish_frame();
+
+        let size = wgpu::Extent3d {
+            width: self.size,
+            height: self.size,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = match self.texture.as_ref() {
+            Some(texture) => texture,
+            None => {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("reui::GlyphCache"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: self.format,
+                    usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                });
+
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                drop(images.insert(device, self.key.clone(), &view, size, None));
+
+                self.dirty = Some([0, 0, self.size, self.size]);
+                self.texture.insert(texture)
+            }
+        };
+
+        if let Some([x0, y0, x1, y1]) = self.dirty.take() {
+            let bytes_per_pixel = self.format.block_size(None).unwrap_or(1);
+
+            let copy_texture = wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: x0, y: y0, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            };
+
+            let data_layout = wgpu::ImageDataLayout {
+                offset: ((y0 * self.size + x0) * bytes_per_pixel) as u64,
+                bytes_per_row: Some(self.size * bytes_per_pixel),
+                rows_per_image: None,
+            };
+
+            let extent = wgpu::Extent3d {
+                width: x1 - x0,
+                height: y1 - y0,
+                depth_or_array_layers: 1,
+            };
+
+            queue.write_texture(copy_texture, &self.pixels, data_layout, extent);
+        }
+    }
+}
+
+/// Decodes PNG data into unmultiplied RGBA pixels.
+fn decode_png(data: &[u8]) -> Option<(u32, u32, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).ok()?;
+    let pixels = &buf[..info.buffer_size()];
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|c| [c[0], c[1], c[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|c| [c[0], c[0], c[0], c[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&c| [c, c, c, 255]).collect(),
+        png::ColorType::Indexed => return None,
+    };
+
+    Some((info.width, info.height, rgba))
+}
+
+struct OutlineBuilder {
+    rasterizer: Rasterizer,
+    scale: f32,
+    offset: [f32; 2],
+    start: Point,
+    last: Point,
+}
+
+impl OutlineBuilder {
+    #[inline]
+    fn point(&self, x: f32, y: f32) -> Point {
+        point(
+            x * self.scale + self.offset[0],
+            -y * self.scale + self.offset[1],
+        )
+    }
+}
+
+impl ttf_parser::OutlineBuilder for OutlineBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.point(x, y);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p1 = self.point(x, y);
+        self.rasterizer.draw_line(self.last, p1);
+        self.last = p1;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p1, p2) = (self.point(x1, y1), self.point(x, y));
+        self.rasterizer.draw_quad(self.last, p1, p2);
+        self.last = p2;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p1, p2, p3) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.rasterizer.draw_cubic(self.last, p1, p2, p3);
+        self.last = p3;
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.rasterizer.draw_line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
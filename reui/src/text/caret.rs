@@ -0,0 +1,158 @@
+use super::{Cluster, Paragraph};
+use crate::{Offset, Rect};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation as _;
+
+impl Paragraph {
+    /// Returns the index of the line containing the byte offset.
+    pub fn line_at_offset(&self, offset: usize) -> usize {
+        let last = self.lines.len().saturating_sub(1);
+        self.lines
+            .iter()
+            .position(|line| offset < line.range.end)
+            .unwrap_or(last)
+    }
+
+    /// Returns the index of the line at the vertical position.
+    pub fn line_at_y(&self, y: f32) -> usize {
+        let last = self.lines.len().saturating_sub(1);
+        self.lines
+            .iter()
+            .position(|line| y < line.top + line.height)
+            .unwrap_or(last)
+    }
+
+    /// Returns the horizontal caret position for the byte offset.
+    pub fn offset_to_x(&self, offset: usize) -> f32 {
+        let Some(line) = self.lines.get(self.line_at_offset(offset)) else {
+            return 0.0;
+        };
+        let clusters = &self.clusters[line.clusters.clone()];
+
+        let inside = clusters.iter().find(|c| c.range.contains(&offset));
+        let after = || clusters.iter().find(|c| c.range.end == offset);
+        match inside.or_else(after) {
+            Some(cluster) => self.cluster_x(cluster, offset),
+            // Empty line or trailing whitespace.
+            None if line.rtl => line.left,
+            None => line.left + line.width,
+        }
+    }
+
+    /// Returns a zero width rectangle of the caret at the byte offset.
+    pub fn caret_rect(&self, offset: usize) -> Rect {
+        let Some(line) = self.lines.get(self.line_at_offset(offset)) else {
+            return Rect::default();
+        };
+        let x = self.offset_to_x(offset);
+        Rect::from_ltwh(x, line.top, 0.0, line.height)
+    }
+
+    /// Returns rectangles covering the selected byte range.
+    ///
+    /// Bidirectional text can produce more than one rectangle per line.
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let mut rects: Vec<Rect> = Vec::new();
+        for line in &self.lines {
+            if range.end <= line.range.start || line.range.end <= range.start {
+                continue;
+            }
+
+            let first = rects.len();
+            for cluster in &self.clusters[line.clusters.clone()] {
+                let start = range.start.max(cluster.range.start);
+                let end = range.end.min(cluster.range.end);
+                if start >= end {
+                    continue;
+                }
+
+                let x0 = self.cluster_x(cluster, start);
+                let x1 = self.cluster_x(cluster, end);
+                let (left, right) = (x0.min(x1), x0.max(x1));
+
+                match rects[first..].last_mut() {
+                    Some(last) if (last.max.x - left).abs() < 0.01 => last.max.x = right,
+                    _ => rects.push(Rect::from_ltrb(
+                        left,
+                        line.top,
+                        right,
+                        line.top + line.height,
+                    )),
+                }
+            }
+        }
+        rects
+    }
+
+    /// Returns the byte offset of the caret position closest to the point.
+    pub fn hit_test(&self, point: Offset) -> usize {
+        let Some(line) = self.lines.get(self.line_at_y(point.y)) else {
+            return 0;
+        };
+        let clusters = &self.clusters[line.clusters.clone()];
+
+        let (Some(first), Some(last)) = (clusters.first(), clusters.last()) else {
+            return line.range.start;
+        };
+
+        if point.x < first.left {
+            return if first.rtl {
+                first.range.end
+            } else {
+                first.range.start
+            };
+        }
+
+        let cluster = clusters
+            .iter()
+            .find(|cluster| point.x < cluster.right)
+            .unwrap_or(last);
+
+        // Split ligatures between grapheme clusters.
+        let boundaries = self.grapheme_boundaries(cluster);
+        let count = boundaries.len() - 1;
+        let width = cluster.right - cluster.left;
+        let t = if width > 0.0 {
+            let t = (point.x - cluster.left) / width;
+            if cluster.rtl {
+                1.0 - t
+            } else {
+                t
+            }
+        } else {
+            0.0
+        };
+
+        let index = (t.clamp(0.0, 1.0) * count as f32).round() as usize;
+        boundaries[index.min(count)]
+    }
+
+    /// Returns the caret position within the cluster, snapped to grapheme boundaries.
+    fn cluster_x(&self, cluster: &Cluster, offset: usize) -> f32 {
+        let boundaries = self.grapheme_boundaries(cluster);
+        let count = boundaries.len() - 1;
+        let index = boundaries.iter().filter(|&&b| b <= offset).count().max(1) - 1;
+
+        let t = index as f32 / count as f32;
+        let width = cluster.right - cluster.left;
+        if cluster.rtl {
+            cluster.right - t * width
+        } else {
+            cluster.left + t * width
+        }
+    }
+
+    fn grapheme_boundaries(&self, cluster: &Cluster) -> Vec<usize> {
+        let Range { start, end } = cluster.range;
+        let text = self.text.get(start..end).unwrap_or_default();
+        let mut boundaries: Vec<usize> = text
+            .grapheme_indices(true)
+            .map(|(index, _)| start + index)
+            .collect();
+        if boundaries.is_empty() {
+            boundaries.push(start);
+        }
+        boundaries.push(end);
+        boundaries
+    }
+}
@@ -0,0 +1,211 @@
+use crate::Color;
+use std::sync::Arc;
+
+self_cell::self_cell!(
+    /// Font data with the face parsed from it.
+    struct FaceCell {
+        owner: Arc<[u8]>,
+        #[covariant]
+        dependent: ShapingFace,
+    }
+);
+
+type ShapingFace<'a> = rustybuzz::Face<'a>;
+
+/// Handle of a font loaded into a [`FontStore`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FontId(pub(crate) u32);
+
+/// Vertical metrics of a font scaled to a font size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FontMetrics {
+    /// Distance from the baseline to the top of the line, positive upwards.
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the line, negative downwards.
+    pub descent: f32,
+    pub line_gap: f32,
+}
+
+impl FontMetrics {
+    #[inline]
+    pub fn line_height(&self) -> f32 {
+        self.ascent - self.descent + self.line_gap
+    }
+}
+
+/// Parsed font file data.
+pub struct Font {
+    face: FaceCell,
+    index: u32,
+    units_per_em: f32,
+    ascender: f32,
+    descender: f32,
+    line_gap: f32,
+    color_layers: bool,
+    color_bitmaps: bool,
+}
+
+impl Font {
+    /// Parses a font from TrueType or OpenType data, `index` selects a face in a collection.
+    pub fn new(
+        data: impl Into<Arc<[u8]>>,
+        index: u32,
+    ) -> Result<Self, ttf_parser::FaceParsingError> {
+        let face = FaceCell::try_new(data.into(), |data| {
+            ttf_parser::Face::parse(data, index).map(rustybuzz::Face::from_face)
+        })?;
+        let face_ref = face.borrow_dependent();
+
+        let units_per_em = face_ref.units_per_em() as f32;
+        let ascender = face_ref.ascender() as f32;
+        let descender = face_ref.descender() as f32;
+        let line_gap = face_ref.line_gap() as f32;
+
+        let tables = face_ref.tables();
+        let color_layers = tables.colr.is_some();
+        let color_bitmaps = tables.sbix.is_some() || tables.cbdt.is_some();
+
+        Ok(Self {
+            face,
+            index,
+            units_per_em,
+            ascender,
+            descender,
+            line_gap,
+            color_layers,
+            color_bitmaps,
+        })
+    }
+
+    /// Returns the parsed font face.
+    #[inline]
+    pub fn face(&self) -> &ttf_parser::Face<'_> {
+        self.face.borrow_dependent()
+    }
+
+    /// Returns the font face prepared for shaping.
+    #[inline]
+    pub fn shaping_face(&self) -> &rustybuzz::Face<'_> {
+        self.face.borrow_dependent()
+    }
+
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        self.face.borrow_owner()
+    }
+
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    #[inline]
+    pub fn units_per_em(&self) -> f32 {
+        self.units_per_em
+    }
+
+    /// Returns scale from font units to pixels for the font size.
+    #[inline]
+    pub fn scale(&self, size: f32) -> f32 {
+        size / self.units_per_em
+    }
+
+    /// Returns `true` if the font has `COLR` layered color glyphs.
+    #[inline]
+    pub fn has_color_layers(&self) -> bool {
+        self.color_layers
+    }
+
+    /// Returns `true` if the font has `sbix` or `CBDT` bitmap glyphs.
+    #[inline]
+    pub fn has_color_bitmaps(&self) -> bool {
+        self.color_bitmaps
+    }
+
+    /// Returns layers of a `COLR` glyph from the first palette, bottom to top.
+    ///
+    /// Layers without a color use the text paint.
+    /// Returns an empty list for glyphs without layers.
+    pub fn color_layers(&self, glyph: u16) -> Vec<(u16, Option<Color>)> {
+        struct Layers(Vec<(u16, Option<Color>)>, u16);
+
+        impl ttf_parser::colr::Painter for Layers {
+            fn outline(&mut self, glyph: ttf_parser::GlyphId) {
+                self.1 = glyph.0;
+            }
+
+            fn paint_foreground(&mut self) {
+                self.0.push((self.1, None));
+            }
+
+            fn paint_color(&mut self, c: ttf_parser::RgbaColor) {
+                let color = Color::new_srgba8(c.red, c.green, c.blue, c.alpha);
+                self.0.push((self.1, Some(color)));
+            }
+        }
+
+        let mut layers = Layers(Vec::new(), 0);
+        if self.color_layers {
+            let face = self.face();
+            let glyph = ttf_parser::GlyphId(glyph);
+            if face.is_color_glyph(glyph) {
+                let _ = face.paint_color_glyph(glyph, 0, &mut layers);
+            }
+        }
+        layers.0
+    }
+
+    pub fn metrics(&self, size: f32) -> FontMetrics {
+        let scale = self.scale(size);
+        FontMetrics {
+            ascent: self.ascender * scale,
+            descent: self.descender * scale,
+            line_gap: self.line_gap * scale,
+        }
+    }
+}
+
+/// Collection of loaded fonts.
+#[derive(Default)]
+pub struct FontStore {
+    fonts: Vec<Font>,
+}
+
+impl std::ops::Index<FontId> for FontStore {
+    type Output = Font;
+    fn index(&self, index: FontId) -> &Self::Output {
+        &self.fonts[index.0 as usize]
+    }
+}
+
+impl FontStore {
+    pub const fn new() -> Self {
+        Self { fonts: Vec::new() }
+    }
+
+    pub fn get(&self, font: FontId) -> Option<&Font> {
+        self.fonts.get(font.0 as usize)
+    }
+
+    pub fn insert(&mut self, font: Font) -> FontId {
+        let id = FontId(self.fonts.len() as u32);
+        self.fonts.push(font);
+        id
+    }
+
+    /// Loads a font from memory.
+    pub fn load(
+        &mut self,
+        data: impl Into<Arc<[u8]>>,
+        index: u32,
+    ) -> Result<FontId, ttf_parser::FaceParsingError> {
+        Font::new(data, index).map(|font| self.insert(font))
+    }
+
+    /// Loads the first face of a font file.
+    pub fn load_file(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<FontId> {
+        let data = std::fs::read(path)?;
+        self.load(data, 0)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+}
@@ -0,0 +1,459 @@
+use super::{FontId, FontStore};
+use crate::Offset;
+use std::ops::Range;
+use unicode_bidi::{BidiInfo, Level};
+use unicode_linebreak::BreakOpportunity;
+use unicode_segmentation::UnicodeSegmentation as _;
+
+/// Horizontal alignment of lines within a paragraph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    /// Left for left-to-right paragraphs and right for right-to-left ones.
+    #[default]
+    Start,
+    /// Right for left-to-right paragraphs and left for right-to-left ones.
+    End,
+    Left,
+    Right,
+    Center,
+}
+
+/// Base direction of a paragraph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParagraphStyle {
+    pub font: FontId,
+    /// Fonts tried in order for characters missing in the primary font.
+    pub fallbacks: Vec<FontId>,
+    pub size: f32,
+    /// Width at which lines are wrapped, `f32::INFINITY` disables wrapping.
+    pub max_width: f32,
+    pub align: TextAlign,
+    /// Line height as a multiple of the font line height.
+    pub line_height: f32,
+    /// Base direction, detected from the first strong character if `None`.
+    pub direction: Option<TextDirection>,
+    /// OpenType features applied to the whole text.
+    pub features: Vec<rustybuzz::Feature>,
+}
+
+impl ParagraphStyle {
+    pub fn new(font: FontId, size: f32) -> Self {
+        Self {
+            font,
+            fallbacks: Vec::new(),
+            size,
+            max_width: f32::INFINITY,
+            align: TextAlign::Start,
+            line_height: 1.0,
+            direction: None,
+            features: Vec::new(),
+        }
+    }
+
+    /// Appends a font to the fallback chain.
+    pub fn fallback(mut self, font: FontId) -> Self {
+        self.fallbacks.push(font);
+        self
+    }
+
+    pub fn max_width(self, max_width: f32) -> Self {
+        Self { max_width, ..self }
+    }
+
+    pub fn align(self, align: TextAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    pub fn line_height(self, line_height: f32) -> Self {
+        Self {
+            line_height,
+            ..self
+        }
+    }
+
+    pub fn direction(self, direction: TextDirection) -> Self {
+        Self {
+            direction: Some(direction),
+            ..self
+        }
+    }
+
+    /// Enables or sets an OpenType feature, e.g. `feature(b"smcp", 1)`.
+    pub fn feature(mut self, tag: &[u8; 4], value: u32) -> Self {
+        let tag = rustybuzz::Tag::from_bytes(tag);
+        self.features.push(rustybuzz::Feature::new(tag, value, ..));
+        self
+    }
+}
+
+/// Glyph placed by the paragraph layout.
+#[derive(Clone, Copy, Debug)]
+pub struct PositionedGlyph {
+    pub font: FontId,
+    pub glyph: u16,
+    /// Glyph origin on the baseline relative to the paragraph origin.
+    pub position: Offset,
+    pub advance: f32,
+    /// Byte offset of the first character of the cluster.
+    pub cluster: usize,
+    pub rtl: bool,
+}
+
+/// Glyphs of a single cluster placed next to each other.
+#[derive(Clone, Debug)]
+pub struct Cluster {
+    /// Byte range of the cluster.
+    pub range: Range<usize>,
+    pub left: f32,
+    pub right: f32,
+    pub rtl: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LineMetrics {
+    /// Byte range of the line including trailing whitespace.
+    pub range: Range<usize>,
+    /// Range of the line in [`Paragraph::glyphs`].
+    pub glyphs: Range<usize>,
+    /// Range of the line in [`Paragraph::clusters`], in visual order.
+    pub clusters: Range<usize>,
+    /// Offset of the line start after alignment.
+    pub left: f32,
+    /// Width of the line without trailing whitespace.
+    pub width: f32,
+    pub top: f32,
+    pub baseline: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub height: f32,
+    pub rtl: bool,
+}
+
+/// Shaped and wrapped text laid out from the top left corner.
+#[derive(Clone, Debug, Default)]
+pub struct Paragraph {
+    pub text: String,
+    pub size: f32,
+    pub glyphs: Vec<PositionedGlyph>,
+    pub clusters: Vec<Cluster>,
+    pub lines: Vec<LineMetrics>,
+    /// Width of the widest line.
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Range of text with a single bidi level, script and font.
+struct Item {
+    range: Range<usize>,
+    level: Level,
+    font: usize,
+    glyphs: Range<usize>,
+}
+
+#[derive(Clone, Copy)]
+struct ShapedGlyph {
+    font: FontId,
+    glyph: u16,
+    cluster: usize,
+    advance: f32,
+    offset: Offset,
+}
+
+impl Paragraph {
+    pub fn new(fonts: &FontStore, text: &str, style: &ParagraphStyle) -> Self {
+        let Some(font) = fonts.get(style.font) else {
+            return Self::default();
+        };
+        let metrics = font.metrics(style.size);
+
+        let level = style.direction.map(|direction| match direction {
+            TextDirection::Ltr => Level::ltr(),
+            TextDirection::Rtl => Level::rtl(),
+        });
+        let bidi = BidiInfo::new(text, level);
+
+        let chain: Vec<(FontId, f32, &rustybuzz::Face<'_>)> = std::iter::once(style.font)
+            .chain(style.fallbacks.iter().copied())
+            .filter_map(|id| {
+                let font = fonts.get(id)?;
+                Some((id, font.scale(style.size), font.shaping_face()))
+            })
+            .collect();
+
+        // Shape every item in logical order.
+        let mut shaped = Vec::new();
+        let mut advances = vec![0.0; text.len()];
+        let mut items = itemize(text, &bidi.levels, &chain);
+        for item in &mut items {
+            let (_, scale, face) = &chain[item.font];
+            let scale = *scale;
+
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(&text[item.range.clone()]);
+            buffer.guess_segment_properties();
+            buffer.set_direction(if item.level.is_rtl() {
+                rustybuzz::Direction::RightToLeft
+            } else {
+                rustybuzz::Direction::LeftToRight
+            });
+
+            let output = rustybuzz::shape(face, &style.features, buffer);
+            let start = shaped.len();
+            for (info, pos) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+                let cluster = item.range.start + info.cluster as usize;
+                let advance = pos.x_advance as f32 * scale;
+                advances[cluster] += advance;
+                shaped.push(ShapedGlyph {
+                    font: chain[item.font].0,
+                    glyph: info.glyph_id as u16,
+                    cluster,
+                    advance,
+                    offset: Offset::new(pos.x_offset as f32, -pos.y_offset as f32) * scale,
+                });
+            }
+            item.glyphs = start..shaped.len();
+        }
+
+        let width = |range: Range<usize>| -> f32 {
+            let end = range.start + text[range.clone()].trim_end().len();
+            advances[range.start..end].iter().sum()
+        };
+
+        // Greedy line breaking at the allowed break opportunities.
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        let mut candidate = None;
+        for (offset, opportunity) in unicode_linebreak::linebreaks(text) {
+            if let Some(candidate) = candidate {
+                if width(start..offset) > style.max_width {
+                    ranges.push(start..candidate);
+                    start = candidate;
+                }
+            }
+
+            if opportunity == BreakOpportunity::Mandatory {
+                ranges.push(start..offset);
+                start = offset;
+                candidate = None;
+            } else {
+                candidate = Some(offset);
+            }
+        }
+        if ranges.is_empty() || text.ends_with(is_line_separator) {
+            ranges.push(text.len()..text.len());
+        }
+
+        let line_height = metrics.line_height() * style.line_height;
+        let leading = (line_height - (metrics.ascent - metrics.descent)) * 0.5;
+
+        let mut paragraph = Self {
+            text: text.to_owned(),
+            size: style.size,
+            ..Self::default()
+        };
+
+        for range in ranges {
+            let top = paragraph.height;
+            let baseline = top + leading + metrics.ascent;
+            let first = paragraph.glyphs.len();
+            let first_cluster = paragraph.clusters.len();
+
+            let para = bidi
+                .paragraphs
+                .iter()
+                .find(|para| para.range.contains(&range.start))
+                .or(bidi.paragraphs.last());
+            let rtl = para.is_some_and(|para| para.level.is_rtl());
+
+            let trimmed = range.start..range.start + text[range.clone()].trim_end().len();
+            let mut pen = 0.0;
+            if let (Some(para), false) = (para, trimmed.is_empty()) {
+                let (levels, runs) = bidi.visual_runs(para, trimmed.clone());
+                for run in runs {
+                    let rtl = levels[run.start].is_rtl();
+                    let overlaps =
+                        |item: &&Item| item.range.start < run.end && run.start < item.range.end;
+
+                    let mut visit = |item: &Item| {
+                        for glyph in &shaped[item.glyphs.clone()] {
+                            if !run.contains(&glyph.cluster) {
+                                continue;
+                            }
+                            let origin = Offset::new(pen, baseline);
+                            paragraph.glyphs.push(PositionedGlyph {
+                                font: glyph.font,
+                                glyph: glyph.glyph,
+                                position: origin + glyph.offset,
+                                advance: glyph.advance,
+                                cluster: glyph.cluster,
+                                rtl,
+                            });
+
+                            match paragraph.clusters[first_cluster..].last_mut() {
+                                Some(last) if last.range.start == glyph.cluster => {
+                                    last.right += glyph.advance;
+                                }
+                                _ => paragraph.clusters.push(Cluster {
+                                    range: glyph.cluster..glyph.cluster,
+                                    left: pen,
+                                    right: pen + glyph.advance,
+                                    rtl,
+                                }),
+                            }
+
+                            pen += glyph.advance;
+                        }
+                    };
+
+                    if rtl {
+                        items.iter().filter(overlaps).rev().for_each(&mut visit);
+                    } else {
+                        items.iter().filter(overlaps).for_each(&mut visit);
+                    }
+                }
+            }
+
+            // Each cluster ends where the next one in logical order starts.
+            let clusters = &mut paragraph.clusters[first_cluster..];
+            let mut starts: Vec<usize> = clusters.iter().map(|c| c.range.start).collect();
+            starts.sort_unstable();
+            for cluster in clusters {
+                let next = starts.partition_point(|&start| start <= cluster.range.start);
+                cluster.range.end = starts.get(next).copied().unwrap_or(trimmed.end);
+            }
+
+            paragraph.width = paragraph.width.max(pen);
+            paragraph.height += line_height;
+            paragraph.lines.push(LineMetrics {
+                range,
+                glyphs: first..paragraph.glyphs.len(),
+                clusters: first_cluster..paragraph.clusters.len(),
+                left: 0.0,
+                width: pen,
+                top,
+                baseline,
+                ascent: metrics.ascent,
+                descent: metrics.descent,
+                height: line_height,
+                rtl,
+            });
+        }
+
+        let available = if style.max_width.is_finite() {
+            style.max_width
+        } else {
+            paragraph.width
+        };
+
+        for line in &mut paragraph.lines {
+            let align = match (style.align, line.rtl) {
+                (TextAlign::Start, false) | (TextAlign::End, true) => TextAlign::Left,
+                (TextAlign::Start, true) | (TextAlign::End, false) => TextAlign::Right,
+                (align, _) => align,
+            };
+            line.left = match align {
+                TextAlign::Right => available - line.width,
+                TextAlign::Center => (available - line.width) * 0.5,
+                _ => 0.0,
+            };
+            for glyph in &mut paragraph.glyphs[line.glyphs.clone()] {
+                glyph.position.x += line.left;
+            }
+            for cluster in &mut paragraph.clusters[line.clusters.clone()] {
+                cluster.left += line.left;
+                cluster.right += line.left;
+            }
+        }
+
+        paragraph
+    }
+}
+
+fn is_line_separator(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Splits text into runs of a single bidi level, script and font.
+///
+/// Characters of common and inherited scripts are merged into the surrounding run.
+/// Each grapheme uses the first font of the chain that covers all of its characters.
+fn itemize(
+    text: &str,
+    levels: &[Level],
+    chain: &[(FontId, f32, &rustybuzz::Face<'_>)],
+) -> Vec<Item> {
+    use unicode_script::{Script, UnicodeScript as _};
+
+    let covers = |font: usize, grapheme: &str| {
+        let face = &chain[font].2;
+        grapheme
+            .chars()
+            .filter(|&c| !is_default_ignorable(c))
+            .all(|c| face.glyph_index(c).is_some())
+    };
+
+    let mut items: Vec<Item> = Vec::new();
+    let mut script = Script::Common;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        let end = offset + grapheme.len();
+        let level = levels[offset];
+        let char_script = grapheme
+            .chars()
+            .map(|c| c.script())
+            .find(|script| !matches!(script, Script::Common | Script::Inherited | Script::Unknown))
+            .unwrap_or(Script::Common);
+        let is_common = char_script == Script::Common;
+
+        // Common characters stay in the current font if it has them.
+        let last_font = items.last().map(|item| item.font);
+        let font = match last_font {
+            Some(font) if is_common && covers(font, grapheme) => font,
+            _ => (0..chain.len())
+                .find(|&font| covers(font, grapheme))
+                .unwrap_or(0),
+        };
+
+        if let Some(item) = items.last_mut() {
+            let same_script = is_common || script == Script::Common || char_script == script;
+            if item.level == level && item.font == font && same_script {
+                item.range.end = end;
+                if !is_common {
+                    script = char_script;
+                }
+                continue;
+            }
+        }
+
+        script = char_script;
+        items.push(Item {
+            range: offset..end,
+            level,
+            font,
+            glyphs: 0..0,
+        });
+    }
+    items
+}
+
+fn is_default_ignorable(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{E0000}'..='\u{E0FFF}'
+    )
+}
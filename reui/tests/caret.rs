@@ -0,0 +1,131 @@
+//! Caret positions, selections and hit-testing with the bundled DejaVu Sans font.
+
+mod common;
+
+use common::{assert_near, font, A, ALEF, BET, SIZE, SPACE};
+use reui::{Offset, Paragraph, ParagraphStyle};
+
+fn layout(text: &str, max_width: f32) -> Paragraph {
+    let (fonts, font) = font();
+    let style = ParagraphStyle::new(font, SIZE).max_width(max_width);
+    Paragraph::new(&fonts, text, &style)
+}
+
+#[test]
+fn caret_at_cluster_boundaries() {
+    let paragraph = layout("aa aa", f32::INFINITY);
+
+    let expected = [
+        0.0,
+        A,
+        2.0 * A,
+        2.0 * A + SPACE,
+        3.0 * A + SPACE,
+        4.0 * A + SPACE,
+    ];
+    for (offset, x) in expected.into_iter().enumerate() {
+        assert_near(paragraph.offset_to_x(offset), x, 1e-3);
+    }
+
+    let rect = paragraph.caret_rect(1);
+    assert_near(rect.min.x, A, 1e-3);
+    assert_near(rect.max.x, A, 1e-3);
+    assert_near(rect.min.y, 0.0, 1e-3);
+    assert_near(rect.max.y, paragraph.lines[0].height, 1e-3);
+}
+
+#[test]
+fn caret_within_ligature_splits_graphemes() {
+    // DejaVu Sans forms an "fi" ligature.
+    let paragraph = layout("fi", f32::INFINITY);
+    assert_eq!(paragraph.glyphs.len(), 1);
+
+    let cluster = &paragraph.clusters[0];
+    assert_eq!(cluster.range, 0..2);
+    let middle = (cluster.left + cluster.right) * 0.5;
+    assert_near(paragraph.offset_to_x(1), middle, 1e-3);
+    assert_eq!(paragraph.hit_test(Offset::new(middle - 1.0, 5.0)), 1);
+    assert_eq!(paragraph.hit_test(Offset::new(cluster.right - 1.0, 5.0)), 2);
+}
+
+#[test]
+fn caret_at_line_ends() {
+    // Wraps after the space into "aa " and "aa".
+    let paragraph = layout("aa aa", 40.0);
+    assert_eq!(paragraph.lines.len(), 2);
+    let second = &paragraph.lines[1];
+
+    // The end of the first word stays on the first line.
+    assert_eq!(paragraph.line_at_offset(2), 0);
+    assert_near(paragraph.offset_to_x(2), 2.0 * A, 1e-3);
+    // The offset after the trailing space starts the second line.
+    assert_eq!(paragraph.line_at_offset(3), 1);
+    assert_near(paragraph.offset_to_x(3), 0.0, 1e-3);
+    assert_near(paragraph.caret_rect(3).min.y, second.top, 1e-3);
+    // The end of the text.
+    assert_eq!(paragraph.line_at_offset(5), 1);
+    assert_near(paragraph.offset_to_x(5), 2.0 * A, 1e-3);
+
+    // Empty trailing line after a newline.
+    let paragraph = layout("aa\n", f32::INFINITY);
+    assert_eq!(paragraph.line_at_offset(3), 1);
+    assert_near(paragraph.offset_to_x(3), 0.0, 1e-3);
+}
+
+#[test]
+fn hit_test_snaps_to_nearest_boundary() {
+    let paragraph = layout("aa aa", 40.0);
+    let y0 = paragraph.lines[0].baseline;
+    let y1 = paragraph.lines[1].baseline;
+
+    assert_eq!(paragraph.hit_test(Offset::new(-10.0, y0)), 0);
+    assert_eq!(paragraph.hit_test(Offset::new(A * 0.4, y0)), 0);
+    assert_eq!(paragraph.hit_test(Offset::new(A * 0.6, y0)), 1);
+    // Past the end of the first line.
+    assert_eq!(paragraph.hit_test(Offset::new(39.0, y0)), 2);
+    assert_eq!(paragraph.hit_test(Offset::new(A * 1.6, y1)), 5);
+    // Below the last line.
+    assert_eq!(paragraph.hit_test(Offset::new(A * 1.4, 1000.0)), 4);
+}
+
+#[test]
+fn caret_at_bidi_run_boundaries() {
+    // Visual order: "aa " then bet (5..7) and alef (3..5), then " aa".
+    let paragraph = layout("aa אב aa", f32::INFINITY);
+    let rtl_start = 2.0 * A + SPACE;
+    let rtl_end = rtl_start + BET + ALEF;
+
+    // Logical start of the right-to-left run is at its right edge.
+    assert_near(paragraph.offset_to_x(3), rtl_end, 1e-3);
+    assert_near(paragraph.offset_to_x(5), rtl_start + BET, 1e-3);
+    // The space after the run starts at its visual end.
+    assert_near(paragraph.offset_to_x(7), rtl_end, 1e-3);
+    assert_near(paragraph.offset_to_x(8), rtl_end + SPACE, 1e-3);
+
+    // Near the right edge of alef is its logical start.
+    assert_eq!(paragraph.hit_test(Offset::new(rtl_end - 1.0, 5.0)), 3);
+    // Near the left edge of bet is its logical end.
+    assert_eq!(paragraph.hit_test(Offset::new(rtl_start + 1.0, 5.0)), 7);
+    assert_eq!(paragraph.hit_test(Offset::new(rtl_start - 1.0, 5.0)), 3);
+}
+
+#[test]
+fn selection_across_bidi_runs() {
+    let paragraph = layout("aa אב aa", f32::INFINITY);
+    let rtl_start = 2.0 * A + SPACE;
+    let rtl_end = rtl_start + BET + ALEF;
+
+    // "a ", then alef which is drawn at the right end of the run.
+    let rects = paragraph.selection_rects(1..5);
+    assert_eq!(rects.len(), 2);
+    assert_near(rects[0].min.x, A, 1e-3);
+    assert_near(rects[0].max.x, rtl_start, 1e-3);
+    assert_near(rects[1].min.x, rtl_start + BET, 1e-3);
+    assert_near(rects[1].max.x, rtl_end, 1e-3);
+
+    // The whole text is a single rectangle.
+    let rects = paragraph.selection_rects(0..paragraph.text.len());
+    assert_eq!(rects.len(), 1);
+    assert_near(rects[0].min.x, 0.0, 1e-3);
+    assert_near(rects[0].max.x, paragraph.width, 1e-3);
+}
@@ -0,0 +1,156 @@
+//! Scissor and stencil clips rendered with the software backend.
+
+use reui::{wgpu, Color, FillRule, Offset, Path, Pixels, Rect, Rounding, SoftwareRenderer};
+
+const SIZE: u32 = 100;
+
+fn render(draw: impl FnOnce(&mut reui::Canvas<'_, reui::Image>)) -> Pixels {
+    SoftwareRenderer::new().render_pixels(SIZE, SIZE, wgpu::Color::WHITE, draw)
+}
+
+/// Whether the pixel is covered by the black fill.
+fn filled(pixels: &Pixels, x: u32, y: u32) -> bool {
+    let [r, g, b, _] = pixels.get(x, y).unwrap();
+    assert!(!(8..=247).contains(&r), "({x}, {y}) is antialiased: {r}");
+    assert_eq!((r, r), (g, b));
+    r < 128
+}
+
+fn fill_all(ctx: &mut reui::Canvas<'_, reui::Image>) {
+    ctx.fill_rect(Rect::from_ltwh(0.0, 0.0, 100.0, 100.0), Color::BLACK);
+}
+
+#[test]
+fn clip_rect() {
+    let pixels = render(|ctx| {
+        ctx.clip_rect(Rect::from_ltrb(20.0, 30.0, 60.0, 70.0));
+        fill_all(ctx);
+    });
+
+    assert!(filled(&pixels, 20, 30));
+    assert!(filled(&pixels, 59, 69));
+    assert!(!filled(&pixels, 19, 50));
+    assert!(!filled(&pixels, 60, 50));
+    assert!(!filled(&pixels, 40, 29));
+    assert!(!filled(&pixels, 40, 70));
+}
+
+#[test]
+fn nested_clip_rects_intersect() {
+    let pixels = render(|ctx| {
+        ctx.clip_rect(Rect::from_ltrb(10.0, 10.0, 60.0, 60.0));
+        ctx.save();
+        ctx.push_translate(20.0, 20.0);
+        ctx.clip_rect(Rect::from_ltrb(10.0, 10.0, 60.0, 60.0));
+        fill_all(ctx);
+        ctx.restore();
+    });
+
+    assert!(filled(&pixels, 30, 30));
+    assert!(filled(&pixels, 59, 59));
+    assert!(!filled(&pixels, 29, 45));
+    assert!(!filled(&pixels, 15, 15));
+    assert!(!filled(&pixels, 60, 45));
+    assert!(!filled(&pixels, 75, 75));
+}
+
+#[test]
+fn restore_pops_clips() {
+    let pixels = render(|ctx| {
+        ctx.save();
+        ctx.clip_rect(Rect::from_ltrb(0.0, 0.0, 50.0, 50.0));
+        let mut path = Path::new();
+        path.circle(Offset::new(25.0, 25.0), 20.0);
+        ctx.clip_path(&path, FillRule::NonZero);
+        ctx.restore();
+
+        fill_all(ctx);
+    });
+
+    assert!(filled(&pixels, 2, 2));
+    assert!(filled(&pixels, 90, 90));
+}
+
+#[test]
+fn clip_rrect_cuts_corners() {
+    let pixels = render(|ctx| {
+        let rect = Rect::from_ltrb(20.0, 20.0, 80.0, 80.0);
+        ctx.clip_rrect(rect, Rounding::same(20.0));
+        fill_all(ctx);
+    });
+
+    assert!(filled(&pixels, 50, 50));
+    assert!(filled(&pixels, 50, 21));
+    assert!(filled(&pixels, 21, 50));
+    assert!(!filled(&pixels, 21, 21));
+    assert!(!filled(&pixels, 78, 78));
+    assert!(!filled(&pixels, 10, 50));
+}
+
+#[test]
+fn clip_path_fill_rules() {
+    let rings = |rule| {
+        render(|ctx| {
+            let mut path = Path::new();
+            path.circle(Offset::new(50.0, 50.0), 40.0);
+            path.circle(Offset::new(50.0, 50.0), 20.0);
+            ctx.clip_path(&path, rule);
+            fill_all(ctx);
+        })
+    };
+
+    // Both circles are solid, so only the even-odd rule leaves the hole.
+    let non_zero = rings(FillRule::NonZero);
+    assert!(filled(&non_zero, 50, 50));
+    assert!(filled(&non_zero, 50, 20));
+    assert!(!filled(&non_zero, 5, 5));
+
+    let even_odd = rings(FillRule::EvenOdd);
+    assert!(!filled(&even_odd, 50, 50));
+    assert!(filled(&even_odd, 50, 20));
+    assert!(!filled(&even_odd, 5, 5));
+}
+
+#[test]
+fn nested_clip_paths_intersect() {
+    let pixels = render(|ctx| {
+        let mut left = Path::new();
+        left.circle(Offset::new(40.0, 50.0), 30.0);
+        let mut right = Path::new();
+        right.circle(Offset::new(60.0, 50.0), 30.0);
+
+        ctx.clip_path(&left, FillRule::NonZero);
+        ctx.save();
+        ctx.clip_path(&right, FillRule::NonZero);
+        fill_all(ctx);
+        ctx.restore();
+
+        // Back to the first clip only.
+        ctx.fill_rect(Rect::from_ltwh(0.0, 60.0, 100.0, 40.0), Color::BLACK);
+    });
+
+    assert!(filled(&pixels, 50, 50));
+    assert!(!filled(&pixels, 15, 50));
+    assert!(!filled(&pixels, 85, 50));
+    // The bottom strip is clipped by the left circle only.
+    assert!(filled(&pixels, 20, 70));
+    assert!(!filled(&pixels, 20, 40));
+    assert!(!filled(&pixels, 75, 62));
+}
+
+#[test]
+fn clip_path_intersects_scissor() {
+    let pixels = render(|ctx| {
+        ctx.clip_rect(Rect::from_ltrb(0.0, 0.0, 50.0, 100.0));
+        let mut path = Path::new();
+        path.rect(Rect::from_ltrb(30.0, 30.0, 70.0, 70.0));
+        ctx.clip_path(&path, FillRule::NonZero);
+        fill_all(ctx);
+    });
+
+    assert!(filled(&pixels, 30, 30));
+    assert!(filled(&pixels, 49, 69));
+    assert!(!filled(&pixels, 50, 50));
+    assert!(!filled(&pixels, 20, 50));
+    assert!(!filled(&pixels, 40, 75));
+}
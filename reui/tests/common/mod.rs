@@ -0,0 +1,92 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::fmt::Debug;
+
+use reui::{Command, FontId, FontStore, Offset, Path};
+
+/// Font size giving a scale of 0.01 for the 2048 units per em of the bundled DejaVu Sans.
+pub const SIZE: f32 = 20.48;
+
+// Advances at `SIZE`.
+pub const A: f32 = 12.55;
+pub const SPACE: f32 = 6.51;
+pub const ALEF: f32 = 13.69;
+pub const BET: f32 = 11.84;
+
+/// Loads the bundled DejaVu Sans font.
+pub fn font() -> (FontStore, FontId) {
+    let mut fonts = FontStore::new();
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts/DejaVuSans.ttf");
+    let font = fonts.load_file(path).unwrap();
+    (fonts, font)
+}
+
+pub fn p(x: f32, y: f32) -> Offset {
+    Offset::new(x, y)
+}
+
+/// Points of a sub-path without curves.
+#[derive(Debug, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Offset>,
+    pub closed: bool,
+    pub hole: bool,
+}
+
+/// Contours of a path, panics if it has curves.
+pub fn contours(path: &Path) -> Vec<Contour> {
+    let mut contours: Vec<Contour> = Vec::new();
+    for command in path {
+        match command {
+            Command::MoveTo(p) => contours.push(Contour {
+                points: vec![p],
+                closed: false,
+                hole: false,
+            }),
+            Command::LineTo(p) => contours.last_mut().unwrap().points.push(p),
+            Command::Close => contours.last_mut().unwrap().closed = true,
+            Command::Hole => contours.last_mut().unwrap().hole = true,
+            Command::Solid => (),
+            _ => panic!("path has curves"),
+        }
+    }
+    contours
+}
+
+/// Area of a polygon, positive if it winds clockwise with y pointing down.
+pub fn signed_area(points: &[Offset]) -> f32 {
+    let mut area = 0.0;
+    let mut prev = points[points.len() - 1];
+    for &p in points {
+        area += prev.cross(p);
+        prev = p;
+    }
+    area * 0.5
+}
+
+/// Values compared by their distance.
+pub trait Near: Copy + Debug {
+    fn distance(self, other: Self) -> f32;
+}
+
+impl Near for f32 {
+    fn distance(self, other: Self) -> f32 {
+        (self - other).abs()
+    }
+}
+
+impl Near for Offset {
+    fn distance(self, other: Self) -> f32 {
+        (self - other).magnitude()
+    }
+}
+
+#[track_caller]
+pub fn assert_near<T: Near>(actual: T, expected: T, tolerance: f32) {
+    assert!(
+        actual.distance(expected) <= tolerance,
+        "{actual:?} != {expected:?}"
+    );
+}
@@ -0,0 +1,87 @@
+//! Conics and the curves built from them, flattened onto the exact curves.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+mod common;
+
+use common::{assert_near, contours, p};
+use reui::{Offset, Path, PathMeasure, Rect, Rounding};
+
+const TOLERANCE: f32 = 0.25;
+
+/// Flattened points of all contours, closed ones end with their first point.
+fn flatten(path: &Path) -> Vec<Offset> {
+    let measure = PathMeasure::new(path, TOLERANCE);
+    measure
+        .contours()
+        .iter()
+        .flat_map(|contour| contours(&contour.extract_segment(0.0, contour.length())))
+        .flat_map(|contour| contour.points)
+        .collect()
+}
+
+/// Distance of `p` from the ellipse, measured along its normalized radius.
+fn ellipse_error(p: Offset, center: Offset, radius: Offset) -> f32 {
+    let d = (p - center).scale(radius.x.recip(), radius.y.recip());
+    (d.magnitude() - 1.0).abs() * radius.x.max(radius.y)
+}
+
+#[test]
+fn quarter_conic_flattens_onto_circle() {
+    let radius = 100.0;
+    let mut path = Path::new();
+    path.move_to(p(radius, 0.0));
+    path.conic_to(p(radius, radius), p(0.0, radius), FRAC_1_SQRT_2);
+
+    let points = flatten(&path);
+    assert!(points.len() > 4, "{}", points.len());
+    assert_near(*points.last().unwrap(), p(0.0, radius), 1e-3);
+    for pair in points.windows(2) {
+        // Vertices are on the circle and chords stay within the tolerance.
+        assert_near(pair[1].magnitude(), radius, 1e-3);
+        let mid = (pair[0] + pair[1]) * 0.5;
+        assert!(radius - mid.magnitude() <= TOLERANCE, "{pair:?}");
+    }
+}
+
+#[test]
+fn circle_and_ellipse_vertices_lie_on_curve() {
+    let center = p(30.0, -20.0);
+    for radius in [p(50.0, 50.0), p(80.0, 15.0)] {
+        let mut path = Path::new();
+        path.oval(Rect::new(center - radius, center + radius));
+
+        let points = flatten(&path);
+        assert!(points.len() > 16, "{}", points.len());
+        for p in points {
+            assert!(ellipse_error(p, center, radius) < 1e-3, "{p:?}");
+        }
+    }
+}
+
+#[test]
+fn rrect_vertices_lie_on_edges_and_corners() {
+    let rect = Rect::from_ltrb(0.0, 0.0, 100.0, 60.0);
+    let rounding = Rounding::new(10.0, 20.0, 5.0, 30.0);
+    let mut path = Path::new();
+    path.rrect(rect, rounding);
+
+    let corners = [
+        (p(10.0, 10.0), rounding.nw),
+        (p(80.0, 20.0), rounding.ne),
+        (p(5.0, 55.0), rounding.sw),
+        (p(70.0, 30.0), rounding.se),
+    ];
+    let points = flatten(&path);
+    assert!(points.len() > 12, "{}", points.len());
+    for p in points {
+        let on_edge = [p.x, p.y, 100.0 - p.x, 60.0 - p.y]
+            .iter()
+            .any(|d| d.abs() < 1e-3);
+        let on_corner = corners.iter().any(|&(center, radius)| {
+            let r = Offset::new(radius, radius);
+            ellipse_error(p, center, r) < 1e-3
+        });
+        assert!(on_edge || on_corner, "{p:?}");
+    }
+}
@@ -0,0 +1,172 @@
+//! Lengths, positions and segments along flattened paths.
+
+use std::f32::consts::TAU;
+
+mod common;
+
+use common::{assert_near, contours, p};
+use reui::{Offset, Path, PathMeasure, Rect};
+
+fn measure_svg(data: &str) -> PathMeasure {
+    PathMeasure::new(&Path::from_svg_data(data).unwrap(), 0.25)
+}
+
+/// Points of the contours of a flattened path, all of them open.
+#[track_caller]
+fn lines(path: &Path) -> Vec<Vec<Offset>> {
+    contours(path)
+        .into_iter()
+        .map(|contour| {
+            assert!(!contour.closed, "{contour:?}");
+            contour.points
+        })
+        .collect()
+}
+
+#[test]
+fn length_of_contours() {
+    let measure = measure_svg("M0,0 L3,4 L3,10");
+    assert_eq!(measure.contours().len(), 1);
+    assert!(!measure.contours()[0].is_closed());
+    assert_near(measure.length(), 11.0, 1e-3);
+
+    // The closing segment counts, contours add up.
+    let measure = measure_svg("M0,0 H10 V5 H0 Z M20,0 h2");
+    assert_eq!(measure.contours().len(), 2);
+    assert!(measure.contours()[0].is_closed());
+    assert_near(measure.contours()[0].length(), 30.0, 1e-3);
+    assert_near(measure.length(), 32.0, 1e-3);
+
+    // Contours without length are skipped.
+    let measure = measure_svg("M0,0 M5,5 L5,5 M0,0 h1");
+    assert_eq!(measure.contours().len(), 1);
+    assert_near(measure.length(), 1.0, 1e-3);
+}
+
+#[test]
+fn length_of_empty_path_is_positive_zero() {
+    let length = PathMeasure::new(&Path::new(), 0.25).length();
+    assert_eq!(length, 0.0);
+    assert!(length.is_sign_positive());
+}
+
+#[test]
+fn length_of_flattened_circle() {
+    let mut path = Path::new();
+    path.circle(p(0.0, 0.0), 100.0);
+    let length = PathMeasure::new(&path, 0.25).length();
+    // Chords are shorter than the arc, by less than the tolerance allows.
+    assert!(length < TAU * 100.0);
+    assert!(length > TAU * 100.0 * 0.999, "{length}");
+}
+
+#[test]
+fn pos_tan_along_contour() {
+    let measure = measure_svg("M0,0 H10 V5 H0 Z");
+    let contour = &measure.contours()[0];
+
+    let cases = [
+        (0.0, p(0.0, 0.0), p(1.0, 0.0)),
+        (4.0, p(4.0, 0.0), p(1.0, 0.0)),
+        (12.0, p(10.0, 2.0), p(0.0, 1.0)),
+        (17.5, p(7.5, 5.0), p(-1.0, 0.0)),
+        // On the closing segment.
+        (28.0, p(0.0, 2.0), p(0.0, -1.0)),
+        // Clamped to the ends.
+        (-5.0, p(0.0, 0.0), p(1.0, 0.0)),
+        (40.0, p(0.0, 0.0), p(0.0, -1.0)),
+    ];
+    for (distance, pos, tan) in cases {
+        let (actual_pos, actual_tan) = contour.pos_tan(distance);
+        assert_near(actual_pos, pos, 1e-4);
+        assert_near(actual_tan, tan, 1e-4);
+    }
+
+    // At a vertex the position is exact.
+    assert_eq!(contour.pos_tan(10.0).0, p(10.0, 0.0));
+}
+
+#[test]
+fn extract_across_vertices() {
+    let measure = measure_svg("M0,0 H10 V5 H0");
+    let contour = &measure.contours()[0];
+
+    assert_eq!(
+        lines(&contour.extract_segment(5.0, 17.0)),
+        [vec![p(5.0, 0.0), p(10.0, 0.0), p(10.0, 5.0), p(8.0, 5.0)]]
+    );
+
+    // Within one segment.
+    assert_eq!(
+        lines(&contour.extract_segment(1.0, 2.0)),
+        [vec![p(1.0, 0.0), p(2.0, 0.0)]]
+    );
+
+    // Clamped to the contour, and empty unless start is before end.
+    assert_eq!(
+        lines(&contour.extract_segment(-5.0, 3.0)),
+        [vec![p(0.0, 0.0), p(3.0, 0.0)]]
+    );
+    assert!(contour.extract_segment(3.0, 3.0).is_empty());
+    assert!(contour.extract_segment(30.0, 40.0).is_empty());
+}
+
+#[test]
+fn extract_from_vertex_has_no_duplicate() {
+    let measure = measure_svg("M0,0 H10 V5 H0");
+    let contour = &measure.contours()[0];
+    assert_eq!(
+        lines(&contour.extract_segment(10.0, 12.0)),
+        [vec![p(10.0, 0.0), p(10.0, 2.0)]]
+    );
+    // Ending on a vertex.
+    assert_eq!(
+        lines(&contour.extract_segment(10.0, 15.0)),
+        [vec![p(10.0, 0.0), p(10.0, 5.0)]]
+    );
+
+    // A repeated first point.
+    let measure = measure_svg("M10,0 L10,0 L10,5");
+    assert_eq!(
+        lines(&measure.extract_segment(0.0, 3.0)),
+        [vec![p(10.0, 0.0), p(10.0, 3.0)]]
+    );
+}
+
+#[test]
+fn extract_whole_closed_contour_stays_closed() {
+    let mut path = Path::new();
+    path.rect(Rect::from_ltrb(0.0, 0.0, 10.0, 5.0));
+    let measure = PathMeasure::new(&path, 0.25);
+
+    let whole = contours(&measure.extract_segment(0.0, measure.length()));
+    assert_eq!(whole.len(), 1);
+    assert_eq!(whole[0].points.len(), 4);
+    assert_eq!(whole[0].points[0], p(0.0, 0.0));
+    assert!(whole[0].closed);
+
+    // A part of it stays open and follows the closing segment.
+    let segment = measure.extract_segment(25.0, 30.0);
+    assert_eq!(lines(&segment), [vec![p(5.0, 0.0), p(0.0, 0.0)]]);
+}
+
+#[test]
+fn extract_across_contours() {
+    let measure = measure_svg("M0,0 H10 M0,5 H10 M0,10 H10");
+
+    // Distances run along all contours one after another.
+    assert_eq!(
+        lines(&measure.extract_segment(8.0, 23.0)),
+        [
+            vec![p(8.0, 0.0), p(10.0, 0.0)],
+            vec![p(0.0, 5.0), p(10.0, 5.0)],
+            vec![p(0.0, 10.0), p(3.0, 10.0)],
+        ]
+    );
+
+    // Starting at the end of a contour does not add an empty piece of it.
+    assert_eq!(
+        lines(&measure.extract_segment(10.0, 12.0)),
+        [vec![p(0.0, 5.0), p(2.0, 5.0)]]
+    );
+}
@@ -0,0 +1,116 @@
+//! Boolean operations on polygons.
+
+mod common;
+
+use common::{contours, signed_area};
+use reui::{Offset, Path, PathOp, Rect, Solidity};
+
+fn square(l: f32, t: f32, r: f32, b: f32) -> Path {
+    let mut path = Path::new();
+    path.rect(Rect::from_ltrb(l, t, r, b));
+    path
+}
+
+/// Area of solid contours minus holes, with holes wound against solids.
+#[track_caller]
+fn area(path: &Path) -> f32 {
+    let contours = contours(path);
+    let solid_sign = contours
+        .iter()
+        .find(|contour| !contour.hole)
+        .map_or(1.0, |contour| signed_area(&contour.points).signum());
+    let mut total = 0.0;
+    for contour in &contours {
+        let area = signed_area(&contour.points) * solid_sign;
+        assert_eq!(area < 0.0, contour.hole, "{contour:?}");
+        total += area;
+    }
+    total
+}
+
+/// Number of contours and their vertices.
+fn shape(path: &Path) -> Vec<usize> {
+    let mut counts: Vec<usize> = contours(path)
+        .iter()
+        .map(|contour| contour.points.len())
+        .collect();
+    counts.sort_unstable();
+    counts
+}
+
+#[track_caller]
+fn assert_op(a: &Path, b: &Path, op: PathOp, expected_area: f32, expected_shape: &[usize]) {
+    let result = Path::op(a, b, op);
+    let actual = area(&result);
+    assert!((actual - expected_area).abs() < 1e-3, "{op:?}: {actual}");
+    assert_eq!(shape(&result), expected_shape, "{op:?}");
+}
+
+#[test]
+fn overlapping_squares() {
+    let a = square(0.0, 0.0, 10.0, 10.0);
+    let b = square(5.0, 5.0, 15.0, 15.0);
+
+    assert_op(&a, &b, PathOp::Union, 175.0, &[8]);
+    assert_op(&a, &b, PathOp::Intersect, 25.0, &[4]);
+    assert_op(&a, &b, PathOp::Difference, 75.0, &[6]);
+    assert_op(&a, &b, PathOp::ReverseDifference, 75.0, &[6]);
+    // The outline of the union with the intersection as a hole touching it at two corners.
+    assert_op(&a, &b, PathOp::Xor, 150.0, &[4, 8]);
+
+    let intersection = contours(&Path::op(&a, &b, PathOp::Intersect));
+    let mut points = intersection[0].points.clone();
+    points.sort_by(|p, q| p.x.total_cmp(&q.x).then(p.y.total_cmp(&q.y)));
+    let expected = [(5.0, 5.0), (5.0, 10.0), (10.0, 5.0), (10.0, 10.0)];
+    assert_eq!(points, expected.map(|(x, y)| Offset::new(x, y)));
+}
+
+#[test]
+fn disjoint_squares() {
+    let a = square(0.0, 0.0, 10.0, 10.0);
+    let b = square(20.0, 0.0, 25.0, 10.0);
+
+    assert_op(&a, &b, PathOp::Union, 150.0, &[4, 4]);
+    assert_op(&a, &b, PathOp::Intersect, 0.0, &[]);
+    assert_op(&a, &b, PathOp::Difference, 100.0, &[4]);
+    assert_op(&a, &b, PathOp::ReverseDifference, 50.0, &[4]);
+    assert_op(&a, &b, PathOp::Xor, 150.0, &[4, 4]);
+}
+
+#[test]
+fn squares_sharing_an_edge() {
+    let a = square(0.0, 0.0, 10.0, 10.0);
+    let b = square(10.0, 0.0, 20.0, 10.0);
+
+    // The shared edge is dropped.
+    assert_op(&a, &b, PathOp::Union, 200.0, &[4]);
+    assert_op(&a, &b, PathOp::Intersect, 0.0, &[]);
+    assert_op(&a, &b, PathOp::Difference, 100.0, &[4]);
+}
+
+#[test]
+fn nested_contours() {
+    let outer = square(0.0, 0.0, 10.0, 10.0);
+    let inner = square(3.0, 3.0, 7.0, 7.0);
+
+    assert_op(&outer, &inner, PathOp::Union, 100.0, &[4]);
+    assert_op(&outer, &inner, PathOp::Intersect, 16.0, &[4]);
+    // A square with a square hole.
+    assert_op(&outer, &inner, PathOp::Difference, 84.0, &[4, 4]);
+    assert_op(&outer, &inner, PathOp::Xor, 84.0, &[4, 4]);
+    assert_op(&outer, &inner, PathOp::ReverseDifference, 0.0, &[]);
+}
+
+#[test]
+fn input_with_hole() {
+    let mut ring = square(0.0, 0.0, 10.0, 10.0);
+    ring.rect(Rect::from_ltrb(2.0, 2.0, 8.0, 8.0));
+    ring.solidity(Solidity::Hole);
+    let bar = square(4.0, -5.0, 6.0, 15.0);
+
+    // The ring covers 64 and the bar 40, overlapping in 8.
+    assert_op(&ring, &bar, PathOp::Union, 96.0, &[4, 4, 12]);
+    assert_op(&ring, &bar, PathOp::Intersect, 8.0, &[4, 4]);
+    assert_op(&ring, &bar, PathOp::Difference, 56.0, &[8, 8]);
+    assert_op(&ring, &bar, PathOp::ReverseDifference, 32.0, &[4, 4, 4]);
+}
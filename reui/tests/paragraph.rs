@@ -0,0 +1,187 @@
+//! Paragraph layout with the bundled DejaVu Sans font.
+
+mod common;
+
+use common::{assert_near, font, A, ALEF, BET, SIZE, SPACE};
+use reui::{Paragraph, ParagraphStyle, TextAlign, TextDirection};
+
+fn layout(text: &str, style: impl FnOnce(ParagraphStyle) -> ParagraphStyle) -> Paragraph {
+    let (fonts, font) = font();
+    Paragraph::new(&fonts, text, &style(ParagraphStyle::new(font, SIZE)))
+}
+
+/// Glyph x positions and clusters in visual order.
+fn glyphs(paragraph: &Paragraph, line: usize) -> Vec<(f32, usize)> {
+    let range = paragraph.lines[line].glyphs.clone();
+    paragraph.glyphs[range]
+        .iter()
+        .map(|glyph| (glyph.position.x, glyph.cluster))
+        .collect()
+}
+
+#[track_caller]
+fn assert_glyphs(actual: &[(f32, usize)], expected: &[(f32, usize)]) {
+    assert_eq!(actual.len(), expected.len(), "{actual:?}");
+    for (&(x, cluster), &(expected_x, expected_cluster)) in actual.iter().zip(expected) {
+        assert!((x - expected_x).abs() < 1e-3, "{actual:?}");
+        assert_eq!(cluster, expected_cluster, "{actual:?}");
+    }
+}
+
+#[test]
+fn ltr_glyph_positions() {
+    let paragraph = layout("aa a", |style| style);
+
+    assert_eq!(paragraph.lines.len(), 1);
+    assert_glyphs(
+        &glyphs(&paragraph, 0),
+        &[(0.0, 0), (A, 1), (2.0 * A, 2), (2.0 * A + SPACE, 3)],
+    );
+    assert_near(paragraph.width, 3.0 * A + SPACE, 1e-3);
+}
+
+#[test]
+fn bidi_reorders_rtl_run_in_ltr_paragraph() {
+    // Alef at bytes 3..5 and bet at 5..7.
+    let paragraph = layout("aa אב aa", |style| style);
+
+    assert_eq!(paragraph.lines.len(), 1);
+    assert!(!paragraph.lines[0].rtl);
+    let rtl_start = 2.0 * A + SPACE;
+    assert_glyphs(
+        &glyphs(&paragraph, 0),
+        &[
+            (0.0, 0),
+            (A, 1),
+            (2.0 * A, 2),
+            (rtl_start, 5),
+            (rtl_start + BET, 3),
+            (rtl_start + BET + ALEF, 7),
+            (rtl_start + BET + ALEF + SPACE, 8),
+            (rtl_start + BET + ALEF + SPACE + A, 9),
+        ],
+    );
+
+    let rtl: Vec<bool> = paragraph.glyphs.iter().map(|glyph| glyph.rtl).collect();
+    assert_eq!(rtl, [false, false, false, true, true, false, false, false]);
+}
+
+#[test]
+fn bidi_reorders_ltr_run_in_rtl_paragraph() {
+    let paragraph = layout("אב aa", |style| style);
+
+    assert!(paragraph.lines[0].rtl);
+    let ltr_end = 2.0 * A + SPACE;
+    assert_glyphs(
+        &glyphs(&paragraph, 0),
+        &[
+            (0.0, 5),
+            (A, 6),
+            (2.0 * A, 4),
+            (ltr_end, 2),
+            (ltr_end + BET, 0),
+        ],
+    );
+}
+
+#[test]
+fn explicit_direction_overrides_detection() {
+    let paragraph = layout("aa", |style| style.direction(TextDirection::Rtl));
+    assert!(paragraph.lines[0].rtl);
+
+    let paragraph = layout("אב", |style| style.direction(TextDirection::Ltr));
+    assert!(!paragraph.lines[0].rtl);
+    // A single right-to-left run is still reversed.
+    assert_glyphs(&glyphs(&paragraph, 0), &[(0.0, 2), (BET, 0)]);
+}
+
+#[test]
+fn wraps_at_max_width() {
+    let text = "aa aa aa";
+    let ranges = |paragraph: &Paragraph| -> Vec<_> {
+        paragraph
+            .lines
+            .iter()
+            .map(|line| line.range.clone())
+            .collect()
+    };
+
+    let unwrapped = layout(text, |style| style);
+    assert_eq!(unwrapped.lines.len(), 1);
+
+    // Two words take 56.71 without the trailing space.
+    let paragraph = layout(text, |style| style.max_width(57.0));
+    assert_eq!(ranges(&paragraph), [0..6, 6..8]);
+
+    let paragraph = layout(text, |style| style.max_width(56.0));
+    assert_eq!(ranges(&paragraph), [0..3, 3..6, 6..8]);
+
+    // Every line starts at the left edge below the previous one.
+    for (index, line) in paragraph.lines.iter().enumerate() {
+        assert_glyphs(
+            &glyphs(&paragraph, index),
+            &[(0.0, line.range.start), (A, line.range.start + 1)],
+        );
+        assert_near(line.width, 2.0 * A, 1e-3);
+        for glyph in &paragraph.glyphs[line.glyphs.clone()] {
+            assert_eq!(glyph.position.y, line.baseline);
+        }
+    }
+    let height = paragraph.lines[0].height;
+    assert_near(paragraph.lines[2].top, 2.0 * height, 1e-3);
+    assert_near(paragraph.height, 3.0 * height, 1e-3);
+}
+
+#[test]
+fn breaks_at_newlines() {
+    let paragraph = layout("a\n\na", |style| style);
+    assert_eq!(paragraph.lines.len(), 3);
+    assert_eq!(paragraph.lines[1].glyphs.len(), 0);
+
+    let paragraph = layout("a\n", |style| style);
+    assert_eq!(paragraph.lines.len(), 2);
+    assert_eq!(paragraph.lines[1].range, 2..2);
+}
+
+#[test]
+fn align_offsets() {
+    let width = 2.0 * A;
+    let left = |text: &str, align, direction| {
+        let paragraph = layout(text, |style| {
+            style.max_width(100.0).align(align).direction(direction)
+        });
+        let line = &paragraph.lines[0];
+        assert_near(line.width, width, 1e-3);
+        assert_near(paragraph.glyphs[0].position.x, line.left, 1e-3);
+        line.left
+    };
+
+    let cases = [
+        (TextAlign::Left, TextDirection::Ltr, 0.0),
+        (TextAlign::Right, TextDirection::Ltr, 100.0 - width),
+        (TextAlign::Center, TextDirection::Ltr, (100.0 - width) * 0.5),
+        (TextAlign::Start, TextDirection::Ltr, 0.0),
+        (TextAlign::End, TextDirection::Ltr, 100.0 - width),
+        (TextAlign::Start, TextDirection::Rtl, 100.0 - width),
+        (TextAlign::End, TextDirection::Rtl, 0.0),
+        (TextAlign::Left, TextDirection::Rtl, 0.0),
+    ];
+    for (align, direction, expected) in cases {
+        let actual = left("aa", align, direction);
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{align:?} {direction:?}: {actual}"
+        );
+    }
+}
+
+#[test]
+fn align_ignores_trailing_whitespace() {
+    let paragraph = layout("aa aa", |style| {
+        style.max_width(40.0).align(TextAlign::Right)
+    });
+    assert_eq!(paragraph.lines.len(), 2);
+    for line in &paragraph.lines {
+        assert_near(line.left, 40.0 - 2.0 * A, 1e-3);
+    }
+}
@@ -0,0 +1,227 @@
+//! Arcs and relative commands of the path builder.
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+mod common;
+
+use common::{assert_near, p};
+use reui::{Command, Offset, Path, Rect};
+
+/// End points of all segments.
+fn ends(path: &Path) -> Vec<Offset> {
+    path.into_iter()
+        .filter_map(|command| match command {
+            Command::MoveTo(p) | Command::LineTo(p) => Some(p),
+            Command::QuadTo(_, p) | Command::ConicTo(_, p, _) | Command::CubicTo(_, _, p) => {
+                Some(p)
+            }
+            Command::Solid | Command::Hole | Command::Close => None,
+        })
+        .collect()
+}
+
+/// Conic segments as control point, end point and weight.
+fn conics(path: &Path) -> Vec<(Offset, Offset, f32)> {
+    path.into_iter()
+        .filter_map(|command| match command {
+            Command::ConicTo(p1, p2, weight) => Some((p1, p2, weight)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks that both ends and the curve midpoint of every conic lie on the ellipse.
+#[track_caller]
+fn assert_on_ellipse(path: &Path, center: Offset, radii: Offset) {
+    let on_ellipse = |p: Offset| {
+        let d = (p - center).scale(1.0 / radii.x, 1.0 / radii.y);
+        assert_near(d.magnitude(), 1.0, 1e-4);
+    };
+    let mut from = None;
+    for command in path {
+        match command {
+            Command::ConicTo(p1, p2, weight) => {
+                let p0 = from.unwrap();
+                on_ellipse(p0);
+                on_ellipse((p0 + p1 * 2.0 * weight + p2) / (2.0 + 2.0 * weight));
+                on_ellipse(p2);
+                from = Some(p2);
+            }
+            Command::MoveTo(p) | Command::LineTo(p) => from = Some(p),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn arc_to_point_ends_exactly_at_target() {
+    let from = p(1.3, 2.7);
+    let to = p(17.1, -3.9);
+    for (large_arc, clockwise) in [(false, false), (false, true), (true, false), (true, true)] {
+        let mut path = Path::new();
+        path.move_to(from);
+        path.arc_to_point(to, p(7.0, 11.0), 0.3, large_arc, clockwise);
+
+        assert!(!conics(&path).is_empty());
+        assert_eq!(path.current_point(), Some(to));
+    }
+}
+
+#[test]
+fn arc_to_point_picks_the_arc() {
+    // Half circles around (5, 0), above for counter-clockwise and below for clockwise.
+    let mut path = Path::new();
+    path.move_to(p(0.0, 0.0));
+    path.arc_to_point(p(10.0, 0.0), p(5.0, 5.0), 0.0, false, true);
+    assert_on_ellipse(&path, p(5.0, 0.0), p(5.0, 5.0));
+    assert_eq!(conics(&path).len(), 2);
+    assert_near(ends(&path)[1], p(5.0, -5.0), 1e-4);
+
+    let mut path = Path::new();
+    path.move_to(p(0.0, 0.0));
+    path.arc_to_point(p(10.0, 0.0), p(5.0, 5.0), 0.0, false, false);
+    assert_near(ends(&path)[1], p(5.0, 5.0), 1e-4);
+
+    // A small arc of a large circle and the large arc of the same circle.
+    let center = p(5.0, 5.0 - 75f32.sqrt());
+    let mut path = Path::new();
+    path.move_to(p(0.0, 5.0));
+    path.arc_to_point(p(10.0, 5.0), p(10.0, 10.0), 0.0, false, false);
+    assert_on_ellipse(&path, center, p(10.0, 10.0));
+    assert_eq!(conics(&path).len(), 1);
+
+    let mut path = Path::new();
+    path.move_to(p(0.0, 5.0));
+    path.arc_to_point(p(10.0, 5.0), p(10.0, 10.0), 0.0, true, true);
+    assert_on_ellipse(&path, center, p(10.0, 10.0));
+    assert_eq!(conics(&path).len(), 4);
+}
+
+#[test]
+fn arc_to_point_scales_radii_and_degenerates() {
+    // Radii too small to reach the target are scaled up to a half ellipse.
+    let mut path = Path::new();
+    path.move_to(p(0.0, 0.0));
+    path.arc_to_point(p(10.0, 0.0), p(1.0, 2.0), 0.0, false, true);
+    assert_on_ellipse(&path, p(5.0, 0.0), p(5.0, 10.0));
+    assert_eq!(path.current_point(), Some(p(10.0, 0.0)));
+
+    // Zero radii produce a straight line.
+    let mut path = Path::new();
+    path.move_to(p(0.0, 0.0));
+    path.arc_to_point(p(10.0, 0.0), p(0.0, 5.0), 0.0, false, true);
+    assert!(conics(&path).is_empty());
+    assert_eq!(ends(&path), [p(0.0, 0.0), p(10.0, 0.0)]);
+
+    // An arc to the current point is skipped.
+    let mut path = Path::new();
+    path.move_to(p(3.0, 4.0));
+    path.arc_to_point(p(3.0, 4.0), p(5.0, 5.0), 0.0, false, true);
+    assert_eq!(ends(&path), [p(3.0, 4.0)]);
+}
+
+#[test]
+fn relative_arc_to_point() {
+    let mut path = Path::new();
+    path.move_to(p(2.5, 1.5));
+    path.relative_arc_to_point(p(10.0, 0.0), p(5.0, 5.0), 0.0, false, true);
+    assert_on_ellipse(&path, p(7.5, 1.5), p(5.0, 5.0));
+    assert_eq!(path.current_point(), Some(p(12.5, 1.5)));
+}
+
+#[test]
+fn arc_to_rounds_corner() {
+    let mut path = Path::new();
+    path.move_to(p(0.0, 0.0));
+    path.arc_to(p(10.0, 0.0), p(10.0, 10.0), 5.0);
+
+    // A line to the first tangent point and a quarter circle to the second.
+    let ends = ends(&path);
+    assert_near(ends[1], p(5.0, 0.0), 1e-4);
+    assert_on_ellipse(&path, p(5.0, 5.0), p(5.0, 5.0));
+    assert_near(path.current_point().unwrap(), p(10.0, 5.0), 1e-4);
+
+    // The other turn direction.
+    let mut path = Path::new();
+    path.move_to(p(0.0, 0.0));
+    path.arc_to(p(10.0, 0.0), p(10.0, -10.0), 5.0);
+    assert_on_ellipse(&path, p(5.0, -5.0), p(5.0, 5.0));
+    assert_near(path.current_point().unwrap(), p(10.0, -5.0), 1e-4);
+}
+
+#[test]
+fn arc_to_degenerates_to_line() {
+    // Collinear points and a zero radius give a line to the corner.
+    for (p2, radius) in [(p(20.0, 0.0), 5.0), (p(10.0, 10.0), 0.0)] {
+        let mut path = Path::new();
+        path.move_to(p(0.0, 0.0));
+        path.arc_to(p(10.0, 0.0), p2, radius);
+        assert_eq!(ends(&path), [p(0.0, 0.0), p(10.0, 0.0)]);
+    }
+
+    // Nothing is added without a current point.
+    let mut path = Path::new();
+    path.arc_to(p(10.0, 0.0), p(10.0, 10.0), 5.0);
+    assert!(path.is_empty());
+}
+
+#[test]
+fn add_arc_follows_oval() {
+    let oval = Rect::from_ltrb(0.0, 0.0, 20.0, 10.0);
+    let center = p(10.0, 5.0);
+    let radii = p(10.0, 5.0);
+
+    // Positive angles go clockwise from the right hand side.
+    let mut path = Path::new();
+    path.add_arc(oval, 0.0, FRAC_PI_2);
+    assert_on_ellipse(&path, center, radii);
+    let ends = ends(&path);
+    assert_near(ends[0], p(20.0, 5.0), 1e-4);
+    assert_near(*ends.last().unwrap(), p(10.0, 10.0), 1e-4);
+
+    let mut path = Path::new();
+    path.add_arc(oval, PI, -PI);
+    assert_on_ellipse(&path, center, radii);
+    assert_eq!(conics(&path).len(), 2);
+    assert_near(path.current_point().unwrap(), p(20.0, 5.0), 1e-4);
+
+    // Sweeps past a full turn are clamped.
+    let mut path = Path::new();
+    path.add_arc(oval, 0.0, 3.0 * TAU);
+    assert_on_ellipse(&path, center, radii);
+    assert_eq!(conics(&path).len(), 4);
+    assert_near(path.current_point().unwrap(), p(20.0, 5.0), 1e-4);
+}
+
+#[test]
+fn relative_commands_follow_current_point() {
+    let mut path = Path::new();
+    assert_eq!(path.current_point(), None);
+
+    // Relative to the origin of an empty path.
+    path.relative_move_to(p(1.0, 2.0));
+    assert_eq!(path.current_point(), Some(p(1.0, 2.0)));
+    path.relative_line_to(p(3.0, 0.0));
+    assert_eq!(path.current_point(), Some(p(4.0, 2.0)));
+    path.relative_quad_to(p(1.0, 0.0), p(1.0, 1.0));
+    assert_eq!(path.current_point(), Some(p(5.0, 3.0)));
+    path.relative_conic_to(p(0.0, 1.0), p(-1.0, 1.0), 0.5);
+    assert_eq!(path.current_point(), Some(p(4.0, 4.0)));
+    path.relative_cubic_to(p(0.0, 1.0), p(1.0, 2.0), p(2.0, 2.0));
+    assert_eq!(path.current_point(), Some(p(6.0, 6.0)));
+
+    let commands: Vec<_> = path.into_iter().collect();
+    assert!(matches!(commands[2], Command::QuadTo(c, _) if c == p(5.0, 2.0)));
+    assert!(
+        matches!(commands[3], Command::ConicTo(c, _, weight) if c == p(5.0, 4.0) && weight == 0.5)
+    );
+    assert!(
+        matches!(commands[4], Command::CubicTo(c1, c2, _) if c1 == p(4.0, 5.0) && c2 == p(5.0, 6.0))
+    );
+
+    // After a close, relative to the start of the sub-path.
+    path.close();
+    assert_eq!(path.current_point(), Some(p(1.0, 2.0)));
+    path.relative_line_to(p(0.0, 1.0));
+    assert_eq!(path.current_point(), Some(p(1.0, 3.0)));
+}
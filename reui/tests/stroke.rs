@@ -0,0 +1,141 @@
+//! Outlines of stroked paths.
+
+use std::f32::consts::PI;
+
+mod common;
+
+use common::{assert_near, contours, p, signed_area};
+use reui::{Command, LineCap, LineJoin, Offset, Path, PathOp, Rect, Stroke};
+
+fn outline(points: &[Offset], close: bool, stroke: Stroke) -> Path {
+    let mut path = Path::new();
+    path.polyline(points, close);
+    path.stroke_outline(&stroke, 0.01)
+}
+
+/// Area covered by the outline filled with the nonzero rule.
+fn covered_area(outline: &Path) -> f32 {
+    // The union merges overlapping polygons and keeps holes wound against solids.
+    let merged = Path::op(outline, &Path::new(), PathOp::Union);
+    let area: f32 = contours(&merged)
+        .iter()
+        .map(|contour| signed_area(&contour.points))
+        .sum();
+    area.abs()
+}
+
+fn bounds(outline: &Path) -> Rect {
+    let points: Vec<Offset> = contours(outline)
+        .into_iter()
+        .flat_map(|contour| contour.points)
+        .collect();
+    let min = points.iter().fold(points[0], |min, &p| min.min(p));
+    let max = points.iter().fold(points[0], |max, &p| max.max(p));
+    Rect::new(min, max)
+}
+
+#[test]
+fn stroked_segment() {
+    let segment = [p(0.0, 0.0), p(10.0, 0.0)];
+    let stroke = Stroke::width(2.0);
+
+    // A butt capped segment is a single rectangle.
+    let butt = outline(&segment, false, stroke.cap(LineCap::Butt));
+    let contours = contours(&butt);
+    assert_eq!(contours.len(), 1);
+    let mut corners = contours[0].points.clone();
+    corners.dedup();
+    assert_eq!(corners.len(), 4, "{corners:?}");
+    for corner in [p(0.0, -1.0), p(10.0, -1.0), p(10.0, 1.0), p(0.0, 1.0)] {
+        assert!(corners.contains(&corner), "{corners:?}");
+    }
+    assert_near(covered_area(&butt), 20.0, 1e-3);
+
+    // Square caps extend by half the width.
+    let square = outline(&segment, false, stroke.cap(LineCap::Square));
+    assert_eq!(bounds(&square), Rect::from_ltrb(-1.0, -1.0, 11.0, 1.0));
+    assert_near(covered_area(&square), 24.0, 1e-3);
+
+    // Round caps add a circle of the half width, flattened within the tolerance.
+    let round = outline(&segment, false, stroke.cap(LineCap::Round));
+    let rounded = bounds(&round);
+    assert_near(rounded.min.x, -1.0, 0.02);
+    assert_near(rounded.max.x, 11.0, 0.02);
+    assert_near(covered_area(&round), 20.0 + PI, 0.05);
+}
+
+#[test]
+fn mixed_caps() {
+    let segment = [p(0.0, 0.0), p(10.0, 0.0)];
+    let stroke = Stroke {
+        start: LineCap::Square,
+        end: LineCap::Butt,
+        ..Stroke::width(2.0)
+    };
+    let outline = outline(&segment, false, stroke);
+    assert_eq!(bounds(&outline), Rect::from_ltrb(-1.0, -1.0, 10.0, 1.0));
+    assert_near(covered_area(&outline), 22.0, 1e-3);
+}
+
+#[test]
+fn polyline_joins() {
+    // A right angle, each arm covers 20 and they overlap in 1.
+    let corner = [p(0.0, 0.0), p(10.0, 0.0), p(10.0, 10.0)];
+    let stroke = Stroke::width(2.0).cap(LineCap::Butt);
+
+    let cases = [
+        // The outer corner is filled up to the miter point.
+        ("miter", LineJoin::Miter, 40.0, 1e-3),
+        // Half of the outer corner square.
+        ("bevel", LineJoin::Bevel, 39.5, 1e-3),
+        // A quarter circle of the half width.
+        ("round", LineJoin::Round, 39.0 + PI / 4.0, 0.02),
+    ];
+    for (name, join, area, tolerance) in cases {
+        let outline = outline(&corner, false, stroke.joint(join));
+        assert_near(covered_area(&outline), area, tolerance);
+        assert_eq!(bounds(&outline).max.x, 11.0, "{name}");
+    }
+
+    // Only the miter join reaches the corner of the outline.
+    let reaches_corner = |join| {
+        contours(&outline(&corner, false, stroke.joint(join)))
+            .iter()
+            .any(|contour| contour.points.contains(&p(11.0, -1.0)))
+    };
+    assert!(reaches_corner(LineJoin::Miter));
+    assert!(!reaches_corner(LineJoin::Bevel));
+}
+
+#[test]
+fn miter_limit_falls_back_to_bevel() {
+    // A sharp turn whose miter is longer than the limit.
+    let sharp = [p(0.0, 0.0), p(10.0, 0.0), p(0.0, 2.0)];
+    let stroke = Stroke::width(2.0).joint(LineJoin::Miter);
+
+    let limited = outline(&sharp, false, stroke.miter_limit(2.0));
+    let unlimited = outline(&sharp, false, stroke.miter_limit(100.0));
+    assert!(bounds(&limited).max.x < 11.5, "{:?}", bounds(&limited));
+    assert!(bounds(&unlimited).max.x > 15.0, "{:?}", bounds(&unlimited));
+    assert!(covered_area(&limited) < covered_area(&unlimited));
+}
+
+#[test]
+fn closed_polyline_has_hole() {
+    let square = [p(0.0, 0.0), p(10.0, 0.0), p(10.0, 10.0), p(0.0, 10.0)];
+    let outline = outline(&square, true, Stroke::width(2.0));
+
+    assert_eq!(contours(&outline).len(), 2);
+    assert!(outline
+        .into_iter()
+        .any(|command| matches!(command, Command::Hole)));
+    assert_eq!(bounds(&outline), Rect::from_ltrb(-1.0, -1.0, 11.0, 11.0));
+    // Between squares of 12 and 8.
+    assert_near(covered_area(&outline), 80.0, 1e-3);
+}
+
+#[test]
+fn zero_width_is_empty() {
+    let segment = [p(0.0, 0.0), p(10.0, 0.0)];
+    assert!(outline(&segment, false, Stroke::width(0.0)).is_empty());
+}
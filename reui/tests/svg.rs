@@ -0,0 +1,143 @@
+//! Parsing of SVG path data.
+
+mod common;
+
+use common::{assert_near, p};
+use reui::{Command, Offset, ParseError, ParseErrorKind, Path};
+
+/// Commands as letters with their points.
+fn commands(data: &str) -> Vec<(char, Vec<Offset>)> {
+    let path = Path::from_svg_data(data).unwrap();
+    path.into_iter()
+        .map(|command| match command {
+            Command::MoveTo(p0) => ('M', vec![p0]),
+            Command::LineTo(p1) => ('L', vec![p1]),
+            Command::QuadTo(p1, p2) => ('Q', vec![p1, p2]),
+            Command::ConicTo(p1, p2, _) => ('K', vec![p1, p2]),
+            Command::CubicTo(p1, p2, p3) => ('C', vec![p1, p2, p3]),
+            Command::Solid | Command::Hole => ('S', vec![]),
+            Command::Close => ('Z', vec![]),
+        })
+        .collect()
+}
+
+fn letters(data: &str) -> String {
+    commands(data)
+        .into_iter()
+        .map(|(letter, _)| letter)
+        .collect()
+}
+
+fn points(data: &str) -> Vec<Offset> {
+    commands(data)
+        .into_iter()
+        .flat_map(|(_, points)| points)
+        .collect()
+}
+
+#[track_caller]
+fn assert_error(data: &str, position: usize, kind: ParseErrorKind) {
+    assert_eq!(
+        Path::from_svg_data(data).err(),
+        Some(ParseError { position, kind }),
+        "{data:?}"
+    );
+}
+
+#[test]
+fn rejects_bad_commands() {
+    assert_error("L 1 1", 0, ParseErrorKind::ExpectedMoveTo);
+    assert_error("  z", 2, ParseErrorKind::ExpectedMoveTo);
+    assert_error("M 0 0 X 1 1", 6, ParseErrorKind::UnexpectedCharacter('X'));
+    assert_error(
+        "M 0 0 L 1 1 é",
+        12,
+        ParseErrorKind::UnexpectedCharacter('é'),
+    );
+}
+
+#[test]
+fn rejects_missing_numbers() {
+    assert_error("M", 1, ParseErrorKind::ExpectedNumber);
+    assert_error("M 0", 3, ParseErrorKind::ExpectedNumber);
+    assert_error("M 0 0 L 1 -", 10, ParseErrorKind::ExpectedNumber);
+    assert_error("M 0 0 C 1 1 2 2", 15, ParseErrorKind::ExpectedNumber);
+    assert_error("M 0 0 H .", 8, ParseErrorKind::ExpectedNumber);
+    // A comma must be followed by a number.
+    assert_error("M 0 0,", 6, ParseErrorKind::ExpectedNumber);
+    assert_error("M 0 0 L 1,,1", 10, ParseErrorKind::ExpectedNumber);
+    // Values out of range are not finite.
+    assert_error("M 0 0 h 1e999", 8, ParseErrorKind::ExpectedNumber);
+}
+
+#[test]
+fn rejects_bad_arc_flags() {
+    assert_error("M 0 0 A 1 1 0 2 0 1 1", 14, ParseErrorKind::ExpectedFlag);
+    assert_error("M 0 0 A 1 1 0 0 x 1 1", 16, ParseErrorKind::ExpectedFlag);
+    assert_error("M 0 0 A 1 1 0 0", 15, ParseErrorKind::ExpectedFlag);
+}
+
+#[test]
+fn implicit_repeats() {
+    // Extra pairs after a moveto are linetos.
+    assert_eq!(letters("M 0 0 1 1 2 2"), "MLL");
+    assert_eq!(
+        points("M 0 0 1 1 2 2"),
+        [p(0.0, 0.0), p(1.0, 1.0), p(2.0, 2.0)]
+    );
+    assert_eq!(letters("M 0 0 L 1 1 2 2 3 3"), "MLLL");
+    assert_eq!(letters("M 0 0 Q 1 1 2 2 3 3 4 4"), "MQQ");
+    assert_eq!(
+        points("M 0 0 H 1 2 V 3 4"),
+        [
+            p(0.0, 0.0),
+            p(1.0, 0.0),
+            p(2.0, 0.0),
+            p(2.0, 3.0),
+            p(2.0, 4.0),
+        ]
+    );
+}
+
+#[test]
+fn relative_commands() {
+    assert_eq!(
+        points("m 1 1 l 2 0 h 1 v 3 l -1 -1"),
+        [
+            p(1.0, 1.0),
+            p(3.0, 1.0),
+            p(4.0, 1.0),
+            p(4.0, 4.0),
+            p(3.0, 3.0),
+        ]
+    );
+    // A relative moveto after a close is relative to the start of the sub-path.
+    assert_eq!(
+        points("M 1 1 L 5 1 Z m 1 1 l 1 0"),
+        [p(1.0, 1.0), p(5.0, 1.0), p(2.0, 2.0), p(3.0, 2.0)]
+    );
+    // Implicit repeats of a relative moveto are relative linetos.
+    assert_eq!(
+        points("m 1 1 1 1 1 1"),
+        [p(1.0, 1.0), p(2.0, 2.0), p(3.0, 3.0)]
+    );
+    assert_eq!(
+        points("M 1 1 c 1 0 2 1 2 2"),
+        [p(1.0, 1.0), p(2.0, 1.0), p(3.0, 2.0), p(3.0, 3.0)]
+    );
+}
+
+#[test]
+fn compact_numbers() {
+    assert_eq!(points("M1.5.5"), [p(1.5, 0.5)]);
+    assert_eq!(points("M1e2-3"), [p(100.0, -3.0)]);
+    assert_eq!(points("M-1-2L.5-.5"), [p(-1.0, -2.0), p(0.5, -0.5)]);
+    assert_eq!(points("M1E+1,2e-1"), [p(10.0, 0.2)]);
+    // An exponent without digits is not part of the number.
+    assert_error("M1 2e", 4, ParseErrorKind::UnexpectedCharacter('e'));
+    // Arc flags need no separator.
+    let arc = commands("M0 0A1 1 0 012 0");
+    assert!(arc.len() > 1);
+    assert!(arc[1..].iter().all(|(letter, _)| *letter == 'K'));
+    assert_near(arc.last().unwrap().1[1], p(2.0, 0.0), 1e-4);
+}